@@ -0,0 +1,353 @@
+//! Admin and operator command line of the tapio-sol program
+//!
+//! Every pool is selected by the jitoSOL mint it is keyed by. Instructions are signed by the
+//! keypair of the Solana CLI config unless `--dry-run` simulates them or `--unsigned` prints
+//! them for a multisig to sign.
+
+mod show;
+mod transaction;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use solana_cli_config::{Config, ConfigInput, CONFIG_FILE};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{clock::Clock, hash::Hash, pubkey::Pubkey, sysvar};
+use tapio_sol_client::{account, instruction, tapio_sol_math::MAX_FEE};
+use transaction::{Encoding, Mode, Nonce, Sender};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Parser)]
+#[command(version, about = "Admin and operator command line of tapio-sol")]
+struct Cli {
+    /// Solana CLI config file
+    #[arg(short = 'C', long, global = true)]
+    config: Option<String>,
+
+    /// RPC URL or moniker (mainnet-beta, testnet, devnet, localhost)
+    #[arg(short = 'u', long, global = true)]
+    url: Option<String>,
+
+    /// keypair signing and paying for the transaction
+    #[arg(short = 'k', long, global = true)]
+    keypair: Option<String>,
+
+    /// signer of the instructions with `--dry-run` or `--unsigned`, such as a multisig vault
+    #[arg(long, global = true)]
+    authority: Option<Pubkey>,
+
+    /// simulate the transaction without sending it
+    #[arg(long, global = true, conflicts_with = "unsigned")]
+    dry_run: bool,
+
+    /// print the unsigned transaction instead of sending it
+    #[arg(long, global = true)]
+    unsigned: bool,
+
+    /// recent blockhash of the unsigned transaction, or the blockhash stored in `--nonce`,
+    /// fetched when omitted
+    #[arg(long, global = true, requires = "unsigned")]
+    blockhash: Option<Hash>,
+
+    /// durable nonce account advanced by the unsigned transaction ahead of its instructions
+    #[arg(long, global = true, requires = "unsigned")]
+    nonce: Option<Pubkey>,
+
+    /// authority of the `--nonce` account, the signer when omitted
+    #[arg(long, global = true, requires = "nonce")]
+    nonce_authority: Option<Pubkey>,
+
+    /// encoding of the unsigned transaction
+    #[arg(long, global = true, value_enum, default_value_t = Encoding::Base64)]
+    encoding: Encoding,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Args)]
+struct PoolArg {
+    /// jitoSOL mint the pool is keyed by
+    #[arg(long)]
+    pool: Pubkey,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// print the state, balances, amplitude and rate of a pool
+    ShowPool(PoolArg),
+    /// create a pool of SOL and jitoSOL, fees over 1e10
+    InitializePool {
+        #[command(flatten)]
+        pool: PoolArg,
+        /// SPL stake pool of jitoSOL
+        #[arg(long)]
+        stake_pool: Pubkey,
+        #[arg(long)]
+        mint_fee: u64,
+        #[arg(long)]
+        swap_fee: u64,
+        #[arg(long)]
+        redeem_fee: u64,
+        /// amplification coefficient
+        #[arg(long)]
+        a: u64,
+    },
+    /// create the pool token mint and its metadata
+    InitializeToken {
+        #[command(flatten)]
+        pool: PoolArg,
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        symbol: String,
+        #[arg(long)]
+        uri: String,
+    },
+    /// ramp the amplification coefficient to `a`, over at least a day
+    RampA {
+        #[command(flatten)]
+        pool: PoolArg,
+        #[arg(long)]
+        a: u64,
+        /// unix timestamp the ramp ends at
+        #[arg(
+            long,
+            required_unless_present = "duration",
+            conflicts_with = "duration"
+        )]
+        end_time: Option<u64>,
+        /// seconds from the cluster clock the ramp lasts
+        #[arg(long)]
+        duration: Option<u64>,
+    },
+    /// freeze the amplification coefficient at its current value
+    StopRampA(PoolArg),
+    /// change the fees over 1e10, the omitted ones are kept
+    SetFees {
+        #[command(flatten)]
+        pool: PoolArg,
+        #[arg(long)]
+        mint_fee: Option<u64>,
+        #[arg(long)]
+        swap_fee: Option<u64>,
+        #[arg(long)]
+        redeem_fee: Option<u64>,
+    },
+    /// change the pool token account receiving the protocol share of the fees
+    SetFeeRecipient {
+        #[command(flatten)]
+        pool: PoolArg,
+        #[arg(long)]
+        recipient: Pubkey,
+        /// protocol share of every fee over 1e10, at most 5e9
+        #[arg(long)]
+        protocol_fee_share: u64,
+    },
+    /// pause operations, all of them when none is given
+    Pause {
+        #[command(flatten)]
+        pool: PoolArg,
+        #[arg(long = "operation", value_enum, value_delimiter = ',')]
+        operations: Vec<Operation>,
+    },
+    /// resume operations, all of them when none is given
+    Unpause {
+        #[command(flatten)]
+        pool: PoolArg,
+        #[arg(long = "operation", value_enum, value_delimiter = ',')]
+        operations: Vec<Operation>,
+    },
+    /// propose a new authority, which takes over once it accepts
+    ProposeAuthority {
+        #[command(flatten)]
+        pool: PoolArg,
+        #[arg(long)]
+        new_authority: Pubkey,
+    },
+    /// accept the authority proposed to the signer
+    AcceptAuthority(PoolArg),
+    /// withdraw the pending authority proposal
+    CancelAuthorityTransfer(PoolArg),
+}
+
+/// operations paused by `pause` and `unpause`
+#[derive(Copy, Clone, PartialEq, Eq, Debug, ValueEnum)]
+enum Operation {
+    Mint,
+    Swap,
+    RedeemProportion,
+    RedeemSingle,
+    RedeemMulti,
+}
+
+impl Operation {
+    fn bits(operations: &[Operation]) -> u8 {
+        use tapio_sol_client::tapio_sol::state;
+        if operations.is_empty() {
+            return state::PAUSE_ALL;
+        }
+        operations.iter().fold(0, |bits, operation| {
+            bits | match operation {
+                Operation::Mint => state::PAUSE_MINT,
+                Operation::Swap => state::PAUSE_SWAP,
+                Operation::RedeemProportion => state::PAUSE_REDEEM_PROPORTION,
+                Operation::RedeemSingle => state::PAUSE_REDEEM_SINGLE,
+                Operation::RedeemMulti => state::PAUSE_REDEEM_MULTI,
+            }
+        })
+    }
+}
+
+fn main() {
+    if let Err(err) = run(Cli::parse()) {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    // a missing default config falls back to the defaults, a given one must load
+    let config = match (&cli.config, CONFIG_FILE.as_ref()) {
+        (Some(path), _) => {
+            Config::load(path).map_err(|err| format!("reading config {path}: {err}"))?
+        }
+        (None, Some(path)) => Config::load(path).unwrap_or_default(),
+        (None, None) => Config::default(),
+    };
+    let (_, url) = ConfigInput::compute_json_rpc_url_setting(
+        cli.url.as_deref().unwrap_or(""),
+        &config.json_rpc_url,
+    );
+    let (_, keypair_path) = ConfigInput::compute_keypair_path_setting(
+        cli.keypair.as_deref().unwrap_or(""),
+        &config.keypair_path,
+    );
+    let (_, commitment) = ConfigInput::compute_commitment_config("", &config.commitment);
+    let rpc = RpcClient::new_with_commitment(url, commitment);
+    if let Command::ShowPool(PoolArg { pool }) = cli.command {
+        return show::show_pool(&rpc, &pool);
+    }
+
+    let mode = if cli.dry_run {
+        Mode::DryRun
+    } else if cli.unsigned {
+        Mode::Unsigned {
+            blockhash: cli.blockhash,
+            nonce: cli.nonce.map(|account| Nonce {
+                account,
+                authority: cli.nonce_authority,
+            }),
+            encoding: cli.encoding,
+        }
+    } else {
+        Mode::Send
+    };
+    let sender = Sender::new(&rpc, mode, &keypair_path, cli.authority)?;
+    let signer = sender.signer();
+
+    let instruction = match cli.command {
+        Command::ShowPool(_) => unreachable!("show-pool sends no transaction"),
+        Command::InitializePool {
+            pool: PoolArg { pool },
+            stake_pool,
+            mint_fee,
+            swap_fee,
+            redeem_fee,
+            a,
+        } => instruction::initialize_pool(
+            &signer,
+            &pool,
+            &stake_pool,
+            mint_fee,
+            swap_fee,
+            redeem_fee,
+            a,
+        ),
+        Command::InitializeToken {
+            pool: PoolArg { pool },
+            name,
+            symbol,
+            uri,
+        } => instruction::initialize_token(&signer, &pool, name, symbol, uri),
+        Command::RampA {
+            pool: PoolArg { pool },
+            a,
+            end_time,
+            duration,
+        } => {
+            let end_time = match (end_time, duration) {
+                (Some(end_time), _) => end_time,
+                (None, Some(duration)) => fetch_unix_timestamp(&rpc)?
+                    .checked_add(duration)
+                    .ok_or("ramp end time overflows")?,
+                (None, None) => unreachable!("clap requires --end-time or --duration"),
+            };
+            instruction::modify_a(&signer, &pool, a, end_time)
+        }
+        Command::StopRampA(PoolArg { pool }) => instruction::stop_ramp_a(&signer, &pool),
+        Command::SetFees {
+            pool: PoolArg { pool },
+            mint_fee,
+            swap_fee,
+            redeem_fee,
+        } => {
+            let (mint_fee, swap_fee, redeem_fee) = match (mint_fee, swap_fee, redeem_fee) {
+                (Some(mint_fee), Some(swap_fee), Some(redeem_fee)) => {
+                    (mint_fee, swap_fee, redeem_fee)
+                }
+                _ => {
+                    let state = account::fetch_pool_state(&rpc, &pool)?;
+                    (
+                        mint_fee.unwrap_or(state.mint_fee),
+                        swap_fee.unwrap_or(state.swap_fee),
+                        redeem_fee.unwrap_or(state.redeem_fee),
+                    )
+                }
+            };
+            if [mint_fee, swap_fee, redeem_fee]
+                .iter()
+                .any(|fee| *fee > MAX_FEE)
+            {
+                return Err(format!("fees are capped at {MAX_FEE}").into());
+            }
+            instruction::set_fees(&signer, &pool, mint_fee, swap_fee, redeem_fee)
+        }
+        Command::SetFeeRecipient {
+            pool: PoolArg { pool },
+            recipient,
+            protocol_fee_share,
+        } => instruction::set_fee_recipient(&signer, &pool, &recipient, protocol_fee_share),
+        Command::Pause {
+            pool: PoolArg { pool },
+            operations,
+        } => {
+            let state = account::fetch_pool_state(&rpc, &pool)?;
+            let paused = state.paused | Operation::bits(&operations);
+            instruction::set_paused(&signer, &pool, paused)
+        }
+        Command::Unpause {
+            pool: PoolArg { pool },
+            operations,
+        } => {
+            let state = account::fetch_pool_state(&rpc, &pool)?;
+            let paused = state.paused & !Operation::bits(&operations);
+            instruction::set_paused(&signer, &pool, paused)
+        }
+        Command::ProposeAuthority {
+            pool: PoolArg { pool },
+            new_authority,
+        } => instruction::propose_authority(&signer, &pool, &new_authority),
+        Command::AcceptAuthority(PoolArg { pool }) => instruction::accept_authority(&signer, &pool),
+        Command::CancelAuthorityTransfer(PoolArg { pool }) => {
+            instruction::cancel_authority_transfer(&signer, &pool)
+        }
+    };
+    sender.submit(&[instruction])
+}
+
+/// unix timestamp of the cluster clock
+fn fetch_unix_timestamp(rpc: &RpcClient) -> Result<u64> {
+    let clock: Clock =
+        bincode::deserialize(&account::fetch_account(rpc, &sysvar::clock::ID)?.data)?;
+    Ok(u64::try_from(clock.unix_timestamp)?)
+}
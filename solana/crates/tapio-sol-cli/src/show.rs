@@ -0,0 +1,203 @@
+//! `show-pool`, the recorded state of a pool next to its value at the current rates
+
+use crate::Result;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use tapio_sol_client::{
+    account, pda::PoolAddresses, quote::PoolSnapshot, tapio_sol::state, tapio_sol_math,
+    RateProvider, Role,
+};
+
+/// decimal amount of a value with 9 decimals, lamports or a virtual price
+fn format_units(amount: u64) -> String {
+    format!(
+        "{}.{:09}",
+        amount / LAMPORTS_PER_SOL,
+        amount % LAMPORTS_PER_SOL
+    )
+}
+
+/// percentage of a fee or share over `FEE_PRECISION`
+fn format_fee(fee: u64) -> String {
+    format!(
+        "{}%",
+        fee as f64 * 100.0 / tapio_sol_math::FEE_PRECISION as f64
+    )
+}
+
+fn format_key(key: &Pubkey) -> String {
+    if *key == Pubkey::default() {
+        "unset".to_string()
+    } else {
+        key.to_string()
+    }
+}
+
+fn format_paused(state: &state::PoolState) -> String {
+    if state.emergency {
+        return "all but redeem_proportion (emergency)".to_string();
+    }
+    let operations: Vec<&str> = [
+        (state::PAUSE_MINT, "mint"),
+        (state::PAUSE_SWAP, "swap"),
+        (state::PAUSE_REDEEM_PROPORTION, "redeem_proportion"),
+        (state::PAUSE_REDEEM_SINGLE, "redeem_single"),
+        (state::PAUSE_REDEEM_MULTI, "redeem_multi"),
+    ]
+    .into_iter()
+    .filter(|(bit, _)| state.paused & bit != 0)
+    .map(|(_, name)| name)
+    .collect();
+    if operations.is_empty() {
+        "none".to_string()
+    } else {
+        operations.join(", ")
+    }
+}
+
+pub fn show_pool(rpc: &RpcClient, pool: &Pubkey) -> Result<()> {
+    let state = account::fetch_pool_state(rpc, pool)?;
+    let addresses = PoolSnapshot::addresses(&state);
+    let accounts = rpc.get_multiple_accounts(&addresses)?;
+    let snapshot = PoolSnapshot::from_accounts(state.clone(), &accounts)?;
+    let pool_addresses = PoolAddresses::new(pool);
+    let current_time = u64::try_from(snapshot.clock.unix_timestamp)?;
+
+    println!("pool                {pool}");
+    println!("state               {}", pool_addresses.state);
+    println!("pool token          {}", state.pool_mint);
+    println!("version             {}", state.version);
+    println!("authority           {}", state.authority);
+    if let Some(pending_authority) = state.pending_authority {
+        println!("pending authority   {pending_authority}");
+    }
+    for (role, name) in [
+        (Role::FeeManager, "fee manager"),
+        (Role::CurveManager, "curve manager"),
+        (Role::Guardian, "guardian"),
+        (Role::Treasury, "treasury"),
+    ] {
+        println!("{name:<20}{}", format_key(&state.role(role)));
+    }
+
+    println!();
+    println!(
+        "fees                mint {}, swap {}, redeem {}",
+        format_fee(state.mint_fee),
+        format_fee(state.swap_fee),
+        format_fee(state.redeem_fee)
+    );
+    println!(
+        "fee recipient       {} ({} of fees)",
+        format_key(&state.fee_recipient),
+        format_fee(state.protocol_fee_share)
+    );
+    println!(
+        "yield recipient     {} ({} performance fee)",
+        format_key(&state.yield_recipient),
+        format_fee(state.performance_fee)
+    );
+    println!(
+        "pending yield       {} recorded, {} at the current rates",
+        format_units(state.pending_yield),
+        format_units(snapshot.state.pending_yield)
+    );
+    println!("paused              {}", format_paused(&state));
+    println!("loss mode           {}", snapshot.state.loss_mode);
+    println!("max rate staleness  {} epochs", state.max_rate_staleness);
+
+    println!();
+    let current_a = tapio_sol_math::interpolate_a(
+        state.a,
+        state.a_time,
+        state.future_a,
+        state.future_a_time,
+        current_time,
+    )
+    .ok_or("amplitude interpolation overflows")?;
+    println!("A                   {current_a}");
+    if current_time < state.future_a_time {
+        println!(
+            "A ramp              {} -> {} from {} to {}, {}s left",
+            state.a,
+            state.future_a,
+            state.a_time,
+            state.future_a_time,
+            state.future_a_time - current_time
+        );
+    } else {
+        println!(
+            "A ramp              none, last ended at {}",
+            state.future_a_time
+        );
+    }
+
+    println!();
+    println!("assets, SOL value recorded / at the current rate");
+    for (i, mint) in state.tokens.iter().enumerate() {
+        let rate = &snapshot.rates[i];
+        let name = if i == 0 {
+            "SOL".to_string()
+        } else {
+            mint.to_string()
+        };
+        println!(
+            "  {i} {name:<44} {} / {}",
+            format_units(state.balances[i]),
+            format_units(snapshot.state.balances[i])
+        );
+        if i == 0 {
+            continue;
+        }
+        let epoch = rate
+            .updated_epoch
+            .map_or("not recorded".to_string(), |epoch| epoch.to_string());
+        println!(
+            "    {:?} {}, rate {} / {} updated in epoch {epoch}",
+            state.rate_providers[i], state.stake_pools[i], rate.lamports, rate.supply
+        );
+        if let RateProvider::SplStakePool | RateProvider::SanctumSplStakePool =
+            state.rate_providers[i]
+        {
+            let data = accounts[2 + 2 * i].as_ref().ok_or("stake pool not found")?;
+            let stake_pool = account::decode_stake_pool(&data.data)?;
+            println!(
+                "    stake pool {} SOL for {} tokens, manager {}",
+                format_units(stake_pool.total_lamports),
+                format_units(stake_pool.pool_token_supply),
+                stake_pool.manager
+            );
+        }
+    }
+
+    println!();
+    if snapshot.invariant_solved {
+        println!(
+            "D                   {} recorded, {} at the current rates",
+            format_units(state.total_supply),
+            format_units(snapshot.state.total_supply)
+        );
+    } else {
+        println!(
+            "D                   {} recorded, does not converge at the current rates",
+            format_units(state.total_supply)
+        );
+    }
+    println!(
+        "pool token supply   {}",
+        format_units(snapshot.pool_token_supply)
+    );
+    if snapshot.invariant_solved {
+        println!(
+            "tapSOL rate         {} SOL, epoch {}",
+            format_units(snapshot.virtual_price()?),
+            snapshot.clock.epoch
+        );
+    } else {
+        println!(
+            "tapSOL rate         n/a, only redeem_proportion is open, epoch {}",
+            snapshot.clock.epoch
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,179 @@
+//! Sending, simulation and unsigned output of the built instructions
+
+use crate::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use clap::ValueEnum;
+use solana_client::{nonce_utils, rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig};
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    message::Message,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signer},
+    transaction::Transaction,
+};
+
+/// what becomes of the transaction
+pub enum Mode {
+    /// sign with the keypair and send
+    Send,
+    /// simulate without signatures and print the logs
+    DryRun,
+    /// print the unsigned transaction, paid for by the authority
+    Unsigned {
+        blockhash: Option<Hash>,
+        nonce: Option<Nonce>,
+        encoding: Encoding,
+    },
+}
+
+/// durable nonce of an unsigned transaction, advanced by its first instruction
+pub struct Nonce {
+    pub account: Pubkey,
+    /// authority of the nonce account, the signer when omitted
+    pub authority: Option<Pubkey>,
+}
+
+/// encoding of an unsigned transaction, the bincode wire format of `Transaction`
+#[derive(Copy, Clone, PartialEq, Eq, Debug, ValueEnum)]
+pub enum Encoding {
+    Base64,
+    Base58,
+}
+
+impl Encoding {
+    pub fn encode(&self, bytes: &[u8]) -> String {
+        match self {
+            Encoding::Base64 => STANDARD.encode(bytes),
+            Encoding::Base58 => bs58::encode(bytes).into_string(),
+        }
+    }
+}
+
+pub struct Sender<'a> {
+    rpc: &'a RpcClient,
+    mode: Mode,
+    keypair: Option<Keypair>,
+    signer: Pubkey,
+}
+
+impl<'a> Sender<'a> {
+    /// the keypair is only read when sending or when no authority is given
+    pub fn new(
+        rpc: &'a RpcClient,
+        mode: Mode,
+        keypair_path: &str,
+        authority: Option<Pubkey>,
+    ) -> Result<Self> {
+        let keypair = match (&mode, authority) {
+            (Mode::Send, _) | (_, None) => Some(
+                read_keypair_file(keypair_path)
+                    .map_err(|err| format!("reading keypair {keypair_path}: {err}"))?,
+            ),
+            _ => None,
+        };
+        let signer = match (authority, &keypair) {
+            (Some(authority), Some(keypair)) if authority != keypair.pubkey() => {
+                return Err(format!(
+                    "authority {authority} is not the keypair {}, use --dry-run or --unsigned",
+                    keypair.pubkey()
+                )
+                .into());
+            }
+            (Some(authority), _) => authority,
+            (None, Some(keypair)) => keypair.pubkey(),
+            (None, None) => unreachable!("the keypair is read without an authority"),
+        };
+        Ok(Sender {
+            rpc,
+            mode,
+            keypair,
+            signer,
+        })
+    }
+
+    /// signer of the instructions and fee payer of the transaction
+    pub fn signer(&self) -> Pubkey {
+        self.signer
+    }
+
+    pub fn submit(&self, instructions: &[Instruction]) -> Result<()> {
+        match &self.mode {
+            Mode::Send => {
+                let keypair = self.keypair.as_ref().expect("keypair read for sending");
+                let blockhash = self.rpc.get_latest_blockhash()?;
+                let transaction = Transaction::new_signed_with_payer(
+                    instructions,
+                    Some(&self.signer),
+                    &[keypair],
+                    blockhash,
+                );
+                let signature = self
+                    .rpc
+                    .send_and_confirm_transaction_with_spinner(&transaction)?;
+                println!("{signature}");
+            }
+            Mode::DryRun => {
+                let transaction =
+                    Transaction::new_unsigned(Message::new(instructions, Some(&self.signer)));
+                let result = self
+                    .rpc
+                    .simulate_transaction_with_config(
+                        &transaction,
+                        RpcSimulateTransactionConfig {
+                            sig_verify: false,
+                            replace_recent_blockhash: true,
+                            ..RpcSimulateTransactionConfig::default()
+                        },
+                    )?
+                    .value;
+                for log in result.logs.unwrap_or_default() {
+                    println!("{log}");
+                }
+                if let Some(units) = result.units_consumed {
+                    println!("compute units: {units}");
+                }
+                if let Some(err) = result.err {
+                    return Err(format!("simulation failed: {err}").into());
+                }
+                println!("simulation succeeded");
+            }
+            Mode::Unsigned {
+                blockhash,
+                nonce,
+                encoding,
+            } => {
+                let message = match nonce {
+                    Some(nonce) => {
+                        let blockhash = match blockhash {
+                            Some(blockhash) => *blockhash,
+                            None => nonce_utils::data_from_account(&nonce_utils::get_account(
+                                self.rpc,
+                                &nonce.account,
+                            )?)?
+                            .blockhash(),
+                        };
+                        let mut message = Message::new_with_nonce(
+                            instructions.to_vec(),
+                            Some(&self.signer),
+                            &nonce.account,
+                            &nonce.authority.unwrap_or(self.signer),
+                        );
+                        message.recent_blockhash = blockhash;
+                        message
+                    }
+                    None => {
+                        let blockhash = match blockhash {
+                            Some(blockhash) => *blockhash,
+                            None => self.rpc.get_latest_blockhash()?,
+                        };
+                        Message::new_with_blockhash(instructions, Some(&self.signer), &blockhash)
+                    }
+                };
+                let transaction = Transaction::new_unsigned(message);
+                println!("{}", encoding.encode(&bincode::serialize(&transaction)?));
+            }
+        }
+        Ok(())
+    }
+}
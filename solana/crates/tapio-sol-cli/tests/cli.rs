@@ -0,0 +1,200 @@
+//! Unsigned transactions printed by the command line against the client builders, offline
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_sdk::{
+    hash::Hash, instruction::Instruction, message::Message, pubkey::Pubkey,
+    transaction::Transaction,
+};
+use std::process::{Command, Output};
+use tapio_sol_client::instruction;
+
+const BLOCKHASH: &str = "4sGjMW1sUnHzSxGspuhpqLDx6wiyjNtZAMdL4VZHirAn";
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_tapio-sol-cli"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// run the command line with `--unsigned` and decode the transaction it prints
+fn unsigned(authority: &Pubkey, encoding: &str, args: &[&str]) -> Transaction {
+    let authority = authority.to_string();
+    let mut all_args = vec![
+        "--unsigned",
+        "--blockhash",
+        BLOCKHASH,
+        "--authority",
+        &authority,
+        "--encoding",
+        encoding,
+    ];
+    all_args.extend_from_slice(args);
+    let output = run(&all_args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let encoded = String::from_utf8(output.stdout).unwrap();
+    let bytes = match encoding {
+        "base58" => bs58::decode(encoded.trim()).into_vec().unwrap(),
+        _ => STANDARD.decode(encoded.trim()).unwrap(),
+    };
+    bincode::deserialize(&bytes).unwrap()
+}
+
+fn expected(authority: &Pubkey, instruction: Instruction) -> Transaction {
+    Transaction::new_unsigned(Message::new_with_blockhash(
+        &[instruction],
+        Some(authority),
+        &BLOCKHASH.parse::<Hash>().unwrap(),
+    ))
+}
+
+#[test]
+fn set_fees_prints_unsigned_transaction() {
+    let authority = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+    let transaction = unsigned(
+        &authority,
+        "base64",
+        &[
+            "set-fees",
+            "--pool",
+            &pool.to_string(),
+            "--mint-fee",
+            "0",
+            "--swap-fee",
+            "25000000",
+            "--redeem-fee",
+            "30000000",
+        ],
+    );
+    assert_eq!(
+        transaction,
+        expected(
+            &authority,
+            instruction::set_fees(&authority, &pool, 0, 25_000_000, 30_000_000)
+        )
+    );
+}
+
+#[test]
+fn ramp_a_and_authority_transfer_print_unsigned_transactions() {
+    let authority = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+    let transaction = unsigned(
+        &authority,
+        "base58",
+        &[
+            "ramp-a",
+            "--pool",
+            &pool.to_string(),
+            "--a",
+            "200",
+            "--end-time",
+            "1800000000",
+        ],
+    );
+    assert_eq!(
+        transaction,
+        expected(
+            &authority,
+            instruction::modify_a(&authority, &pool, 200, 1_800_000_000)
+        )
+    );
+
+    let new_authority = Pubkey::new_unique();
+    let transaction = unsigned(
+        &authority,
+        "base64",
+        &[
+            "propose-authority",
+            "--pool",
+            &pool.to_string(),
+            "--new-authority",
+            &new_authority.to_string(),
+        ],
+    );
+    assert_eq!(
+        transaction,
+        expected(
+            &authority,
+            instruction::propose_authority(&authority, &pool, &new_authority)
+        )
+    );
+}
+
+#[test]
+fn fees_above_max_fee_are_rejected() {
+    let pool = Pubkey::new_unique().to_string();
+    let output = run(&[
+        "--unsigned",
+        "--blockhash",
+        BLOCKHASH,
+        "--authority",
+        &Pubkey::new_unique().to_string(),
+        "set-fees",
+        "--pool",
+        &pool,
+        "--mint-fee",
+        "0",
+        "--swap-fee",
+        "600000000",
+        "--redeem-fee",
+        "0",
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("fees are capped"));
+}
+
+#[test]
+fn durable_nonce_is_advanced_first() {
+    let authority = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+    let nonce = Pubkey::new_unique();
+    let nonce_authority = Pubkey::new_unique();
+    let transaction = unsigned(
+        &authority,
+        "base64",
+        &[
+            "--nonce",
+            &nonce.to_string(),
+            "--nonce-authority",
+            &nonce_authority.to_string(),
+            "set-fees",
+            "--pool",
+            &pool.to_string(),
+            "--mint-fee",
+            "0",
+            "--swap-fee",
+            "25000000",
+            "--redeem-fee",
+            "30000000",
+        ],
+    );
+    let mut message = Message::new_with_nonce(
+        vec![instruction::set_fees(
+            &authority, &pool, 0, 25_000_000, 30_000_000,
+        )],
+        Some(&authority),
+        &nonce,
+        &nonce_authority,
+    );
+    message.recent_blockhash = BLOCKHASH.parse().unwrap();
+    assert_eq!(transaction, Transaction::new_unsigned(message));
+}
+
+#[test]
+fn unreadable_config_is_rejected() {
+    let output = run(&[
+        "--config",
+        "/nonexistent/config.yml",
+        "show-pool",
+        "--pool",
+        &Pubkey::new_unique().to_string(),
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("reading config"));
+}
@@ -0,0 +1,32 @@
+//! Decoding and fetching of the pool accounts
+
+use crate::{error::ClientError, Result};
+use anchor_lang::{prelude::Pubkey, AccountDeserialize};
+use solana_client::rpc_client::RpcClient;
+use tapio_sol::{stake::StakePool, state::PoolState};
+
+/// decode a pool state account, checking its discriminator
+pub fn decode_pool_state(data: &[u8]) -> Result<PoolState> {
+    Ok(PoolState::try_deserialize(&mut &data[..])?)
+}
+
+/// decode an SPL stake pool account
+pub fn decode_stake_pool(data: &[u8]) -> Result<StakePool> {
+    Ok(StakePool::try_deserialize(&mut &data[..])?)
+}
+
+/// data of the account at `address`
+pub fn fetch_account(rpc: &RpcClient, address: &Pubkey) -> Result<solana_sdk::account::Account> {
+    rpc.get_account_with_commitment(address, rpc.commitment())?
+        .value
+        .ok_or(ClientError::AccountNotFound(*address))
+}
+
+/// pool state of the pool keyed by the jitoSOL mint `pool`
+pub fn fetch_pool_state(rpc: &RpcClient, pool: &Pubkey) -> Result<PoolState> {
+    decode_pool_state(&fetch_account(rpc, &crate::pda::state_address(pool))?.data)
+}
+
+pub fn fetch_stake_pool(rpc: &RpcClient, address: &Pubkey) -> Result<StakePool> {
+    decode_stake_pool(&fetch_account(rpc, address)?.data)
+}
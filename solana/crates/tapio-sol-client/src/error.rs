@@ -0,0 +1,54 @@
+use anchor_lang::prelude::Pubkey;
+use std::fmt;
+use tapio_sol::errors::ErrorCode;
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// the RPC request failed
+    Rpc(Box<solana_client::client_error::ClientError>),
+    /// the account does not exist
+    AccountNotFound(Pubkey),
+    /// the account could not be decoded
+    Anchor(anchor_lang::error::Error),
+    /// the program would reject the operation with this error
+    Program(ErrorCode),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Rpc(error) => write!(f, "RPC error: {error}"),
+            ClientError::AccountNotFound(address) => write!(f, "Account {address} not found."),
+            ClientError::Anchor(error) => write!(f, "{error}"),
+            ClientError::Program(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<solana_client::client_error::ClientError> for ClientError {
+    fn from(error: solana_client::client_error::ClientError) -> Self {
+        ClientError::Rpc(Box::new(error))
+    }
+}
+
+impl From<anchor_lang::error::Error> for ClientError {
+    fn from(error: anchor_lang::error::Error) -> Self {
+        ClientError::Anchor(error)
+    }
+}
+
+impl From<ErrorCode> for ClientError {
+    fn from(error: ErrorCode) -> Self {
+        ClientError::Program(error)
+    }
+}
+
+impl From<tapio_sol_math::MathError> for ClientError {
+    fn from(error: tapio_sol_math::MathError) -> Self {
+        ClientError::Program(error.into())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
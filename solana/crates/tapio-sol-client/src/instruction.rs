@@ -0,0 +1,644 @@
+//! Builders for every instruction of the program
+//!
+//! Pool operations take the decoded `PoolState` to pass the fee recipient and the
+//! `ACCOUNTS_PER_ASSET` remaining accounts of every asset after jitoSOL. The rate snapshot
+//! is always passed, pools created before it must run `refresh_rate` once.
+
+use crate::pda::{self, PoolAddresses};
+use anchor_lang::{
+    prelude::{AccountMeta, Pubkey},
+    solana_program::{instruction::Instruction, sysvar},
+    system_program, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{
+    associated_token::{self, get_associated_token_address},
+    metadata::mpl_token_metadata,
+    token,
+};
+use tapio_sol::{accounts, instruction as data, rate::RateProvider, state::PoolState, state::Role};
+
+fn build(
+    accounts: impl ToAccountMetas,
+    data: impl InstructionData,
+    remaining_accounts: Vec<AccountMeta>,
+) -> Instruction {
+    let mut metas = accounts.to_account_metas(None);
+    metas.extend(remaining_accounts);
+    Instruction {
+        program_id: tapio_sol::ID,
+        accounts: metas,
+        data: data.data(),
+    }
+}
+
+/// the jitoSOL mint the pool is keyed by
+pub fn pool_key(state: &PoolState) -> Pubkey {
+    state.tokens[1]
+}
+
+/// remaining accounts of the assets after jitoSOL: the token account of `user`, the pool
+/// holder and the staking information account, quotes pass the holder for the user
+pub fn asset_accounts(state: &PoolState, user: Option<&Pubkey>) -> Vec<AccountMeta> {
+    let pool = pool_key(state);
+    let mut metas = Vec::new();
+    for (i, (mint, stake_pool)) in state
+        .tokens
+        .iter()
+        .zip(state.stake_pools.iter())
+        .enumerate()
+    {
+        if i < 2 {
+            continue;
+        }
+        let holder = pda::holder_address(&pool, i, mint);
+        match user {
+            Some(user) => {
+                metas.push(AccountMeta::new(
+                    get_associated_token_address(user, mint),
+                    false,
+                ));
+                metas.push(AccountMeta::new(holder, false));
+            }
+            None => {
+                metas.push(AccountMeta::new_readonly(holder, false));
+                metas.push(AccountMeta::new_readonly(holder, false));
+            }
+        }
+        metas.push(AccountMeta::new_readonly(*stake_pool, false));
+    }
+    metas
+}
+
+fn fee_recipient(state: &PoolState) -> Option<Pubkey> {
+    (state.fee_recipient != Pubkey::default()).then_some(state.fee_recipient)
+}
+
+fn modify_pool(signer: &Pubkey, pool: &Pubkey) -> accounts::ModifyPool {
+    accounts::ModifyPool {
+        payer: *signer,
+        jito_sol_mint_account: *pool,
+        state_account: pda::state_address(pool),
+        system_program: system_program::ID,
+    }
+}
+
+fn modify_a_accounts(signer: &Pubkey, pool: &Pubkey) -> accounts::ModifyA {
+    accounts::ModifyA {
+        payer: *signer,
+        jito_sol_mint_account: *pool,
+        state_account: pda::state_address(pool),
+        system_program: system_program::ID,
+        rent: sysvar::rent::ID,
+    }
+}
+
+fn mint_accounts(user: &Pubkey, state: &PoolState) -> accounts::MintShare {
+    let addresses = PoolAddresses::new(&pool_key(state));
+    accounts::MintShare {
+        payer: *user,
+        mint_account: addresses.mint,
+        jito_sol_mint_account: addresses.pool,
+        state_account: addresses.state,
+        mint_token_account: get_associated_token_address(user, &addresses.mint),
+        sol_user_account: *user,
+        jito_sol_user_token_account: get_associated_token_address(user, &addresses.pool),
+        sol_program_account: addresses.sol_holder,
+        jito_sol_program_token_account: addresses.jito_sol_holder,
+        stake_pool_account: state.stake_pools[1],
+        fee_recipient_account: fee_recipient(state),
+        rate_snapshot: addresses.rate_snapshot,
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    }
+}
+
+fn swap_accounts(user: &Pubkey, state: &PoolState) -> accounts::SwapToken {
+    let addresses = PoolAddresses::new(&pool_key(state));
+    accounts::SwapToken {
+        payer: *user,
+        mint_account: addresses.mint,
+        jito_sol_mint_account: addresses.pool,
+        state_account: addresses.state,
+        sol_user_account: *user,
+        jito_sol_user_token_account: get_associated_token_address(user, &addresses.pool),
+        sol_program_account: addresses.sol_holder,
+        jito_sol_program_token_account: addresses.jito_sol_holder,
+        stake_pool_account: state.stake_pools[1],
+        fee_recipient_account: fee_recipient(state),
+        rate_snapshot: addresses.rate_snapshot,
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    }
+}
+
+fn redeem_accounts(user: &Pubkey, state: &PoolState) -> accounts::RedeemShare {
+    let addresses = PoolAddresses::new(&pool_key(state));
+    accounts::RedeemShare {
+        payer: *user,
+        mint_account: addresses.mint,
+        jito_sol_mint_account: addresses.pool,
+        state_account: addresses.state,
+        mint_token_account: get_associated_token_address(user, &addresses.mint),
+        sol_user_account: *user,
+        jito_sol_user_token_account: get_associated_token_address(user, &addresses.pool),
+        sol_program_account: addresses.sol_holder,
+        jito_sol_program_token_account: addresses.jito_sol_holder,
+        stake_pool_account: state.stake_pools[1],
+        fee_recipient_account: fee_recipient(state),
+        rate_snapshot: addresses.rate_snapshot,
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    }
+}
+
+fn quote_accounts(state: &PoolState) -> accounts::Quote {
+    let addresses = PoolAddresses::new(&pool_key(state));
+    accounts::Quote {
+        mint_account: addresses.mint,
+        jito_sol_mint_account: addresses.pool,
+        state_account: addresses.state,
+        sol_program_account: addresses.sol_holder,
+        jito_sol_program_token_account: addresses.jito_sol_holder,
+        stake_pool_account: state.stake_pools[1],
+    }
+}
+
+/// create the pool of `pool`, the jitoSOL mint, priced by `stake_pool`
+pub fn initialize_pool(
+    payer: &Pubkey,
+    pool: &Pubkey,
+    stake_pool: &Pubkey,
+    mint_fee: u64,
+    swap_fee: u64,
+    redeem_fee: u64,
+    a: u64,
+) -> Instruction {
+    let addresses = PoolAddresses::new(pool);
+    build(
+        accounts::InitializePool {
+            payer: *payer,
+            jito_sol_mint_account: *pool,
+            state_account: addresses.state,
+            sol_program_account: addresses.sol_holder,
+            jito_sol_program_token_account: addresses.jito_sol_holder,
+            stake_pool_account: *stake_pool,
+            rate_snapshot: addresses.rate_snapshot,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        data::InitializePool {
+            mint_fee,
+            swap_fee,
+            redeem_fee,
+            a,
+        },
+        Vec::new(),
+    )
+}
+
+/// create the pool token mint with its metadata
+pub fn initialize_token(
+    payer: &Pubkey,
+    pool: &Pubkey,
+    token_name: String,
+    token_symbol: String,
+    token_uri: String,
+) -> Instruction {
+    let addresses = PoolAddresses::new(pool);
+    build(
+        accounts::InitializeToken {
+            payer: *payer,
+            jito_sol_mint_account: *pool,
+            mint_account: addresses.mint,
+            state_account: addresses.state,
+            metadata_account: pda::metadata_address(pool),
+            token_program: token::ID,
+            token_metadata_program: mpl_token_metadata::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        data::InitializeToken {
+            token_name: Box::new(token_name),
+            token_symbol: Box::new(token_symbol),
+            token_uri: Box::new(token_uri),
+        },
+        Vec::new(),
+    )
+}
+
+/// add a liquid staking token priced by `stake_pool` through `rate_provider`
+pub fn add_token(
+    payer: &Pubkey,
+    pool: &Pubkey,
+    token_mint: &Pubkey,
+    stake_pool: &Pubkey,
+    rate_provider: RateProvider,
+) -> Instruction {
+    build(
+        accounts::AddToken {
+            payer: *payer,
+            jito_sol_mint_account: *pool,
+            state_account: pda::state_address(pool),
+            token_mint_account: *token_mint,
+            token_program_account: pda::token_holder_address(pool, token_mint),
+            stake_pool_account: *stake_pool,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        data::AddToken { rate_provider },
+        Vec::new(),
+    )
+}
+
+pub fn set_fees(
+    signer: &Pubkey,
+    pool: &Pubkey,
+    mint_fee: u64,
+    swap_fee: u64,
+    redeem_fee: u64,
+) -> Instruction {
+    build(
+        modify_pool(signer, pool),
+        data::SetFees {
+            mint_fee,
+            swap_fee,
+            redeem_fee,
+        },
+        Vec::new(),
+    )
+}
+
+/// set the pool token account receiving `protocol_fee_share` of every fee, signed by the
+/// authority
+pub fn set_fee_recipient(
+    signer: &Pubkey,
+    pool: &Pubkey,
+    fee_recipient: &Pubkey,
+    protocol_fee_share: u64,
+) -> Instruction {
+    build(
+        accounts::SetFeeRecipient {
+            payer: *signer,
+            jito_sol_mint_account: *pool,
+            state_account: pda::state_address(pool),
+            fee_recipient_account: *fee_recipient,
+            system_program: system_program::ID,
+        },
+        data::SetFeeRecipient { protocol_fee_share },
+        Vec::new(),
+    )
+}
+
+/// set the pool token account receiving the yield, `performance_fee` of it goes to the
+/// treasury, signed by the authority
+pub fn set_yield_recipient(
+    signer: &Pubkey,
+    pool: &Pubkey,
+    yield_recipient: &Pubkey,
+    performance_fee: u64,
+) -> Instruction {
+    build(
+        accounts::SetYieldRecipient {
+            payer: *signer,
+            jito_sol_mint_account: *pool,
+            state_account: pda::state_address(pool),
+            yield_recipient_account: *yield_recipient,
+            system_program: system_program::ID,
+        },
+        data::SetYieldRecipient { performance_fee },
+        Vec::new(),
+    )
+}
+
+/// mint the accumulated yield to the yield recipient, and the performance fee to the
+/// pool token ATA of the treasury, or of the authority while no treasury is set
+pub fn distribute_yield(state: &PoolState) -> Instruction {
+    let addresses = PoolAddresses::new(&pool_key(state));
+    let treasury_token_account = (state.performance_fee > 0).then(|| {
+        get_associated_token_address(&state.role_or_authority(Role::Treasury), &addresses.mint)
+    });
+    build(
+        accounts::DistributeYield {
+            mint_account: addresses.mint,
+            jito_sol_mint_account: addresses.pool,
+            state_account: addresses.state,
+            sol_program_account: addresses.sol_holder,
+            jito_sol_program_token_account: addresses.jito_sol_holder,
+            stake_pool_account: state.stake_pools[1],
+            yield_recipient_account: state.yield_recipient,
+            treasury_token_account,
+            rate_snapshot: addresses.rate_snapshot,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        data::DistributeYield {},
+        asset_accounts(state, None),
+    )
+}
+
+/// burn the insurance buffer against a recorded loss, signed by the treasury
+pub fn cover_loss(signer: &Pubkey, pool: &Pubkey) -> Instruction {
+    let addresses = PoolAddresses::new(pool);
+    build(
+        accounts::CoverLoss {
+            payer: *signer,
+            mint_account: addresses.mint,
+            jito_sol_mint_account: addresses.pool,
+            state_account: addresses.state,
+            insurance_token_account: pda::insurance_address(pool),
+            rate_snapshot: addresses.rate_snapshot,
+            token_program: token::ID,
+        },
+        data::CoverLoss {},
+        Vec::new(),
+    )
+}
+
+/// move `amount` pool tokens from the insurance buffer to `destination`, signed by the treasury
+pub fn withdraw_insurance(
+    signer: &Pubkey,
+    state: &PoolState,
+    destination: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let pool = pool_key(state);
+    let addresses = PoolAddresses::new(&pool);
+    build(
+        accounts::WithdrawInsurance {
+            payer: *signer,
+            mint_account: addresses.mint,
+            jito_sol_mint_account: addresses.pool,
+            state_account: addresses.state,
+            sol_program_account: addresses.sol_holder,
+            jito_sol_program_token_account: addresses.jito_sol_holder,
+            stake_pool_account: state.stake_pools[1],
+            insurance_token_account: pda::insurance_address(&pool),
+            destination_token_account: *destination,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        data::WithdrawInsurance { amount },
+        asset_accounts(state, None),
+    )
+}
+
+pub fn set_rate_staleness(signer: &Pubkey, pool: &Pubkey, max_rate_staleness: u64) -> Instruction {
+    build(
+        modify_pool(signer, pool),
+        data::SetRateStaleness { max_rate_staleness },
+        Vec::new(),
+    )
+}
+
+/// change the allowed stake pool programs, passing the staking information account of
+/// every asset after SOL for the program to check their owners against the new list
+pub fn set_stake_pool_programs(
+    signer: &Pubkey,
+    state: &PoolState,
+    stake_pool_programs: Vec<Pubkey>,
+) -> Instruction {
+    build(
+        modify_pool(signer, &pool_key(state)),
+        data::SetStakePoolPrograms {
+            stake_pool_programs,
+        },
+        state.stake_pools[1..]
+            .iter()
+            .map(|stake_pool| AccountMeta::new_readonly(*stake_pool, false))
+            .collect(),
+    )
+}
+
+pub fn set_role(signer: &Pubkey, pool: &Pubkey, role: Role, key: &Pubkey) -> Instruction {
+    build(
+        modify_pool(signer, pool),
+        data::SetRole { role, key: *key },
+        Vec::new(),
+    )
+}
+
+/// pause the operations in the `PAUSE_MINT` and friends bits of `paused`
+pub fn set_paused(signer: &Pubkey, pool: &Pubkey, paused: u8) -> Instruction {
+    build(
+        modify_pool(signer, pool),
+        data::SetPaused { paused },
+        Vec::new(),
+    )
+}
+
+pub fn set_emergency(signer: &Pubkey, pool: &Pubkey, emergency: bool) -> Instruction {
+    build(
+        modify_pool(signer, pool),
+        data::SetEmergency { emergency },
+        Vec::new(),
+    )
+}
+
+pub fn propose_authority(signer: &Pubkey, pool: &Pubkey, new_authority: &Pubkey) -> Instruction {
+    build(
+        modify_pool(signer, pool),
+        data::ProposeAuthority {
+            new_authority: *new_authority,
+        },
+        Vec::new(),
+    )
+}
+
+/// accept the authority transfer, signed by the proposed authority
+pub fn accept_authority(signer: &Pubkey, pool: &Pubkey) -> Instruction {
+    build(
+        modify_pool(signer, pool),
+        data::AcceptAuthority {},
+        Vec::new(),
+    )
+}
+
+pub fn cancel_authority_transfer(signer: &Pubkey, pool: &Pubkey) -> Instruction {
+    build(
+        modify_pool(signer, pool),
+        data::CancelAuthorityTransfer {},
+        Vec::new(),
+    )
+}
+
+/// ramp the amplitude to `a` until the unix timestamp `future_a_time`
+pub fn modify_a(signer: &Pubkey, pool: &Pubkey, a: u64, future_a_time: u64) -> Instruction {
+    build(
+        modify_a_accounts(signer, pool),
+        data::ModifyA { a, future_a_time },
+        Vec::new(),
+    )
+}
+
+pub fn stop_ramp_a(signer: &Pubkey, pool: &Pubkey) -> Instruction {
+    build(
+        modify_a_accounts(signer, pool),
+        data::StopRampA {},
+        Vec::new(),
+    )
+}
+
+pub fn migrate_pool(signer: &Pubkey, pool: &Pubkey) -> Instruction {
+    build(
+        accounts::MigratePool {
+            payer: *signer,
+            jito_sol_mint_account: *pool,
+            state_account: pda::state_address(pool),
+            system_program: system_program::ID,
+        },
+        data::MigratePool {},
+        Vec::new(),
+    )
+}
+
+/// deposit `amounts` of every asset from the accounts of `user`
+pub fn mint(
+    user: &Pubkey,
+    state: &PoolState,
+    amounts: Vec<u64>,
+    min_mint_amount: u64,
+) -> Instruction {
+    build(
+        mint_accounts(user, state),
+        data::Mint {
+            amounts,
+            min_mint_amount,
+        },
+        asset_accounts(state, Some(user)),
+    )
+}
+
+/// swap `dx` of asset `i` to at least `min_dy` of asset `j`
+pub fn swap(user: &Pubkey, state: &PoolState, i: u16, j: u16, dx: u64, min_dy: u64) -> Instruction {
+    build(
+        swap_accounts(user, state),
+        data::Swap { i, j, dx, min_dy },
+        asset_accounts(state, Some(user)),
+    )
+}
+
+/// swap at most `max_dx` of asset `i` to exactly `dy` of asset `j`
+pub fn swap_exact_out(
+    user: &Pubkey,
+    state: &PoolState,
+    i: u16,
+    j: u16,
+    dy: u64,
+    max_dx: u64,
+) -> Instruction {
+    build(
+        swap_accounts(user, state),
+        data::SwapExactOut { i, j, dy, max_dx },
+        asset_accounts(state, Some(user)),
+    )
+}
+
+pub fn redeem_proportion(
+    user: &Pubkey,
+    state: &PoolState,
+    amount: u64,
+    min_redeem_amounts: Vec<u64>,
+) -> Instruction {
+    build(
+        redeem_accounts(user, state),
+        data::RedeemProportion {
+            amount,
+            min_redeem_amounts,
+        },
+        asset_accounts(state, Some(user)),
+    )
+}
+
+pub fn redeem_single(
+    user: &Pubkey,
+    state: &PoolState,
+    amount: u64,
+    i: u16,
+    min_redeem_amount: u64,
+) -> Instruction {
+    build(
+        redeem_accounts(user, state),
+        data::RedeemSingle {
+            amount,
+            i,
+            min_redeem_amount,
+        },
+        asset_accounts(state, Some(user)),
+    )
+}
+
+pub fn redeem_multi(
+    user: &Pubkey,
+    state: &PoolState,
+    amounts: Vec<u64>,
+    max_redeem_amount: u64,
+) -> Instruction {
+    build(
+        redeem_accounts(user, state),
+        data::RedeemMulti {
+            amounts,
+            max_redeem_amount,
+        },
+        asset_accounts(state, Some(user)),
+    )
+}
+
+/// on-chain quote, the `MintResult` is returned as return data of a simulation
+pub fn quote_mint(state: &PoolState, amounts: Vec<u64>) -> Instruction {
+    build(
+        quote_accounts(state),
+        data::QuoteMint { amounts },
+        asset_accounts(state, None),
+    )
+}
+
+pub fn quote_swap(state: &PoolState, i: u16, j: u16, dx: u64) -> Instruction {
+    build(
+        quote_accounts(state),
+        data::QuoteSwap { i, j, dx },
+        asset_accounts(state, None),
+    )
+}
+
+pub fn quote_redeem_proportion(state: &PoolState, amount: u64) -> Instruction {
+    build(
+        quote_accounts(state),
+        data::QuoteRedeemProportion { amount },
+        asset_accounts(state, None),
+    )
+}
+
+pub fn quote_redeem_single(state: &PoolState, amount: u64, i: u16) -> Instruction {
+    build(
+        quote_accounts(state),
+        data::QuoteRedeemSingle { amount, i },
+        asset_accounts(state, None),
+    )
+}
+
+pub fn get_virtual_price(state: &PoolState) -> Instruction {
+    build(
+        quote_accounts(state),
+        data::GetVirtualPrice {},
+        asset_accounts(state, None),
+    )
+}
+
+/// refresh the rate snapshot, creating it on the first call
+pub fn refresh_rate(payer: &Pubkey, state: &PoolState) -> Instruction {
+    build(
+        accounts::RefreshRate {
+            payer: *payer,
+            quote: quote_accounts(state),
+            rate_snapshot: pda::rate_snapshot_address(&pool_key(state)),
+            system_program: system_program::ID,
+        },
+        data::RefreshRate {},
+        asset_accounts(state, None),
+    )
+}
@@ -0,0 +1,21 @@
+//! Rust client of the tapio-sol program for bots and backend services
+//!
+//! - [`pda`]: addresses of a pool, derived from the jitoSOL mint it is keyed by
+//! - [`instruction`]: builders for every instruction of the program
+//! - [`account`]: decoding and fetching of `PoolState` and `StakePool`
+//! - [`quote`]: off-chain quotes through the same math as the program
+
+pub mod account;
+pub mod error;
+pub mod instruction;
+pub mod pda;
+pub mod quote;
+
+pub use error::{ClientError, Result};
+pub use tapio_sol::{
+    rate::{ExchangeRate, RateProvider},
+    stake::StakePool,
+    state::{PoolState, RateSnapshot, Role},
+    ID,
+};
+pub use {tapio_sol, tapio_sol_math};
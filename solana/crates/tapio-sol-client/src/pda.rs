@@ -0,0 +1,88 @@
+//! Addresses of a pool, every PDA is derived from the jitoSOL mint the pool is keyed by
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::{associated_token::get_associated_token_address, metadata::mpl_token_metadata};
+use tapio_sol::{
+    state::{PoolState, RateSnapshot},
+    HOLDER_SEED, ID, MINT_SEED, SOL,
+};
+
+/// pool state account
+pub fn state_address(pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[PoolState::SEED, pool.as_ref()], &ID).0
+}
+
+/// pool token mint
+pub fn mint_address(pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[MINT_SEED, pool.as_ref()], &ID).0
+}
+
+/// holder of the pool SOL
+pub fn sol_holder_address(pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[HOLDER_SEED, SOL, pool.as_ref()], &ID).0
+}
+
+/// holder of the pool jitoSOL
+pub fn jito_sol_holder_address(pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[HOLDER_SEED, pool.as_ref()], &ID).0
+}
+
+/// holder of a liquid staking token added after jitoSOL
+pub fn token_holder_address(pool: &Pubkey, token_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[HOLDER_SEED, pool.as_ref(), token_mint.as_ref()], &ID).0
+}
+
+/// holder of the pool asset at `index`, following `tapio_sol::holder_seeds`
+pub fn holder_address(pool: &Pubkey, index: usize, token_mint: &Pubkey) -> Pubkey {
+    match index {
+        0 => sol_holder_address(pool),
+        1 => jito_sol_holder_address(pool),
+        _ => token_holder_address(pool, token_mint),
+    }
+}
+
+/// rate snapshot account
+pub fn rate_snapshot_address(pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[RateSnapshot::SEED, pool.as_ref()], &ID).0
+}
+
+/// token metadata account of the pool token mint
+pub fn metadata_address(pool: &Pubkey) -> Pubkey {
+    let mint = mint_address(pool);
+    Pubkey::find_program_address(
+        &[b"metadata", mpl_token_metadata::ID.as_ref(), mint.as_ref()],
+        &mpl_token_metadata::ID,
+    )
+    .0
+}
+
+/// insurance buffer, the pool token account of the pool state burned by `cover_loss` and
+/// withdrawn by the treasury
+pub fn insurance_address(pool: &Pubkey) -> Pubkey {
+    get_associated_token_address(&state_address(pool), &mint_address(pool))
+}
+
+/// the fixed addresses of a pool
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct PoolAddresses {
+    /// the jitoSOL mint the pool is keyed by
+    pub pool: Pubkey,
+    pub state: Pubkey,
+    pub mint: Pubkey,
+    pub sol_holder: Pubkey,
+    pub jito_sol_holder: Pubkey,
+    pub rate_snapshot: Pubkey,
+}
+
+impl PoolAddresses {
+    pub fn new(pool: &Pubkey) -> Self {
+        PoolAddresses {
+            pool: *pool,
+            state: state_address(pool),
+            mint: mint_address(pool),
+            sol_holder: sol_holder_address(pool),
+            jito_sol_holder: jito_sol_holder_address(pool),
+            rate_snapshot: rate_snapshot_address(pool),
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Off-chain quotes through `tapio-sol-math`, matching the quote instructions of the program
+//!
+//! Amounts are in the units of the instruction arguments. The pool balances are read from
+//! the holders and converted to SOL with the current exchange rates, as every
+//! state-changing instruction does before it runs.
+
+use crate::{account, error::ClientError, pda::PoolAddresses, Result};
+use anchor_lang::{
+    prelude::{AccountInfo, Clock, Pubkey},
+    solana_program::sysvar,
+    AccountDeserialize,
+};
+use anchor_spl::token::{Mint, TokenAccount};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::account::Account;
+use tapio_sol::{errors::ErrorCode, rate::ExchangeRate, state, state::PoolState};
+use tapio_sol_math::{
+    MintResult, RedeemMultiResult, RedeemProportionResult, RedeemSingleResult, SwapResult,
+};
+
+/// a pool as the next instruction would see it
+#[derive(Clone)]
+pub struct PoolSnapshot {
+    /// pool state with the balances, D and loss mode of the current holders and rates
+    pub state: PoolState,
+    pub pool_token_supply: u64,
+    /// exchange rate of every asset, native SOL first
+    pub rates: Vec<ExchangeRate>,
+    pub clock: Clock,
+    /// whether D was solved from the current balances, when it does not converge the
+    /// recorded D is kept and only `quote_redeem_proportion` runs, like the program
+    pub invariant_solved: bool,
+}
+
+impl PoolSnapshot {
+    /// accounts read by `from_accounts`: the pool token mint, the clock, the SOL holder,
+    /// then the holder and the staking information account of every other asset
+    pub fn addresses(state: &PoolState) -> Vec<Pubkey> {
+        let addresses = PoolAddresses::new(&state.tokens[1]);
+        let mut result = vec![addresses.mint, sysvar::clock::ID, addresses.sol_holder];
+        for (i, mint) in state.tokens.iter().enumerate().skip(1) {
+            result.push(crate::pda::holder_address(&addresses.pool, i, mint));
+            result.push(state.stake_pools[i]);
+        }
+        result
+    }
+
+    /// snapshot of `state` from the accounts at `PoolSnapshot::addresses`, in order
+    pub fn from_accounts(mut state: PoolState, accounts: &[Option<Account>]) -> Result<Self> {
+        let addresses = PoolSnapshot::addresses(&state);
+        if accounts.len() != addresses.len() {
+            return Err(ErrorCode::ArgumentValidationFailure.into());
+        }
+        let account = |index: usize| {
+            accounts[index]
+                .as_ref()
+                .ok_or(ClientError::AccountNotFound(addresses[index]))
+        };
+        let pool_token_supply = Mint::try_deserialize(&mut &account(0)?.data[..])?.supply;
+        let clock: Clock = bincode::deserialize(&account(1)?.data)
+            .map_err(|_| ClientError::Program(ErrorCode::ArgumentValidationFailure))?;
+
+        let sol_balance = account(2)?
+            .lamports
+            .checked_sub(tapio_sol::INIT_SOL)
+            .ok_or(ErrorCode::ArithmeticError)?;
+        let mut amounts = vec![sol_balance];
+        let mut balances = vec![sol_balance];
+        let mut rates = vec![ExchangeRate::NATIVE];
+        for i in 1..state.tokens.len() {
+            let holder = account(1 + 2 * i)?;
+            let rate = read_rate(&state, i, &addresses[2 + 2 * i], account(2 + 2 * i)?)?;
+            let amount = TokenAccount::try_deserialize(&mut &holder.data[..])?.amount;
+            balances.push(tapio_sol::convert_balance(amount, &rate)?);
+            amounts.push(amount);
+            rates.push(rate);
+        }
+
+        let current_time = u64::try_from(clock.unix_timestamp)
+            .map_err(|_| ClientError::Program(ErrorCode::ArithmeticError))?;
+        let a = tapio_sol_math::interpolate_a(
+            state.a,
+            state.a_time,
+            state.future_a,
+            state.future_a_time,
+            current_time,
+        )
+        .ok_or(ErrorCode::ArithmeticError)?;
+        let (total_supply, invariant_solved) = match tapio_sol_math::get_d(&balances, a) {
+            Ok((total_supply, _)) => (total_supply, true),
+            Err(tapio_sol_math::MathError::InvariantDidNotConverge) => (state.total_supply, false),
+            Err(error) => return Err(error.into()),
+        };
+        state.record_rates(&amounts, rates.clone(), total_supply, a)?;
+        state.balances = balances;
+        state.total_supply = total_supply;
+        state.loss_mode = total_supply < pool_token_supply;
+        Ok(PoolSnapshot {
+            state,
+            pool_token_supply,
+            rates,
+            clock,
+            invariant_solved,
+        })
+    }
+
+    /// fetch the pool keyed by the jitoSOL mint `pool`
+    pub fn fetch(rpc: &RpcClient, pool: &Pubkey) -> Result<Self> {
+        let state = account::fetch_pool_state(rpc, pool)?;
+        let accounts = rpc.get_multiple_accounts(&PoolSnapshot::addresses(&state))?;
+        PoolSnapshot::from_accounts(state, &accounts)
+    }
+
+    /// the math inputs at the snapshot clock
+    pub fn math_pool(&self) -> Result<tapio_sol_math::Pool> {
+        let current_time = u64::try_from(self.clock.unix_timestamp)
+            .map_err(|_| ClientError::Program(ErrorCode::ArithmeticError))?;
+        Ok(tapio_sol::pool::math_pool_at(
+            &self.state,
+            self.pool_token_supply,
+            current_time,
+        ))
+    }
+
+    fn check_open(&self, operation: u8) -> Result<()> {
+        if self.state.is_paused(operation) {
+            return Err(ErrorCode::OperationPaused.into());
+        }
+        if operation != state::PAUSE_REDEEM_PROPORTION && !self.invariant_solved {
+            return Err(ErrorCode::InvariantDidNotConverge.into());
+        }
+        if operation != state::PAUSE_REDEEM_PROPORTION && self.state.loss_mode {
+            return Err(ErrorCode::PoolImbalanced.into());
+        }
+        Ok(())
+    }
+
+    fn check_rate_fresh(&self, index: usize) -> Result<()> {
+        let rate = self
+            .rates
+            .get(index)
+            .ok_or(ErrorCode::ArgumentValidationFailure)?;
+        if rate.is_stale(self.clock.epoch, self.state.max_rate_staleness) {
+            return Err(ErrorCode::StaleExchangeRate.into());
+        }
+        Ok(())
+    }
+
+    pub fn quote_mint(&self, amounts: &[u64]) -> Result<MintResult> {
+        self.check_open(state::PAUSE_MINT)?;
+        for i in 0..self.rates.len() {
+            self.check_rate_fresh(i)?;
+        }
+        Ok(tapio_sol_math::get_mint_amount(
+            &self.math_pool()?,
+            amounts,
+        )?)
+    }
+
+    pub fn quote_swap(&self, i: u16, j: u16, dx: u64) -> Result<SwapResult> {
+        self.check_open(state::PAUSE_SWAP)?;
+        self.check_rate_fresh(usize::from(j))?;
+        Ok(tapio_sol_math::get_swap_amount(
+            &self.math_pool()?,
+            usize::from(i),
+            usize::from(j),
+            dx,
+        )?)
+    }
+
+    pub fn quote_swap_exact_out(&self, i: u16, j: u16, dy: u64) -> Result<SwapResult> {
+        self.check_open(state::PAUSE_SWAP)?;
+        self.check_rate_fresh(usize::from(j))?;
+        Ok(tapio_sol_math::get_swap_amount_exact_out(
+            &self.math_pool()?,
+            usize::from(i),
+            usize::from(j),
+            dy,
+        )?)
+    }
+
+    pub fn quote_redeem_proportion(&self, amount: u64) -> Result<RedeemProportionResult> {
+        self.check_open(state::PAUSE_REDEEM_PROPORTION)?;
+        Ok(tapio_sol_math::get_redeem_proportion_amount(
+            &self.math_pool()?,
+            amount,
+        )?)
+    }
+
+    pub fn quote_redeem_single(&self, amount: u64, i: u16) -> Result<RedeemSingleResult> {
+        self.check_open(state::PAUSE_REDEEM_SINGLE)?;
+        self.check_rate_fresh(usize::from(i))?;
+        Ok(tapio_sol_math::get_redeem_single_amount(
+            &self.math_pool()?,
+            amount,
+            usize::from(i),
+        )?)
+    }
+
+    pub fn quote_redeem_multi(&self, amounts: &[u64]) -> Result<RedeemMultiResult> {
+        self.check_open(state::PAUSE_REDEEM_MULTI)?;
+        for (i, amount) in amounts.iter().enumerate() {
+            if *amount > 0 {
+                self.check_rate_fresh(i)?;
+            }
+        }
+        Ok(tapio_sol_math::get_redeem_multi_amount(
+            &self.math_pool()?,
+            amounts,
+        )?)
+    }
+
+    /// SOL value of one pool token scaled by `VIRTUAL_PRICE_PRECISION`
+    pub fn virtual_price(&self) -> Result<u64> {
+        if !self.invariant_solved {
+            return Err(ErrorCode::InvariantDidNotConverge.into());
+        }
+        Ok(tapio_sol_math::get_virtual_price(&self.math_pool()?)?)
+    }
+}
+
+/// exchange rate of the asset at `index` through the rate provider of the pool
+pub fn read_rate(
+    state: &PoolState,
+    index: usize,
+    address: &Pubkey,
+    account: &Account,
+) -> Result<ExchangeRate> {
+    let mut lamports = account.lamports;
+    let mut data = account.data.clone();
+    let info = AccountInfo::new(
+        address,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &account.owner,
+        account.executable,
+        account.rent_epoch,
+    );
+    Ok(state.rate_providers[index].read(&info, &state.stake_pool_programs)?)
+}
@@ -0,0 +1,236 @@
+//! Instructions built by the client against tapio-sol running natively in a
+//! `solana-program-test` bank, with the offline quotes checked against the results.
+
+use anchor_lang::{
+    prelude::{AccountInfo, Pubkey},
+    solana_program::{entrypoint::ProgramResult, instruction::Instruction, system_instruction},
+    system_program, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{
+    associated_token::{
+        get_associated_token_address,
+        spl_associated_token_account::instruction::create_associated_token_account,
+    },
+    metadata::mpl_token_metadata,
+    token::{self, spl_token},
+};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    program_pack::Pack,
+    signature::{Keypair, Signer},
+    sysvar,
+    transaction::Transaction,
+};
+use tapio_sol_client::{account, instruction, pda, quote::PoolSnapshot, PoolState};
+
+fn tapio_sol_entry(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    tapio_sol::entry(program_id, accounts, data)
+}
+
+fn stake_pool_test_entry(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    stake_pool_test::entry(program_id, accounts, data)
+}
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) {
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+async fn pool_state(context: &mut ProgramTestContext, pool: &Pubkey) -> PoolState {
+    let account = context
+        .banks_client
+        .get_account(pda::state_address(pool))
+        .await
+        .unwrap()
+        .unwrap();
+    account::decode_pool_state(&account.data).unwrap()
+}
+
+async fn snapshot(context: &mut ProgramTestContext, pool: &Pubkey) -> PoolSnapshot {
+    let state = pool_state(context, pool).await;
+    let mut accounts = Vec::new();
+    for address in PoolSnapshot::addresses(&state) {
+        accounts.push(context.banks_client.get_account(address).await.unwrap());
+    }
+    PoolSnapshot::from_accounts(state, &accounts).unwrap()
+}
+
+/// a pool of jitoSOL priced at 1.1 SOL with 0.1 SOL and 0.1 SOL worth of jitoSOL minted
+async fn setup() -> (ProgramTestContext, Pubkey) {
+    let mut program_test =
+        ProgramTest::new("tapio_sol", tapio_sol::ID, processor!(tapio_sol_entry));
+    program_test.add_program(
+        "stake_pool_test",
+        stake_pool_test::ID,
+        processor!(stake_pool_test_entry),
+    );
+    // the token metadata program runs from its mainnet build in `tests/fixtures`
+    program_test.prefer_bpf(true);
+    program_test.add_program("mpl_token_metadata", mpl_token_metadata::ID, None);
+    let mut context = program_test.start_with_context().await;
+
+    let jito_sol = Keypair::new();
+    let pool = jito_sol.pubkey();
+    let payer = context.payer.pubkey();
+    let stake_pool =
+        Pubkey::find_program_address(&[b"test", pool.as_ref()], &stake_pool_test::ID).0;
+    let rent = context.banks_client.get_rent().await.unwrap();
+    process(
+        &mut context,
+        &[
+            system_instruction::create_account(
+                &payer,
+                &pool,
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &token::ID,
+            ),
+            spl_token::instruction::initialize_mint(&token::ID, &pool, &payer, None, 9).unwrap(),
+            create_associated_token_account(&payer, &payer, &pool, &token::ID),
+            spl_token::instruction::mint_to(
+                &token::ID,
+                &pool,
+                &get_associated_token_address(&payer, &pool),
+                &payer,
+                &[],
+                1_000_000_000,
+            )
+            .unwrap(),
+            Instruction {
+                program_id: stake_pool_test::ID,
+                accounts: stake_pool_test::accounts::Initialize {
+                    payer,
+                    pool_mint: pool,
+                    stake_pool_account: stake_pool,
+                    stake_pool_program: stake_pool_test::ID,
+                    system_program: system_program::ID,
+                    rent: sysvar::rent::ID,
+                }
+                .to_account_metas(None),
+                data: stake_pool_test::instruction::Initialize {
+                    total_lamports: 11,
+                    pool_token_supply: 10,
+                }
+                .data(),
+            },
+        ],
+        &[&jito_sol],
+    )
+    .await;
+
+    process(
+        &mut context,
+        &[
+            instruction::initialize_pool(
+                &payer,
+                &pool,
+                &stake_pool,
+                0,
+                25_000_000,
+                30_000_000,
+                1000,
+            ),
+            instruction::initialize_token(
+                &payer,
+                &pool,
+                "Tapio Sol".to_string(),
+                "tapSOL".to_string(),
+                "https://example.com".to_string(),
+            ),
+        ],
+        &[],
+    )
+    .await;
+    let state = pool_state(&mut context, &pool).await;
+    process(
+        &mut context,
+        &[instruction::mint(
+            &payer,
+            &state,
+            vec![100_000_000, 100_000_000],
+            0,
+        )],
+        &[],
+    )
+    .await;
+    (context, pool)
+}
+
+#[tokio::test]
+async fn addresses_match_pool_state() {
+    let (mut context, pool) = setup().await;
+    let state = pool_state(&mut context, &pool).await;
+
+    assert_eq!(state.pool_mint, pda::mint_address(&pool));
+    assert_eq!(instruction::pool_key(&state), pool);
+    assert_eq!(state.total_supply, 200_000_000);
+    assert!(context
+        .banks_client
+        .get_account(pda::rate_snapshot_address(&pool))
+        .await
+        .unwrap()
+        .is_some());
+}
+
+#[tokio::test]
+async fn quote_swap_matches_swap() {
+    let (mut context, pool) = setup().await;
+    let quote = snapshot(&mut context, &pool)
+        .await
+        .quote_swap(0, 1, 1_000_000)
+        .unwrap();
+
+    let payer = context.payer.pubkey();
+    let state = pool_state(&mut context, &pool).await;
+    process(
+        &mut context,
+        &[instruction::swap(&payer, &state, 0, 1, 1_000_000, 0)],
+        &[],
+    )
+    .await;
+    let state = pool_state(&mut context, &pool).await;
+    assert_eq!(state.balances, vec![quote.balance_i, quote.balance_j]);
+    assert_eq!(state.total_supply, quote.total_supply);
+}
+
+#[tokio::test]
+async fn quote_redeem_single_matches_redeem() {
+    let (mut context, pool) = setup().await;
+    let snapshot = snapshot(&mut context, &pool).await;
+    let quote = snapshot.quote_redeem_single(10_000_000, 0).unwrap();
+    assert_eq!(snapshot.virtual_price().unwrap(), 1_000_000_000);
+
+    let payer = context.payer.pubkey();
+    let state = pool_state(&mut context, &pool).await;
+    process(
+        &mut context,
+        &[instruction::redeem_single(&payer, &state, 10_000_000, 0, 0)],
+        &[],
+    )
+    .await;
+    let state = pool_state(&mut context, &pool).await;
+    assert_eq!(state.balances, quote.balances);
+    assert_eq!(state.total_supply, quote.total_supply);
+}
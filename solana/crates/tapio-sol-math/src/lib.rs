@@ -0,0 +1,838 @@
+//! StableSwap math of the tapio-sol pool without any Solana runtime dependency
+//!
+//! The program, the client and off-chain simulators all quote through this crate so that
+//! their results match to the lamport. Every function takes the pool as a plain [`Pool`]
+//! with the current unix timestamp, nothing reads a sysvar.
+//!
+//! ```
+//! use tapio_sol_math::{get_mint_amount, Pool};
+//!
+//! let pool = Pool {
+//!     balances: vec![0, 0],
+//!     precisions: vec![1, 1],
+//!     a: 10_000,
+//!     future_a: 10_000,
+//!     ..Pool::default()
+//! };
+//! let result = get_mint_amount(&pool, &[1_000_000_000, 1_000_000_000]).unwrap();
+//! assert_eq!(result.mint_amount, 2_000_000_000);
+//! ```
+
+#![no_std]
+
+extern crate alloc;
+
+mod math;
+
+use alloc::vec::Vec;
+use core::fmt;
+pub use math::U256;
+
+const A_PRECISION: u128 = 100u128;
+pub const FEE_PRECISION: u64 = 10_000_000_000u64;
+/// hard cap of the mint, swap and redeem fees, 5%
+pub const MAX_FEE: u64 = FEE_PRECISION / 20;
+/// scale of the virtual price, one SOL per pool token equals `VIRTUAL_PRICE_PRECISION`
+pub const VIRTUAL_PRICE_PRECISION: u64 = 1_000_000_000u64;
+const NUMBER_OF_ITERATIONS_TO_CONVERGE: u32 = 255;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MathError {
+    /// a step overflowed, underflowed or divided by zero
+    Arithmetic,
+    /// the amounts or indexes do not fit the pool
+    ArgumentValidation,
+    /// Newton's method ran out of iterations before the last two steps were within one
+    InvariantDidNotConverge,
+}
+
+impl fmt::Display for MathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MathError::Arithmetic => write!(f, "Arithmetic error."),
+            MathError::ArgumentValidation => write!(f, "Argument failed validation."),
+            MathError::InvariantDidNotConverge => write!(f, "Invariant did not converge."),
+        }
+    }
+}
+
+pub type Result<T> = core::result::Result<T, MathError>;
+
+/// the pool fields the math reads, with the time the amplitude ramp is evaluated at
+///
+/// Balances are in the common precision, token amounts times `precisions`, and already
+/// converted to SOL with the exchange rate of each asset.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Pool {
+    pub balances: Vec<u64>,
+    pub precisions: Vec<u64>,
+    /// D of `balances`
+    pub total_supply: u64,
+    pub a: u64,
+    pub a_time: u64,
+    pub future_a: u64,
+    pub future_a_time: u64,
+    pub mint_fee: u64,
+    pub swap_fee: u64,
+    pub redeem_fee: u64,
+    pub protocol_fee_share: u64,
+    /// pool tokens issued, a pool without any issues them one to one with D
+    pub pool_token_supply: u64,
+    /// part of D held for the yield recipient, the rest backs the pool tokens
+    pub pending_yield: u64,
+    /// unix timestamp the amplitude ramp is evaluated at
+    pub current_time: u64,
+}
+
+/// amplitude at `current` of a ramp from `a0` at `t0` to `a1` at `t1`, the algorithm is
+/// based on https://docs.acoconut.fi/asset/acbtc/algorithm
+pub fn interpolate_a(a0: u64, t0: u64, a1: u64, t1: u64, current: u64) -> Option<u64> {
+    if current < t1 {
+        let time_diff: u64 = current.checked_sub(t0)?;
+        let time_diff_div: u64 = t1.checked_sub(t0)?;
+        if a1 > a0 {
+            let diff = a1.checked_sub(a0)?;
+            let amount = diff.checked_mul(time_diff)?.checked_div(time_diff_div)?;
+            Some(a0.checked_add(amount)?)
+        } else {
+            let diff = a0.checked_sub(a1)?;
+            let amount = diff.checked_mul(time_diff)?.checked_div(time_diff_div)?;
+            Some(a0.checked_sub(amount)?)
+        }
+    } else {
+        Some(a1)
+    }
+}
+
+/// the invariant D of `balances` and the Newton iterations it took, products are carried
+/// in 256 bits so that a pool of any u64 balances cannot overflow before the result is
+/// cast back
+pub fn get_d(balances: &[u64], a: u64) -> Result<(u64, u32)> {
+    let (d, iterations) = solve_d(balances, a).ok_or(MathError::Arithmetic)?;
+    let iterations: u32 = iterations.ok_or(MathError::InvariantDidNotConverge)?;
+    Ok((d, iterations))
+}
+
+/// the balance of `token_index` keeping the invariant at `target_d` and the Newton
+/// iterations it took, in 256 bits like `get_d`
+pub fn get_y(
+    balances: &[u64],
+    token_index: usize,
+    target_d: u64,
+    amplitude: u64,
+) -> Result<(u64, u32)> {
+    let (y, iterations) =
+        solve_y(balances, token_index, target_d, amplitude).ok_or(MathError::Arithmetic)?;
+    let iterations: u32 = iterations.ok_or(MathError::InvariantDidNotConverge)?;
+    Ok((y, iterations))
+}
+
+/// Newton's method for D, None when a step overflows and no iteration count when the
+/// last two steps are still more than one apart
+fn solve_d(balances: &[u64], a: u64) -> Option<(u64, Option<u32>)> {
+    let zero: U256 = U256::zero();
+    let one: U256 = U256::one();
+    let a_precision: U256 = U256::from(A_PRECISION);
+    let mut sum: U256 = U256::zero();
+    let mut ann: U256 = U256::from(a);
+    let balance_size: U256 = U256::from(balances.len());
+    for x in balances.iter() {
+        let balance: U256 = U256::from(*x);
+        sum = sum.checked_add(balance)?;
+        ann = ann.checked_mul(balance_size)?;
+    }
+    if sum == zero {
+        return Some((0u64, Some(0u32)));
+    }
+
+    let mut prev_d: U256;
+    let mut d: U256 = sum;
+    for i in 1..=NUMBER_OF_ITERATIONS_TO_CONVERGE {
+        let mut p_d: U256 = d;
+        for x in balances.iter() {
+            let balance: U256 = U256::from(*x);
+            let div_op: U256 = balance.checked_mul(balance_size)?;
+            p_d = p_d.checked_mul(d)?.checked_div(div_op)?;
+        }
+        prev_d = d;
+        let t1: U256 = p_d.checked_mul(balance_size)?;
+        let t2: U256 = balance_size.checked_add(one)?.checked_mul(p_d)?;
+        let t3: U256 = ann
+            .checked_sub(a_precision)?
+            .checked_mul(d)?
+            .checked_div(a_precision)?
+            .checked_add(t2)?;
+        d = ann
+            .checked_mul(sum)?
+            .checked_div(a_precision)?
+            .checked_add(t1)?
+            .checked_mul(d)?
+            .checked_div(t3)?;
+        if d > prev_d {
+            if d - prev_d <= one {
+                return Some((u64::try_from(d).ok()?, Some(i)));
+            }
+        } else if prev_d - d <= one {
+            return Some((u64::try_from(d).ok()?, Some(i)));
+        }
+    }
+    Some((u64::try_from(d).ok()?, None))
+}
+
+/// Newton's method for y, None when a step overflows and no iteration count when the
+/// last two steps are still more than one apart
+fn solve_y(
+    balances: &[u64],
+    token_index: usize,
+    target_d: u64,
+    amplitude: u64,
+) -> Option<(u64, Option<u32>)> {
+    let one: U256 = U256::one();
+    let two: U256 = U256::from(2u64);
+    let a_precision: U256 = U256::from(A_PRECISION);
+    let mut c: U256 = U256::from(target_d);
+    let mut sum: U256 = U256::zero();
+    let mut ann: U256 = U256::from(amplitude);
+    let balance_size: U256 = U256::from(balances.len());
+    let target_d_u256: U256 = U256::from(target_d);
+
+    for (i, balance_ref) in balances.iter().enumerate() {
+        let balance: U256 = U256::from(*balance_ref);
+        ann = ann.checked_mul(balance_size)?;
+        if i == token_index {
+            continue;
+        }
+        sum = sum.checked_add(balance)?;
+        let div_op: U256 = balance.checked_mul(balance_size)?;
+        c = c.checked_mul(target_d_u256)?.checked_div(div_op)?
+    }
+
+    c = c
+        .checked_mul(target_d_u256)?
+        .checked_mul(a_precision)?
+        .checked_div(ann.checked_mul(balance_size)?)?;
+    let b: U256 = sum.checked_add(target_d_u256.checked_mul(a_precision)?.checked_div(ann)?)?;
+    let mut prev_y: U256;
+    let mut y: U256 = target_d_u256;
+
+    for i in 1..=NUMBER_OF_ITERATIONS_TO_CONVERGE {
+        prev_y = y;
+        y = y.checked_mul(y)?.checked_add(c)?.checked_div(
+            y.checked_mul(two)?
+                .checked_add(b)?
+                .checked_sub(target_d_u256)?,
+        )?;
+        if y > prev_y {
+            if y - prev_y <= one {
+                return Some((u64::try_from(y).ok()?, Some(i)));
+            }
+        } else if prev_y - y <= one {
+            return Some((u64::try_from(y).ok()?, Some(i)));
+        }
+    }
+    Some((u64::try_from(y).ok()?, None))
+}
+
+/// helper function to determine the mint amount
+pub fn get_mint_amount(pool_info: &Pool, amounts: &[u64]) -> Result<MintResult> {
+    if pool_info.balances.len() != amounts.len() {
+        return Err(MathError::ArgumentValidation);
+    }
+
+    let a: u64 = interpolate_a(
+        pool_info.a,
+        pool_info.a_time,
+        pool_info.future_a,
+        pool_info.future_a_time,
+        pool_info.current_time,
+    )
+    .ok_or(MathError::Arithmetic)?;
+    let old_d: u64 = pool_info.total_supply;
+    let zero: u64 = 0u64;
+
+    let mut balances: Vec<u64> = pool_info.balances.to_vec();
+    for i in 0..balances.len() {
+        if amounts[i] == zero {
+            if old_d == zero {
+                return Err(MathError::ArgumentValidation);
+            }
+            continue;
+        }
+        let result: u64 = balances[i]
+            .checked_add(
+                amounts[i]
+                    .checked_mul(pool_info.precisions[i])
+                    .ok_or(MathError::Arithmetic)?,
+            )
+            .ok_or(MathError::Arithmetic)?;
+        balances[i] = result;
+    }
+    let (new_d, iterations) = get_d(&balances, a)?;
+    let mut mint_amount: u64 = get_pool_token_amount(
+        pool_info,
+        new_d.checked_sub(old_d).ok_or(MathError::Arithmetic)?,
+    )?;
+    let mut fee_amount: u64 = zero;
+    let mint_fee: u64 = pool_info.mint_fee;
+
+    if pool_info.mint_fee > zero {
+        fee_amount = mul_div(mint_amount, mint_fee, FEE_PRECISION)?;
+        mint_amount = mint_amount
+            .checked_sub(fee_amount)
+            .ok_or(MathError::Arithmetic)?;
+    }
+
+    Ok(MintResult {
+        mint_amount,
+        fee_amount,
+        balances,
+        total_supply: new_d,
+        iterations,
+    })
+}
+
+/// helper function to determine the swap amount
+pub fn get_swap_amount(
+    pool_info: &Pool,
+    input_index: usize,
+    output_index: usize,
+    dx: u64,
+) -> Result<SwapResult> {
+    let zero: u64 = 0u64;
+    let one: u64 = 1u64;
+    let balance_size: usize = pool_info.balances.len();
+    if input_index == output_index {
+        return Err(MathError::Arithmetic);
+    }
+    if dx <= zero {
+        return Err(MathError::Arithmetic);
+    }
+    if input_index >= balance_size {
+        return Err(MathError::Arithmetic);
+    }
+    if output_index >= balance_size {
+        return Err(MathError::Arithmetic);
+    }
+
+    let a: u64 = interpolate_a(
+        pool_info.a,
+        pool_info.a_time,
+        pool_info.future_a,
+        pool_info.future_a_time,
+        pool_info.current_time,
+    )
+    .ok_or(MathError::Arithmetic)?;
+    let d: u64 = pool_info.total_supply;
+    let mut balances: Vec<u64> = pool_info.balances.to_vec();
+    balances[input_index] = balances[input_index]
+        .checked_add(
+            dx.checked_mul(pool_info.precisions[input_index])
+                .ok_or(MathError::Arithmetic)?,
+        )
+        .ok_or(MathError::Arithmetic)?;
+    let (y, y_iterations) = get_y(&balances, output_index, d, a)?;
+    let mut dy: u64 = balances[output_index]
+        .checked_sub(y)
+        .ok_or(MathError::Arithmetic)?
+        .checked_sub(one)
+        .ok_or(MathError::Arithmetic)?
+        .checked_div(pool_info.precisions[output_index])
+        .ok_or(MathError::Arithmetic)?;
+    let mut fee_amount: u64 = zero;
+    if pool_info.swap_fee > zero {
+        fee_amount = mul_div(dy, pool_info.swap_fee, FEE_PRECISION)?;
+        dy = dy.checked_sub(fee_amount).ok_or(MathError::Arithmetic)?;
+    }
+
+    balances[output_index] = y;
+    let (balance_j, protocol_fee_amount, total_supply, d_iterations) =
+        keep_protocol_fee(pool_info, &mut balances, output_index, fee_amount, d, a)?;
+    Ok(SwapResult {
+        dx,
+        dy,
+        y,
+        balance_i: balances[input_index],
+        balance_j,
+        fee_amount,
+        protocol_fee_amount,
+        total_supply,
+        iterations: y_iterations + d_iterations,
+    })
+}
+
+/// helper function to determine the input amount of an exact output swap
+pub fn get_swap_amount_exact_out(
+    pool_info: &Pool,
+    input_index: usize,
+    output_index: usize,
+    dy: u64,
+) -> Result<SwapResult> {
+    let zero: u64 = 0u64;
+    let one: u64 = 1u64;
+    let balance_size: usize = pool_info.balances.len();
+    if input_index == output_index {
+        return Err(MathError::Arithmetic);
+    }
+    if dy <= zero {
+        return Err(MathError::Arithmetic);
+    }
+    if input_index >= balance_size {
+        return Err(MathError::Arithmetic);
+    }
+    if output_index >= balance_size {
+        return Err(MathError::Arithmetic);
+    }
+
+    let a: u64 = interpolate_a(
+        pool_info.a,
+        pool_info.a_time,
+        pool_info.future_a,
+        pool_info.future_a_time,
+        pool_info.current_time,
+    )
+    .ok_or(MathError::Arithmetic)?;
+    let d: u64 = pool_info.total_supply;
+    let mut balances: Vec<u64> = pool_info.balances.to_vec();
+
+    // The swap fee is charged on the output, gross the requested amount up so the user receives dy.
+    let mut gross_dy: u64 = dy;
+    let mut fee_amount: u64 = zero;
+    if pool_info.swap_fee > zero {
+        let net_precision: u128 = u128::from(
+            FEE_PRECISION
+                .checked_sub(pool_info.swap_fee)
+                .ok_or(MathError::Arithmetic)?,
+        );
+        let gross: u128 = u128::from(dy)
+            .checked_mul(u128::from(FEE_PRECISION))
+            .ok_or(MathError::Arithmetic)?
+            .checked_add(net_precision.checked_sub(1).ok_or(MathError::Arithmetic)?)
+            .ok_or(MathError::Arithmetic)?
+            .checked_div(net_precision)
+            .ok_or(MathError::Arithmetic)?;
+        gross_dy = u64::try_from(gross).map_err(|_| MathError::Arithmetic)?;
+        fee_amount = gross_dy.checked_sub(dy).ok_or(MathError::Arithmetic)?;
+    }
+    let y: u64 = balances[output_index]
+        .checked_sub(
+            gross_dy
+                .checked_mul(pool_info.precisions[output_index])
+                .ok_or(MathError::Arithmetic)?,
+        )
+        .ok_or(MathError::Arithmetic)?
+        .checked_sub(one)
+        .ok_or(MathError::Arithmetic)?;
+    balances[output_index] = y;
+    let (x, y_iterations) = get_y(&balances, input_index, d, a)?;
+    // dx = (x - balance[i]) / precisions[i] + 1 in case there was rounding errors
+    let dx: u64 = x
+        .checked_sub(balances[input_index])
+        .ok_or(MathError::Arithmetic)?
+        .checked_div(pool_info.precisions[input_index])
+        .ok_or(MathError::Arithmetic)?
+        .checked_add(one)
+        .ok_or(MathError::Arithmetic)?;
+    balances[input_index] = balances[input_index]
+        .checked_add(
+            dx.checked_mul(pool_info.precisions[input_index])
+                .ok_or(MathError::Arithmetic)?,
+        )
+        .ok_or(MathError::Arithmetic)?;
+
+    let (balance_j, protocol_fee_amount, total_supply, d_iterations) =
+        keep_protocol_fee(pool_info, &mut balances, output_index, fee_amount, d, a)?;
+    Ok(SwapResult {
+        dx,
+        dy,
+        y,
+        balance_i: balances[input_index],
+        balance_j,
+        fee_amount,
+        protocol_fee_amount,
+        total_supply,
+        iterations: y_iterations + d_iterations,
+    })
+}
+
+/// keep the protocol share of a swap fee in the output balance and return the new output
+/// balance, the pool token amount to mint for it, the new D and the iterations solving it
+fn keep_protocol_fee(
+    pool_info: &Pool,
+    balances: &mut [u64],
+    output_index: usize,
+    fee_amount: u64,
+    d: u64,
+    a: u64,
+) -> Result<(u64, u64, u64, u32)> {
+    let protocol_fee: u64 = get_protocol_fee(fee_amount, pool_info.protocol_fee_share)?;
+    if protocol_fee == 0 {
+        return Ok((balances[output_index], 0, d, 0));
+    }
+    balances[output_index] = balances[output_index]
+        .checked_add(
+            protocol_fee
+                .checked_mul(pool_info.precisions[output_index])
+                .ok_or(MathError::Arithmetic)?,
+        )
+        .ok_or(MathError::Arithmetic)?;
+    let (new_d, iterations) = get_d(balances, a)?;
+    let protocol_fee_d: u64 = new_d.saturating_sub(d);
+    let protocol_fee_amount: u64 = get_pool_token_amount(pool_info, protocol_fee_d)?;
+    let total_supply: u64 = d.checked_add(protocol_fee_d).ok_or(MathError::Arithmetic)?;
+    Ok((
+        balances[output_index],
+        protocol_fee_amount,
+        total_supply,
+        iterations,
+    ))
+}
+
+/// protocol share of a fee amount
+pub fn get_protocol_fee(fee_amount: u64, protocol_fee_share: u64) -> Result<u64> {
+    mul_div(fee_amount, protocol_fee_share, FEE_PRECISION)
+}
+
+/// `x * y / z` with a 128-bit intermediate, amounts times fees or balances overflow u64
+fn mul_div(x: u64, y: u64, z: u64) -> Result<u64> {
+    let result: u128 = u128::from(x)
+        .checked_mul(u128::from(y))
+        .ok_or(MathError::Arithmetic)?
+        .checked_div(u128::from(z))
+        .ok_or(MathError::Arithmetic)?;
+    u64::try_from(result).map_err(|_| MathError::Arithmetic)
+}
+
+/// `mul_div` rounded up
+fn mul_div_up(x: u64, y: u64, z: u64) -> Result<u64> {
+    let z: u128 = u128::from(z);
+    let result: u128 = u128::from(x)
+        .checked_mul(u128::from(y))
+        .ok_or(MathError::Arithmetic)?
+        .checked_add(z.checked_sub(1).ok_or(MathError::Arithmetic)?)
+        .ok_or(MathError::Arithmetic)?
+        .checked_div(z)
+        .ok_or(MathError::Arithmetic)?;
+    u64::try_from(result).map_err(|_| MathError::Arithmetic)
+}
+
+/// D backing the pool tokens, everything but the pending yield
+fn get_backing(pool_info: &Pool) -> Result<u64> {
+    pool_info
+        .total_supply
+        .checked_sub(pool_info.pending_yield)
+        .ok_or(MathError::Arithmetic)
+}
+
+/// pool tokens worth `amount` of D, rounded down
+///
+/// A pool token is worth the D backing the pool tokens over their supply, fees kept by the
+/// pool raise its worth and a pool without pool tokens issues them one to one with D.
+pub fn get_pool_token_amount(pool_info: &Pool, amount: u64) -> Result<u64> {
+    if pool_info.pool_token_supply == 0 {
+        return Ok(amount);
+    }
+    mul_div(amount, pool_info.pool_token_supply, get_backing(pool_info)?)
+}
+
+/// `get_pool_token_amount` rounded up, for the pool tokens burned for an amount of D
+fn get_pool_token_amount_up(pool_info: &Pool, amount: u64) -> Result<u64> {
+    if pool_info.pool_token_supply == 0 {
+        return Ok(amount);
+    }
+    mul_div_up(amount, pool_info.pool_token_supply, get_backing(pool_info)?)
+}
+
+/// D worth `amount` pool tokens, rounded down
+fn get_d_amount(pool_info: &Pool, amount: u64) -> Result<u64> {
+    if pool_info.pool_token_supply == 0 {
+        return Ok(amount);
+    }
+    mul_div(amount, get_backing(pool_info)?, pool_info.pool_token_supply)
+}
+
+/// SOL value of one pool token, the D backing the pool tokens over their supply scaled by
+/// `VIRTUAL_PRICE_PRECISION`
+///
+/// The balances behind D are already converted to SOL with the exchange rates of the
+/// liquid staking tokens. It is the SOL value `get_redeem_proportion_amount` pays out per pool
+/// token before the redeem fee, in a balanced pool; the balances of an imbalanced pool sum above
+/// D and pay out slightly more. An empty pool is priced at one SOL per pool token.
+pub fn get_virtual_price(pool_info: &Pool) -> Result<u64> {
+    if pool_info.pool_token_supply == 0u64 {
+        return Ok(VIRTUAL_PRICE_PRECISION);
+    }
+    mul_div(
+        get_backing(pool_info)?,
+        VIRTUAL_PRICE_PRECISION,
+        pool_info.pool_token_supply,
+    )
+}
+
+/// helper function to determine the redeem proportion amount, the share of every balance
+/// the D worth of the pool tokens is of D
+pub fn get_redeem_proportion_amount(
+    pool_info: &Pool,
+    amount_bal: u64,
+) -> Result<RedeemProportionResult> {
+    let mut amount: u64 = amount_bal;
+    let zero: u64 = 0u64;
+
+    if amount <= zero {
+        return Err(MathError::ArgumentValidation);
+    }
+
+    let d: u64 = pool_info.total_supply;
+    let mut amounts: Vec<u64> = Vec::new();
+    let mut balances: Vec<u64> = pool_info.balances.to_vec();
+
+    let mut fee_amount: u64 = zero;
+    if pool_info.redeem_fee > zero {
+        fee_amount = mul_div(amount, pool_info.redeem_fee, FEE_PRECISION)?;
+        // Redemption fee is charged with pool token before redemption.
+        amount = amount
+            .checked_sub(fee_amount)
+            .ok_or(MathError::Arithmetic)?;
+    }
+
+    // D drops by the worth of the redeemed pool tokens
+    let redeem_amount: u64 = get_d_amount(pool_info, amount)?;
+    for i in 0..pool_info.balances.len() {
+        let balance_i: u64 = balances[i];
+        let diff_i: u64 = mul_div(balance_i, redeem_amount, d)?;
+        balances[i] = balance_i.checked_sub(diff_i).ok_or(MathError::Arithmetic)?;
+        let amounts_i: u64 = diff_i
+            .checked_div(pool_info.precisions[i])
+            .ok_or(MathError::Arithmetic)?;
+        amounts.push(amounts_i);
+    }
+    let total_supply: u64 = d.checked_sub(redeem_amount).ok_or(MathError::Arithmetic)?;
+    Ok(RedeemProportionResult {
+        amounts: amounts.to_vec(),
+        balances: balances.to_vec(),
+        fee_amount,
+        total_supply,
+        redeem_amount,
+    })
+}
+
+/// helper function to determine the redeem single amount
+pub fn get_redeem_single_amount(
+    pool_info: &Pool,
+    amount_bal: u64,
+    i: usize,
+) -> Result<RedeemSingleResult> {
+    let mut amount: u64 = amount_bal;
+    let zero: u64 = 0u64;
+    let one: u64 = 1u64;
+    if amount <= zero {
+        return Err(MathError::ArgumentValidation);
+    }
+    if i >= pool_info.balances.len() {
+        return Err(MathError::ArgumentValidation);
+    }
+    let mut balances: Vec<u64> = pool_info.balances.to_vec();
+    let a: u64 = interpolate_a(
+        pool_info.a,
+        pool_info.a_time,
+        pool_info.future_a,
+        pool_info.future_a_time,
+        pool_info.current_time,
+    )
+    .ok_or(MathError::Arithmetic)?;
+    let d: u64 = pool_info.total_supply;
+    let mut fee_amount: u64 = zero;
+
+    if pool_info.redeem_fee > zero {
+        fee_amount = mul_div(amount, pool_info.redeem_fee, FEE_PRECISION)?;
+        // Redemption fee is charged with pool token before redemption.
+        amount = amount
+            .checked_sub(fee_amount)
+            .ok_or(MathError::Arithmetic)?;
+    }
+
+    // D drops by the worth of the redeemed pool tokens
+    let redeem_amount: u64 = get_d_amount(pool_info, amount)?;
+    let total_supply: u64 = d.checked_sub(redeem_amount).ok_or(MathError::Arithmetic)?;
+    let (y, iterations) = get_y(&balances, i, total_supply, a)?;
+    // dy = (balance[i] - y - 1) / precisions[i] in case there was rounding errors
+    let balance_i: u64 = pool_info.balances[i];
+    let dy: u64 = balance_i
+        .checked_sub(y)
+        .ok_or(MathError::Arithmetic)?
+        .checked_sub(one)
+        .ok_or(MathError::Arithmetic)?
+        .checked_div(pool_info.precisions[i])
+        .ok_or(MathError::Arithmetic)?;
+    balances[i] = y;
+    Ok(RedeemSingleResult {
+        dy,
+        fee_amount,
+        total_supply,
+        balances: balances.to_vec(),
+        redeem_amount,
+        iterations,
+    })
+}
+
+/// helper function to determine the pool token amount burned by a redeem to exact amounts,
+/// charging an imbalance fee on the distance from a proportional redeem like Curve
+pub fn get_redeem_multi_amount(pool_info: &Pool, amounts: &[u64]) -> Result<RedeemMultiResult> {
+    let zero: u64 = 0u64;
+    let one: u64 = 1u64;
+    if pool_info.balances.len() != amounts.len() {
+        return Err(MathError::ArgumentValidation);
+    }
+    if amounts.iter().all(|amount| *amount == zero) {
+        return Err(MathError::ArgumentValidation);
+    }
+
+    let a: u64 = interpolate_a(
+        pool_info.a,
+        pool_info.a_time,
+        pool_info.future_a,
+        pool_info.future_a_time,
+        pool_info.current_time,
+    )
+    .ok_or(MathError::Arithmetic)?;
+    let old_d: u64 = pool_info.total_supply;
+    let mut balances: Vec<u64> = pool_info.balances.to_vec();
+    for i in 0..balances.len() {
+        balances[i] = balances[i]
+            .checked_sub(
+                amounts[i]
+                    .checked_mul(pool_info.precisions[i])
+                    .ok_or(MathError::Arithmetic)?,
+            )
+            .ok_or(MathError::Arithmetic)?;
+    }
+    let (new_d, new_d_iterations) = get_d(&balances, a)?;
+
+    // imbalance fee = swap_fee * n / (4 * (n - 1)), charged on the deviation of every balance
+    let balance_size: u64 = u64::try_from(balances.len()).map_err(|_| MathError::Arithmetic)?;
+    let imbalance_fee: u64 = pool_info
+        .swap_fee
+        .checked_mul(balance_size)
+        .ok_or(MathError::Arithmetic)?
+        .checked_div(
+            balance_size
+                .checked_sub(one)
+                .ok_or(MathError::Arithmetic)?
+                .checked_mul(4u64)
+                .ok_or(MathError::Arithmetic)?,
+        )
+        .ok_or(MathError::Arithmetic)?;
+    let mut fee_balances: Vec<u64> = balances.to_vec();
+    for i in 0..balances.len() {
+        let ideal_balance: u128 = u128::from(new_d)
+            .checked_mul(u128::from(pool_info.balances[i]))
+            .ok_or(MathError::Arithmetic)?
+            .checked_div(u128::from(old_d))
+            .ok_or(MathError::Arithmetic)?;
+        let difference: u128 = ideal_balance.abs_diff(u128::from(balances[i]));
+        let fee: u64 = u64::try_from(
+            difference
+                .checked_mul(u128::from(imbalance_fee))
+                .ok_or(MathError::Arithmetic)?
+                .checked_div(u128::from(FEE_PRECISION))
+                .ok_or(MathError::Arithmetic)?,
+        )
+        .map_err(|_| MathError::Arithmetic)?;
+        fee_balances[i] = fee_balances[i]
+            .checked_sub(fee)
+            .ok_or(MathError::Arithmetic)?;
+    }
+    let (fee_d, fee_d_iterations) = get_d(&fee_balances, a)?;
+    let mut redeem_amount: u64 = get_pool_token_amount_up(
+        pool_info,
+        old_d
+            .checked_sub(fee_d)
+            .ok_or(MathError::Arithmetic)?
+            .checked_add(one)
+            .ok_or(MathError::Arithmetic)?,
+    )?;
+
+    // Redemption fee is charged with pool token on top of the burned amount.
+    if pool_info.redeem_fee > zero {
+        let net_precision: u128 = u128::from(
+            FEE_PRECISION
+                .checked_sub(pool_info.redeem_fee)
+                .ok_or(MathError::Arithmetic)?,
+        );
+        let gross: u128 = u128::from(redeem_amount)
+            .checked_mul(u128::from(FEE_PRECISION))
+            .ok_or(MathError::Arithmetic)?
+            .checked_add(net_precision.checked_sub(1).ok_or(MathError::Arithmetic)?)
+            .ok_or(MathError::Arithmetic)?
+            .checked_div(net_precision)
+            .ok_or(MathError::Arithmetic)?;
+        redeem_amount = u64::try_from(gross).map_err(|_| MathError::Arithmetic)?;
+    }
+    let fee_amount: u64 = redeem_amount
+        .checked_sub(get_pool_token_amount(
+            pool_info,
+            old_d.checked_sub(new_d).ok_or(MathError::Arithmetic)?,
+        )?)
+        .ok_or(MathError::Arithmetic)?;
+
+    Ok(RedeemMultiResult {
+        fee_amount,
+        total_supply: new_d,
+        balances,
+        redeem_amount,
+        iterations: new_d_iterations + fee_d_iterations,
+    })
+}
+
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct RedeemMultiResult {
+    pub fee_amount: u64,
+    pub total_supply: u64,
+    pub balances: Vec<u64>,
+    pub redeem_amount: u64,
+    /// Newton iterations spent solving the invariant
+    pub iterations: u32,
+}
+
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct MintResult {
+    pub mint_amount: u64,
+    pub fee_amount: u64,
+    pub balances: Vec<u64>,
+    pub total_supply: u64,
+    /// Newton iterations spent solving the invariant
+    pub iterations: u32,
+}
+
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct SwapResult {
+    pub dx: u64,
+    pub dy: u64,
+    pub y: u64,
+    pub balance_i: u64,
+    pub balance_j: u64,
+    pub fee_amount: u64,
+    pub protocol_fee_amount: u64,
+    pub total_supply: u64,
+    /// Newton iterations spent solving the invariant
+    pub iterations: u32,
+}
+
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct RedeemProportionResult {
+    pub amounts: Vec<u64>,
+    pub balances: Vec<u64>,
+    pub fee_amount: u64,
+    pub total_supply: u64,
+    pub redeem_amount: u64,
+}
+
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct RedeemSingleResult {
+    pub dy: u64,
+    pub fee_amount: u64,
+    pub total_supply: u64,
+    pub balances: Vec<u64>,
+    pub redeem_amount: u64,
+    /// Newton iterations spent solving the invariant
+    pub iterations: u32,
+}
@@ -0,0 +1,12 @@
+//! 256-bit unsigned integer used for the intermediate products of the invariant
+
+#![allow(clippy::assign_op_pattern)]
+#![allow(clippy::ptr_offset_with_cast)]
+#![allow(clippy::manual_range_contains)]
+#![allow(clippy::manual_div_ceil)]
+
+use uint::construct_uint;
+
+construct_uint! {
+    pub struct U256(4);
+}
@@ -0,0 +1,68 @@
+pub mod state;
+
+use crate::state::{AccountType, Lido, LidoProgram};
+use anchor_lang::prelude::*;
+
+declare_id!("CrX7kMhLC3cSsXJdT7JDgqrRVWGnUpX3gfEfxxU2NVLi");
+
+#[program]
+pub mod lido_test {
+    use super::*;
+
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        sol_balance: u64,
+        st_sol_supply: u64,
+    ) -> Result<()> {
+        crate::initialize(ctx, sol_balance, st_sol_supply)
+    }
+
+    pub fn update(ctx: Context<Update>, sol_balance: u64, st_sol_supply: u64) -> Result<()> {
+        crate::update(ctx, sol_balance, st_sol_supply)
+    }
+}
+
+pub fn initialize(ctx: Context<Initialize>, sol_balance: u64, st_sol_supply: u64) -> Result<()> {
+    let state = &mut ctx.accounts.lido_account;
+    state.account_type = AccountType::Lido;
+    state.st_sol_mint = ctx.accounts.st_sol_mint.key();
+    state.exchange_rate.sol_balance = sol_balance;
+    state.exchange_rate.st_sol_supply = st_sol_supply;
+    Ok(())
+}
+pub fn update(ctx: Context<Update>, sol_balance: u64, st_sol_supply: u64) -> Result<()> {
+    let state = &mut ctx.accounts.lido_account;
+    state.exchange_rate.sol_balance = sol_balance;
+    state.exchange_rate.st_sol_supply = st_sol_supply;
+    state.exchange_rate.computed_in_epoch = Clock::get()?.epoch;
+    Ok(())
+}
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: any mint backed by the test state
+    pub st_sol_mint: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        seeds = [b"test".as_ref(), st_sol_mint.key().as_ref()],
+        bump,
+        payer = payer,
+        space = 8 + state::Lido::INIT_SPACE
+    )]
+    pub lido_account: Account<'info, Lido>,
+
+    pub lido_program: Program<'info, LidoProgram>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct Update<'info> {
+    pub payer: Signer<'info>,
+
+    #[account(mut)]
+    pub lido_account: Account<'info, Lido>,
+}
@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+
+use std::io::Write;
+
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Eq, Default, Debug, InitSpace,
+)]
+pub enum AccountType {
+    /// If the account has not been initialized, the enum will be 0
+    #[default]
+    Uninitialized,
+    /// Lido state
+    Lido,
+    /// Validator list
+    Validator,
+    /// Maintainer list
+    Maintainer,
+}
+
+/// stSOL exchange rate, computed once per epoch
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Eq, Default, Debug, InitSpace,
+)]
+pub struct ExchangeRate {
+    /// Epoch in which the exchange rate was last computed
+    pub computed_in_epoch: u64,
+
+    /// Amount of stSOL that existed at that time
+    pub st_sol_supply: u64,
+
+    /// Amount of SOL managed by Lido at that time
+    pub sol_balance: u64,
+}
+
+/// Lido (Solido) state, up to the exchange rate.
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Eq, Default, Debug, InitSpace,
+)]
+pub struct Lido {
+    /// Account type, must be `Lido`
+    pub account_type: AccountType,
+
+    /// Version number of the Lido state
+    pub lido_version: u8,
+
+    /// Manager authority
+    pub manager: Pubkey,
+
+    /// The SPL Token mint address for stSOL
+    pub st_sol_mint: Pubkey,
+
+    /// Exchange rate to use when depositing
+    pub exchange_rate: ExchangeRate,
+}
+
+impl AccountSerialize for Lido {
+    fn try_serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
+        if AnchorSerialize::serialize(self, writer).is_err() {
+            return Err(ErrorCode::AccountDidNotSerialize.into());
+        }
+        Ok(())
+    }
+}
+impl AccountDeserialize for Lido {
+    fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+        Self::try_deserialize_unchecked(buf)
+    }
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+        AnchorDeserialize::deserialize(buf).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+impl anchor_lang::Discriminator for Lido {
+    const DISCRIMINATOR: [u8; 8] = [0; 8];
+}
+
+impl Owner for Lido {
+    fn owner() -> Pubkey {
+        crate::ID
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LidoProgram;
+
+impl Id for LidoProgram {
+    fn id() -> Pubkey {
+        crate::ID
+    }
+}
@@ -0,0 +1,61 @@
+pub mod state;
+
+use crate::state::{MarinadeProgram, State};
+use anchor_lang::prelude::*;
+
+declare_id!("MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD");
+
+#[program]
+pub mod marinade_test {
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>, msol_price: u64) -> Result<()> {
+        crate::initialize(ctx, msol_price)
+    }
+
+    pub fn update(ctx: Context<Update>, msol_price: u64) -> Result<()> {
+        crate::update(ctx, msol_price)
+    }
+}
+
+pub fn initialize(ctx: Context<Initialize>, msol_price: u64) -> Result<()> {
+    let state = &mut ctx.accounts.marinade_state_account;
+    state.msol_mint = ctx.accounts.msol_mint.key();
+    state.msol_price = msol_price;
+    Ok(())
+}
+pub fn update(ctx: Context<Update>, msol_price: u64) -> Result<()> {
+    let state = &mut ctx.accounts.marinade_state_account;
+    state.msol_price = msol_price;
+    state.stake_system.last_stake_delta_epoch = Clock::get()?.epoch;
+    Ok(())
+}
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: any mint backed by the test state
+    pub msol_mint: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        seeds = [b"test".as_ref(), msol_mint.key().as_ref()],
+        bump,
+        payer = payer,
+        space = 8 + state::State::INIT_SPACE
+    )]
+    pub marinade_state_account: Account<'info, State>,
+
+    pub marinade_program: Program<'info, MarinadeProgram>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct Update<'info> {
+    pub payer: Signer<'info>,
+
+    #[account(mut)]
+    pub marinade_state_account: Account<'info, State>,
+}
@@ -0,0 +1,53 @@
+pub mod stake;
+
+use crate::stake::{StakePool, StakePoolProgram};
+use anchor_lang::prelude::*;
+
+declare_id!("SP12tWFxD9oJsVWNavTTBZvMbA6gkAmxtVgxdqvyvhY");
+
+#[program]
+pub mod sanctum_stake_pool_test {
+    use super::*;
+
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        total_lamports: u64,
+        pool_token_supply: u64,
+    ) -> Result<()> {
+        crate::initialize(ctx, total_lamports, pool_token_supply)
+    }
+}
+
+pub fn initialize(
+    ctx: Context<Initialize>,
+    total_lamports: u64,
+    pool_token_supply: u64,
+) -> Result<()> {
+    let state = &mut ctx.accounts.stake_pool_account;
+    state.account_type = stake::AccountType::StakePool;
+    state.pool_mint = ctx.accounts.pool_mint.key();
+    state.total_lamports = total_lamports;
+    state.pool_token_supply = pool_token_supply;
+    Ok(())
+}
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: any mint backed by the test stake pool
+    pub pool_mint: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        seeds = [b"test".as_ref(), pool_mint.key().as_ref()],
+        bump,
+        payer = payer,
+        space = 8 + stake::StakePool::INIT_SPACE
+    )]
+    pub stake_pool_account: Account<'info, StakePool>,
+
+    pub stake_pool_program: Program<'info, StakePoolProgram>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
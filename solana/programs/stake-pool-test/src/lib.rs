@@ -16,6 +16,10 @@ pub mod stake_pool_test {
     ) -> Result<()> {
         crate::initialize(ctx, total_lamports, pool_token_supply)
     }
+
+    pub fn update(ctx: Context<Update>, total_lamports: u64, pool_token_supply: u64) -> Result<()> {
+        crate::update(ctx, total_lamports, pool_token_supply)
+    }
 }
 
 pub fn initialize(
@@ -23,19 +27,32 @@ pub fn initialize(
     total_lamports: u64,
     pool_token_supply: u64,
 ) -> Result<()> {
+    let state = &mut ctx.accounts.stake_pool_account;
+    state.account_type = stake::AccountType::StakePool;
+    state.pool_mint = ctx.accounts.pool_mint.key();
+    state.total_lamports = total_lamports;
+    state.pool_token_supply = pool_token_supply;
+    Ok(())
+}
+pub fn update(ctx: Context<Update>, total_lamports: u64, pool_token_supply: u64) -> Result<()> {
     let state = &mut ctx.accounts.stake_pool_account;
     state.total_lamports = total_lamports;
     state.pool_token_supply = pool_token_supply;
+    state.last_update_epoch = Clock::get()?.epoch;
     Ok(())
 }
+
 #[derive(Accounts)]
 pub struct Initialize<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
 
+    /// CHECK: any mint backed by the test stake pool
+    pub pool_mint: UncheckedAccount<'info>,
+
     #[account(
         init,
-        seeds = [b"test".as_ref()],
+        seeds = [b"test".as_ref(), pool_mint.key().as_ref()],
         bump,
         payer = payer,
         space = 8 + stake::StakePool::INIT_SPACE
@@ -46,3 +63,11 @@ pub struct Initialize<'info> {
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
 }
+
+#[derive(Accounts)]
+pub struct Update<'info> {
+    pub payer: Signer<'info>,
+
+    #[account(mut)]
+    pub stake_pool_account: Account<'info, StakePool>,
+}
@@ -27,4 +27,31 @@ pub enum ErrorCode {
 
     #[msg("Unauthorized signer.")]
     Unauthorized,
+
+    #[msg("Operation paused.")]
+    OperationPaused,
+
+    #[msg("Exchange rate is stale.")]
+    StaleExchangeRate,
+
+    #[msg("Swap above maximum.")]
+    SwapOverMax,
+
+    #[msg("Redeem above maximum.")]
+    RedeemOverMax,
+
+    #[msg("Invariant did not converge.")]
+    InvariantDidNotConverge,
+}
+
+impl From<tapio_sol_math::MathError> for ErrorCode {
+    fn from(error: tapio_sol_math::MathError) -> Self {
+        match error {
+            tapio_sol_math::MathError::Arithmetic => ErrorCode::ArithmeticError,
+            tapio_sol_math::MathError::ArgumentValidation => ErrorCode::ArgumentValidationFailure,
+            tapio_sol_math::MathError::InvariantDidNotConverge => {
+                ErrorCode::InvariantDidNotConverge
+            }
+        }
+    }
 }
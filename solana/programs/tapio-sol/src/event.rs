@@ -1,3 +1,5 @@
+use crate::rate::RateProvider;
+use crate::state::Role;
 use anchor_lang::prelude::*;
 
 /// emit when a pool is created with its fees and amplitude
@@ -20,6 +22,7 @@ pub struct Minted {
     pub balances: Vec<u64>,
     pub total_supply: u64,
     pub fee_amount: u64,
+    pub protocol_fee_amount: u64,
     pub output_amount: u64,
 }
 
@@ -34,6 +37,24 @@ pub struct TokenSwapped {
     pub min_output_amount: u64,
     pub balances: Vec<u64>,
     pub total_supply: u64,
+    pub fee_amount: u64,
+    pub protocol_fee_amount: u64,
+    pub output_amount: u64,
+}
+
+/// emit when users swap tokens for an exact output and record the pool state
+#[event]
+pub struct TokenSwappedExactOut {
+    pub swapper: Pubkey,
+    pub a: u64,
+    pub input_asset: Pubkey,
+    pub output_asset: Pubkey,
+    pub input_amount: u64,
+    pub max_input_amount: u64,
+    pub balances: Vec<u64>,
+    pub total_supply: u64,
+    pub fee_amount: u64,
+    pub protocol_fee_amount: u64,
     pub output_amount: u64,
 }
 
@@ -47,6 +68,7 @@ pub struct RedeemedProportion {
     pub balances: Vec<u64>,
     pub total_supply: u64,
     pub fee_amount: u64,
+    pub protocol_fee_amount: u64,
     pub output_amounts: Vec<u64>,
 }
 
@@ -61,12 +83,165 @@ pub struct RedeemedSingle {
     pub balances: Vec<u64>,
     pub total_supply: u64,
     pub fee_amount: u64,
+    pub protocol_fee_amount: u64,
     pub output_amount: u64,
 }
 
+/// emit when users redeem tokens to exact amounts and record the pool state
+#[event]
+pub struct RedeemedMulti {
+    pub redeemer: Pubkey,
+    pub a: u64,
+    pub input_amount: u64,
+    pub max_input_amount: u64,
+    pub balances: Vec<u64>,
+    pub total_supply: u64,
+    pub fee_amount: u64,
+    pub protocol_fee_amount: u64,
+    pub output_amounts: Vec<u64>,
+}
+
+/// emit when admins modify pool fees
+#[event]
+pub struct FeesModified {
+    pub mint_fee: u64,
+    pub swap_fee: u64,
+    pub redeem_fee: u64,
+}
+
+/// emit when the authority assigns or revokes a role
+#[event]
+pub struct RoleUpdated {
+    pub role: Role,
+    pub key: Pubkey,
+}
+
+/// emit when the paused operations change
+#[event]
+pub struct PauseUpdated {
+    pub paused: u8,
+}
+
+/// emit when the pool enters or leaves emergency mode
+#[event]
+pub struct EmergencyUpdated {
+    pub emergency: bool,
+}
+
+/// emit when the authority proposes a new authority
+#[event]
+pub struct AuthorityProposed {
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+}
+
+/// emit when the pending authority accepts the pool
+#[event]
+pub struct AuthorityTransferred {
+    pub previous_authority: Pubkey,
+    pub authority: Pubkey,
+}
+
+/// emit when the authority cancels a pending transfer
+#[event]
+pub struct AuthorityTransferCancelled {
+    pub pending_authority: Pubkey,
+}
+
+/// emit when admins modify the fee recipient and its share
+#[event]
+pub struct FeeRecipientModified {
+    pub fee_recipient: Pubkey,
+    pub protocol_fee_share: u64,
+}
+
+/// emit when admins modify the yield recipient and the performance fee
+#[event]
+pub struct YieldRecipientModified {
+    pub yield_recipient: Pubkey,
+    pub performance_fee: u64,
+}
+
+/// emit when the yield accumulated in the pool is minted
+#[event]
+pub struct YieldCollected {
+    pub yield_recipient: Pubkey,
+    pub yield_amount: u64,
+    pub performance_fee_amount: u64,
+    pub total_supply: u64,
+}
+
+/// emit when the pool value drops and D is written down
+#[event]
+pub struct LossRecorded {
+    pub previous_total_supply: u64,
+    pub total_supply: u64,
+    pub pool_token_supply: u64,
+}
+
+/// emit when the insurance buffer burns pool tokens to cover a loss
+#[event]
+pub struct LossCovered {
+    pub amount: u64,
+    pub total_supply: u64,
+    pub pool_token_supply: u64,
+}
+
+/// emit when the treasury withdraws pool tokens from the insurance buffer
+#[event]
+pub struct InsuranceWithdrawn {
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+/// emit when admins modify the exchange rate staleness tolerance
+#[event]
+pub struct RateStalenessModified {
+    pub max_rate_staleness: u64,
+}
+
+/// emit when admins modify the allowed stake pool programs
+#[event]
+pub struct StakePoolProgramsModified {
+    pub stake_pool_programs: Vec<Pubkey>,
+}
+
 /// emit when admins modify pool amplitude
 #[event]
 pub struct AModified {
     pub value: u64,
     pub time: u64,
 }
+
+/// emit when admins stop an amplitude ramp and record the frozen value
+#[event]
+pub struct RampAStopped {
+    pub value: u64,
+    pub time: u64,
+}
+
+/// emit when admins add a liquid staking token to the pool
+#[event]
+pub struct TokenAdded {
+    pub token: Pubkey,
+    pub stake_pool: Pubkey,
+    pub rate_provider: RateProvider,
+    pub precision: u64,
+}
+
+/// emit when a legacy pool state is migrated and record the converted ramp
+#[event]
+pub struct PoolMigrated {
+    pub version: u8,
+    pub a: u64,
+    pub future_a: u64,
+    pub future_a_time: u64,
+}
+
+/// emit when the rate snapshot is refreshed by the crank
+#[event]
+pub struct RateRefreshed {
+    pub total_supply: u64,
+    pub total_sol_value: u64,
+    pub epoch: u64,
+}
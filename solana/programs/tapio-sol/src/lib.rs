@@ -1,10 +1,14 @@
 pub mod errors;
 pub mod event;
+pub mod lido;
+pub mod marinade;
 pub mod pool;
+pub mod rate;
 pub mod stake;
 pub mod state;
 
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::clock::DEFAULT_MS_PER_SLOT;
 use anchor_spl::{
     associated_token::AssociatedToken,
     metadata::{
@@ -39,41 +43,209 @@ pub mod tapio_sol {
         crate::initialize_token(ctx, token_name, token_symbol, token_uri)
     }
 
-    pub fn modify_a(ctx: Context<ModifyA>, a: u64, future_a_block: u64) -> Result<()> {
-        crate::modify_a(ctx, a, future_a_block)
+    pub fn add_token(ctx: Context<AddToken>, rate_provider: rate::RateProvider) -> Result<()> {
+        crate::add_token(ctx, rate_provider)
     }
 
-    pub fn mint(ctx: Context<MintShare>, amounts: Vec<u64>, min_mint_amount: u64) -> Result<()> {
+    pub fn set_fees(
+        ctx: Context<ModifyPool>,
+        mint_fee: u64,
+        swap_fee: u64,
+        redeem_fee: u64,
+    ) -> Result<()> {
+        crate::set_fees(ctx, mint_fee, swap_fee, redeem_fee)
+    }
+
+    pub fn set_fee_recipient(ctx: Context<SetFeeRecipient>, protocol_fee_share: u64) -> Result<()> {
+        crate::set_fee_recipient(ctx, protocol_fee_share)
+    }
+
+    pub fn set_yield_recipient(
+        ctx: Context<SetYieldRecipient>,
+        performance_fee: u64,
+    ) -> Result<()> {
+        crate::set_yield_recipient(ctx, performance_fee)
+    }
+
+    pub fn distribute_yield<'info>(
+        ctx: Context<'_, '_, '_, 'info, DistributeYield<'info>>,
+    ) -> Result<()> {
+        crate::distribute_yield(ctx)
+    }
+
+    pub fn cover_loss(ctx: Context<CoverLoss>) -> Result<()> {
+        crate::cover_loss(ctx)
+    }
+
+    pub fn withdraw_insurance<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawInsurance<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        crate::withdraw_insurance(ctx, amount)
+    }
+
+    pub fn set_rate_staleness(ctx: Context<ModifyPool>, max_rate_staleness: u64) -> Result<()> {
+        crate::set_rate_staleness(ctx, max_rate_staleness)
+    }
+
+    pub fn set_stake_pool_programs(
+        ctx: Context<ModifyPool>,
+        stake_pool_programs: Vec<Pubkey>,
+    ) -> Result<()> {
+        crate::set_stake_pool_programs(ctx, stake_pool_programs)
+    }
+
+    pub fn set_role(ctx: Context<ModifyPool>, role: state::Role, key: Pubkey) -> Result<()> {
+        crate::set_role(ctx, role, key)
+    }
+
+    pub fn set_paused(ctx: Context<ModifyPool>, paused: u8) -> Result<()> {
+        crate::set_paused(ctx, paused)
+    }
+
+    pub fn set_emergency(ctx: Context<ModifyPool>, emergency: bool) -> Result<()> {
+        crate::set_emergency(ctx, emergency)
+    }
+
+    pub fn propose_authority(ctx: Context<ModifyPool>, new_authority: Pubkey) -> Result<()> {
+        crate::propose_authority(ctx, new_authority)
+    }
+
+    pub fn accept_authority(ctx: Context<ModifyPool>) -> Result<()> {
+        crate::accept_authority(ctx)
+    }
+
+    pub fn cancel_authority_transfer(ctx: Context<ModifyPool>) -> Result<()> {
+        crate::cancel_authority_transfer(ctx)
+    }
+
+    pub fn modify_a(ctx: Context<ModifyA>, a: u64, future_a_time: u64) -> Result<()> {
+        crate::modify_a(ctx, a, future_a_time)
+    }
+
+    pub fn stop_ramp_a(ctx: Context<ModifyA>) -> Result<()> {
+        crate::stop_ramp_a(ctx)
+    }
+
+    pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+        crate::migrate_pool(ctx)
+    }
+
+    pub fn mint<'info>(
+        ctx: Context<'_, '_, '_, 'info, MintShare<'info>>,
+        amounts: Vec<u64>,
+        min_mint_amount: u64,
+    ) -> Result<()> {
         crate::mint(ctx, amounts, min_mint_amount)
     }
 
-    pub fn swap(ctx: Context<SwapToken>, i: u16, j: u16, dx: u64, min_dy: u64) -> Result<()> {
+    pub fn swap<'info>(
+        ctx: Context<'_, '_, '_, 'info, SwapToken<'info>>,
+        i: u16,
+        j: u16,
+        dx: u64,
+        min_dy: u64,
+    ) -> Result<()> {
         crate::swap(ctx, i, j, dx, min_dy)
     }
 
-    pub fn redeem_proportion(
-        ctx: Context<RedeemShare>,
+    pub fn swap_exact_out<'info>(
+        ctx: Context<'_, '_, '_, 'info, SwapToken<'info>>,
+        i: u16,
+        j: u16,
+        dy: u64,
+        max_dx: u64,
+    ) -> Result<()> {
+        crate::swap_exact_out(ctx, i, j, dy, max_dx)
+    }
+
+    pub fn redeem_proportion<'info>(
+        ctx: Context<'_, '_, '_, 'info, RedeemShare<'info>>,
         amount: u64,
         min_redeem_amounts: Vec<u64>,
     ) -> Result<()> {
         crate::redeem_proportion(ctx, amount, min_redeem_amounts)
     }
 
-    pub fn redeem_single(
-        ctx: Context<RedeemShare>,
+    pub fn redeem_single<'info>(
+        ctx: Context<'_, '_, '_, 'info, RedeemShare<'info>>,
         amount: u64,
         i: u16,
         min_redeem_amount: u64,
     ) -> Result<()> {
         crate::redeem_single(ctx, amount, i, min_redeem_amount)
     }
-}
 
-const MINT_SEED: &[u8] = b"mint";
-const HOLDER_SEED: &[u8] = b"holder";
-const SOL: &[u8] = b"sol";
+    pub fn redeem_multi<'info>(
+        ctx: Context<'_, '_, '_, 'info, RedeemShare<'info>>,
+        amounts: Vec<u64>,
+        max_redeem_amount: u64,
+    ) -> Result<()> {
+        crate::redeem_multi(ctx, amounts, max_redeem_amount)
+    }
+
+    pub fn quote_mint<'info>(
+        ctx: Context<'_, '_, '_, 'info, Quote<'info>>,
+        amounts: Vec<u64>,
+    ) -> Result<pool::MintResult> {
+        crate::quote_mint(ctx, amounts)
+    }
+
+    pub fn quote_swap<'info>(
+        ctx: Context<'_, '_, '_, 'info, Quote<'info>>,
+        i: u16,
+        j: u16,
+        dx: u64,
+    ) -> Result<pool::SwapResult> {
+        crate::quote_swap(ctx, i, j, dx)
+    }
+
+    pub fn quote_redeem_proportion<'info>(
+        ctx: Context<'_, '_, '_, 'info, Quote<'info>>,
+        amount: u64,
+    ) -> Result<pool::RedeemProportionResult> {
+        crate::quote_redeem_proportion(ctx, amount)
+    }
+
+    pub fn quote_redeem_single<'info>(
+        ctx: Context<'_, '_, '_, 'info, Quote<'info>>,
+        amount: u64,
+        i: u16,
+    ) -> Result<pool::RedeemSingleResult> {
+        crate::quote_redeem_single(ctx, amount, i)
+    }
+
+    pub fn get_virtual_price<'info>(ctx: Context<'_, '_, '_, 'info, Quote<'info>>) -> Result<u64> {
+        crate::get_virtual_price(ctx)
+    }
+
+    pub fn refresh_rate<'info>(ctx: Context<'_, '_, '_, 'info, RefreshRate<'info>>) -> Result<()> {
+        crate::refresh_rate(ctx)
+    }
+}
 
-const INIT_SOL: u64 = 100000000u64;
+/// seed of the pool token mint PDA, followed by the jitoSOL mint
+pub const MINT_SEED: &[u8] = b"mint";
+/// seed of the holder PDAs keeping the pool assets, see `holder_seeds`
+pub const HOLDER_SEED: &[u8] = b"holder";
+/// seed of the SOL holder after `HOLDER_SEED`
+pub const SOL: &[u8] = b"sol";
+
+/// lamports kept in the SOL holder outside of the pool balance
+pub const INIT_SOL: u64 = 100000000u64;
+const TOKEN_DECIMALS: u8 = 9u8;
+/// remaining accounts passed for every asset after jitoSOL, see `lst_accounts`
+pub const ACCOUNTS_PER_ASSET: usize = 3;
+/// maximum factor the amplitude may move by within one ramp
+const MAX_A_CHANGE: u64 = 10u64;
+/// minimum duration of a ramp, in seconds
+const MIN_RAMP_TIME: u64 = 86400u64;
+/// hard cap of the protocol share of the fees, 50%
+pub const MAX_PROTOCOL_FEE_SHARE: u64 = pool::FEE_PRECISION / 2;
+/// hard cap of the treasury share of the yield, 20%
+pub const MAX_PERFORMANCE_FEE: u64 = pool::FEE_PRECISION / 5;
+/// drop of D in lamports left to the rounding of the invariant before `LossRecorded` is emitted
+const LOSS_TOLERANCE: u64 = 10u64;
 
 /// initialize the jitoSOL pool with its fees and amplitude
 pub fn initialize_pool(
@@ -88,15 +260,17 @@ pub fn initialize_pool(
     if state.pool_initialized {
         return Err(errors::ErrorCode::AccountInitialized.into());
     }
-    let current_epoch = Clock::get()?.epoch;
+    validate_fees(mint_fee, swap_fee, redeem_fee)?;
+    let current_time = u64::try_from(Clock::get()?.unix_timestamp)
+        .map_err(|_| errors::ErrorCode::ArithmeticError)?;
     state.authority = ctx.accounts.payer.key();
     state.mint_fee = mint_fee;
     state.swap_fee = swap_fee;
     state.redeem_fee = redeem_fee;
     state.a = a;
-    state.a_block = current_epoch;
+    state.a_time = current_time;
     state.future_a = a;
-    state.future_a_block = current_epoch;
+    state.future_a_time = current_time;
     state.tokens = vec![
         ctx.accounts.system_program.key(),
         ctx.accounts.jito_sol_mint_account.key(),
@@ -105,8 +279,27 @@ pub fn initialize_pool(
     state.precisions = vec![1, 1];
     state.total_supply = 0;
     state.pool_initialized = true;
+    state.version = state::CURRENT_VERSION;
     state.bump = ctx.bumps.state_account;
-    state.stake_pool = ctx.accounts.stake_pool_account.key();
+    state.stake_pool_programs = default_stake_pool_programs();
+    rate::RateProvider::SplStakePool.validate(
+        &ctx.accounts.stake_pool_account,
+        &state.stake_pool_programs,
+        &ctx.accounts.jito_sol_mint_account.key(),
+    )?;
+    state.stake_pools = vec![Pubkey::default(), ctx.accounts.stake_pool_account.key()];
+    state.holder_bumps = vec![
+        ctx.bumps.sol_program_account,
+        ctx.bumps.jito_sol_program_token_account,
+    ];
+    state.rate_providers = vec![rate::RateProvider::Native, rate::RateProvider::SplStakePool];
+
+    let jito_sol_key = ctx.accounts.jito_sol_mint_account.key();
+    let (pool_mint, _) =
+        Pubkey::find_program_address(&[MINT_SEED, jito_sol_key.as_ref()], &crate::ID);
+    ctx.accounts
+        .rate_snapshot
+        .init(ctx.bumps.rate_snapshot, jito_sol_key, pool_mint);
 
     anchor_lang::system_program::transfer(
         CpiContext::new(
@@ -188,528 +381,1878 @@ pub fn initialize_token(
     Ok(())
 }
 
-/// modify the amplitude of the curve and its effective epoch
-pub fn modify_a(ctx: Context<ModifyA>, a: u64, future_a_block: u64) -> Result<()> {
+/// add a liquid staking token to the pool while it holds no liquidity
+pub fn add_token(ctx: Context<AddToken>, rate_provider: rate::RateProvider) -> Result<()> {
     let state = &mut ctx.accounts.state_account;
+    let token_key = ctx.accounts.token_mint_account.key();
+
     if state.authority != ctx.accounts.payer.key() {
         return Err(errors::ErrorCode::Unauthorized.into());
     }
-    let current_block = Clock::get()?.epoch;
-    let initial_a: u64 = pool::get_a(state.a, state.a_block, state.future_a, state.future_a_block)
+    if !state.pool_initialized || state.total_supply != 0 {
+        return Err(errors::ErrorCode::ArgumentValidationFailure.into());
+    }
+    if state.tokens.len() >= state::MAX_TOKENS || state.tokens.contains(&token_key) {
+        return Err(errors::ErrorCode::ArgumentValidationFailure.into());
+    }
+    if rate_provider == rate::RateProvider::Native {
+        return Err(errors::ErrorCode::ArgumentValidationFailure.into());
+    }
+    rate_provider.validate(
+        &ctx.accounts.stake_pool_account,
+        &state.stake_pool_programs,
+        &token_key,
+    )?;
+    let decimals = ctx.accounts.token_mint_account.decimals;
+    if decimals > TOKEN_DECIMALS {
+        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    }
+    let precision: u64 = 10u64
+        .checked_pow(u32::from(TOKEN_DECIMALS - decimals))
         .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
 
-    if initial_a > a || current_block > future_a_block {
+    state.tokens.push(token_key);
+    state.balances.push(0);
+    state.precisions.push(precision);
+    state
+        .stake_pools
+        .push(ctx.accounts.stake_pool_account.key());
+    state.holder_bumps.push(ctx.bumps.token_program_account);
+    state.rate_providers.push(rate_provider);
+
+    emit!(event::TokenAdded {
+        token: token_key,
+        stake_pool: ctx.accounts.stake_pool_account.key(),
+        rate_provider,
+        precision,
+    });
+    Ok(())
+}
+
+/// update the mint, swap and redeem fees of the pool
+pub fn set_fees(
+    ctx: Context<ModifyPool>,
+    mint_fee: u64,
+    swap_fee: u64,
+    redeem_fee: u64,
+) -> Result<()> {
+    let state = &mut ctx.accounts.state_account;
+    if !state.has_role(state::Role::FeeManager, &ctx.accounts.payer.key()) {
+        return Err(errors::ErrorCode::Unauthorized.into());
+    }
+    validate_fees(mint_fee, swap_fee, redeem_fee)?;
+
+    state.mint_fee = mint_fee;
+    state.swap_fee = swap_fee;
+    state.redeem_fee = redeem_fee;
+    emit!(event::FeesModified {
+        mint_fee,
+        swap_fee,
+        redeem_fee,
+    });
+    Ok(())
+}
+
+/// update the pool token account receiving the protocol share of the fees, by the authority
+/// alone since the fee manager could otherwise route the fees to itself
+pub fn set_fee_recipient(ctx: Context<SetFeeRecipient>, protocol_fee_share: u64) -> Result<()> {
+    let state = &mut ctx.accounts.state_account;
+    if state.authority != ctx.accounts.payer.key() {
+        return Err(errors::ErrorCode::Unauthorized.into());
+    }
+    if protocol_fee_share > MAX_PROTOCOL_FEE_SHARE {
+        return Err(errors::ErrorCode::ArgumentValidationFailure.into());
+    }
+
+    state.fee_recipient = ctx.accounts.fee_recipient_account.key();
+    state.protocol_fee_share = protocol_fee_share;
+    emit!(event::FeeRecipientModified {
+        fee_recipient: state.fee_recipient,
+        protocol_fee_share,
+    });
+    Ok(())
+}
+
+/// update the pool token account receiving the yield and the treasury share of it, by the
+/// authority alone like `set_fee_recipient`
+pub fn set_yield_recipient(ctx: Context<SetYieldRecipient>, performance_fee: u64) -> Result<()> {
+    let state = &mut ctx.accounts.state_account;
+    if state.authority != ctx.accounts.payer.key() {
+        return Err(errors::ErrorCode::Unauthorized.into());
+    }
+    if performance_fee > MAX_PERFORMANCE_FEE {
         return Err(errors::ErrorCode::ArgumentValidationFailure.into());
     }
 
-    state.a = initial_a;
-    state.a_block = current_block;
-    state.future_a = a;
-    state.future_a_block = future_a_block;
-    emit!(event::AModified {
-        value: a,
-        time: future_a_block,
+    state.yield_recipient = ctx.accounts.yield_recipient_account.key();
+    state.performance_fee = performance_fee;
+    emit!(event::YieldRecipientModified {
+        yield_recipient: state.yield_recipient,
+        performance_fee,
     });
     Ok(())
 }
 
-/// mint the jitoSOL pool tokens with respect to the minimum mint amount
-pub fn mint(ctx: Context<MintShare>, amounts: Vec<u64>, min_mint_amount: u64) -> Result<()> {
+/// collect the fees and yields, then mint the pool tokens worth the yield recorded by
+/// `collect_fees` but not minted yet, the performance fee goes to the treasury and the rest
+/// to the yield recipient
+///
+/// The liquidity provider share of the fees stays in D and raises the worth of every pool token.
+pub fn distribute_yield<'info>(
+    ctx: Context<'_, '_, '_, 'info, DistributeYield<'info>>,
+) -> Result<()> {
     let state = &mut ctx.accounts.state_account;
     let jito_sol_key = ctx.accounts.jito_sol_mint_account.key();
 
-    if ctx.accounts.jito_sol_user_token_account.mint != state.tokens[1] {
-        return Err(errors::ErrorCode::TokenValidationFailure.into());
-    }
     if jito_sol_key != state.tokens[1] {
         return Err(errors::ErrorCode::TokenValidationFailure.into());
     }
-    if ctx.accounts.stake_pool_account.key() != state.stake_pool {
+    if ctx.accounts.stake_pool_account.key() != state.stake_pools[1] {
         return Err(errors::ErrorCode::TokenValidationFailure.into());
     }
-    collect_fees(
-        &ctx.accounts.sol_program_account,
-        &ctx.accounts.jito_sol_program_token_account,
-        state,
-        &ctx.accounts.stake_pool_account,
-    )?;
-
-    let pool::MintResult {
-        mint_amount,
-        fee_amount,
-        balances,
-        total_supply,
-    } = pool::get_mint_amount(state, &amounts)?;
-
-    let a: u64 = pool::get_a(state.a, state.a_block, state.future_a, state.future_a_block)
-        .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
-    if mint_amount < min_mint_amount {
-        return Err(errors::ErrorCode::MintUnderMin.into());
-    }
+    let jito_sol_program_token_account = ctx
+        .accounts
+        .jito_sol_program_token_account
+        .to_account_info();
+    let sol_program_account = ctx.accounts.sol_program_account.to_account_info();
     let token_accounts = TokenAccounts {
         token_program: ctx.accounts.token_program.to_account_info(),
         system_program: ctx.accounts.system_program.to_account_info(),
         state_account: state.to_account_info(),
-        payer_account: ctx.accounts.payer.to_account_info(),
-        sol_user_account: ctx.accounts.sol_user_account.to_account_info(),
-        jito_sol_user_token_account: ctx.accounts.jito_sol_user_token_account.to_account_info(),
-        sol_program_account: ctx.accounts.sol_program_account.to_account_info(),
-        jito_sol_program_token_account: ctx
-            .accounts
-            .jito_sol_program_token_account
-            .to_account_info(),
+        // the yield is minted, no pool asset is transferred
+        payer_account: state.to_account_info(),
+        sol_user_account: sol_program_account.clone(),
+        sol_program_account,
+        lst_accounts: lst_accounts(
+            state,
+            LstAccounts {
+                mint: jito_sol_key,
+                user_token_account: jito_sol_program_token_account.clone(),
+                program_token_account: jito_sol_program_token_account,
+                rate: state.rate_providers[1].read(
+                    &ctx.accounts.stake_pool_account.to_account_info(),
+                    &state.stake_pool_programs,
+                )?,
+            },
+            ctx.remaining_accounts,
+        )?,
+        pool_key: jito_sol_key,
+        holder_bumps: state.holder_bumps.clone(),
     };
-
-    for (i, amount) in amounts.iter().enumerate() {
-        if *amount == 0u64 {
-            continue;
-        }
-        transfer_to_program(
-            i,
-            token_accounts.clone(),
-            *amount,
-            &ctx.accounts.stake_pool_account,
-        )?;
+    let pool_token_supply = ctx.accounts.mint_account.supply;
+    collect_fees(&token_accounts, state, pool_token_supply, false)?;
+    if state.loss_mode {
+        return Err(errors::ErrorCode::PoolImbalanced.into());
+    }
+    if state.pending_yield == 0 {
+        return Err(errors::ErrorCode::ArgumentValidationFailure.into());
     }
 
+    let yield_amount = pool::get_pool_token_amount(state, pool_token_supply, state.pending_yield)?;
+    let performance_fee_amount = pool::get_protocol_fee(yield_amount, state.performance_fee)?;
+
     let mint_signer_seeds: &[&[&[u8]]] =
         &[&[MINT_SEED, jito_sol_key.as_ref(), &[ctx.bumps.mint_account]]];
-    anchor_spl::token::mint_to(
-        CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            MintTo {
-                authority: ctx.accounts.mint_account.to_account_info(),
-                to: ctx.accounts.mint_token_account.to_account_info(),
-                mint: ctx.accounts.mint_account.to_account_info(),
-            },
-            mint_signer_seeds,
-        ),
-        mint_amount,
-    )?;
+    if performance_fee_amount > 0 {
+        let treasury_token_account =
+            ctx.accounts
+                .treasury_token_account
+                .as_ref()
+                .ok_or::<errors::ErrorCode>(errors::ErrorCode::TokenValidationFailure)?;
+        anchor_spl::token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    authority: ctx.accounts.mint_account.to_account_info(),
+                    to: treasury_token_account.to_account_info(),
+                    mint: ctx.accounts.mint_account.to_account_info(),
+                },
+                mint_signer_seeds,
+            ),
+            performance_fee_amount,
+        )?;
+    }
+    let recipient_amount = yield_amount
+        .checked_sub(performance_fee_amount)
+        .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
+    if recipient_amount > 0 {
+        anchor_spl::token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    authority: ctx.accounts.mint_account.to_account_info(),
+                    to: ctx.accounts.yield_recipient_account.to_account_info(),
+                    mint: ctx.accounts.mint_account.to_account_info(),
+                },
+                mint_signer_seeds,
+            ),
+            recipient_amount,
+        )?;
+    }
 
-    state.total_supply = total_supply;
-    state.balances = balances;
-    emit!(event::Minted {
-        minter: ctx.accounts.payer.key(),
-        a,
-        input_amounts: amounts,
-        min_output_amount: min_mint_amount,
-        balances: state.balances.clone(),
+    // the minted pool tokens back the yield from now on
+    state.pending_yield = 0;
+
+    update_rate_snapshot(
+        &mut ctx.accounts.rate_snapshot,
+        &mut ctx.accounts.mint_account,
+        state.backing_value(),
+    )?;
+    emit!(event::YieldCollected {
+        yield_recipient: state.yield_recipient,
+        yield_amount,
+        performance_fee_amount,
         total_supply: state.total_supply,
-        fee_amount,
-        output_amount: mint_amount,
     });
-
     Ok(())
 }
 
-/// swap from/to jitoSOL with respect to its minimum output
-pub fn swap(ctx: Context<SwapToken>, i: u16, j: u16, dx: u64, min_dy: u64) -> Result<()> {
+/// burn pool tokens held by the pool state in its insurance buffer to cover a recorded loss,
+/// by the treasury while the pool is in loss mode
+pub fn cover_loss(ctx: Context<CoverLoss>) -> Result<()> {
     let state = &mut ctx.accounts.state_account;
     let jito_sol_key = ctx.accounts.jito_sol_mint_account.key();
 
-    if ctx.accounts.jito_sol_user_token_account.mint != state.tokens[1] {
-        return Err(errors::ErrorCode::TokenValidationFailure.into());
-    }
-    if jito_sol_key != state.tokens[1] {
-        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    if !state.has_role(state::Role::Treasury, &ctx.accounts.payer.key()) {
+        return Err(errors::ErrorCode::Unauthorized.into());
     }
-    if ctx.accounts.stake_pool_account.key() != state.stake_pool {
-        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    if !state.loss_mode {
+        return Err(errors::ErrorCode::ArgumentValidationFailure.into());
     }
-    collect_fees(
-        &ctx.accounts.sol_program_account,
-        &ctx.accounts.jito_sol_program_token_account,
-        state,
-        &ctx.accounts.stake_pool_account,
-    )?;
 
-    let pool::SwapResult {
-        dx: _,
-        dy,
-        y,
-        balance_i,
-    } = pool::get_swap_amount(state, usize::from(i), usize::from(j), dx)?;
-    if y < min_dy {
-        return Err(errors::ErrorCode::SwapUnderMin.into());
+    let deficit = ctx
+        .accounts
+        .mint_account
+        .supply
+        .saturating_sub(state.total_supply);
+    let amount = deficit.min(ctx.accounts.insurance_token_account.amount);
+    if amount == 0 {
+        return Err(errors::ErrorCode::ArgumentValidationFailure.into());
     }
 
-    state.balances[usize::from(i)] = balance_i;
-    state.balances[usize::from(j)] = y;
-
-    let sol_program_seeds: &[&[&[u8]]] = &[&[
-        HOLDER_SEED,
-        SOL,
-        jito_sol_key.as_ref(),
-        &[ctx.bumps.sol_program_account],
-    ]];
-    let jito_sol_pub_key = ctx.accounts.jito_sol_mint_account.key();
-    let jito_sol_program_seeds: &[&[&[u8]]] = &[&[
-        HOLDER_SEED,
-        jito_sol_pub_key.as_ref(),
-        &[ctx.bumps.jito_sol_program_token_account],
-    ]];
-    let token_accounts = TokenAccounts {
-        token_program: ctx.accounts.token_program.to_account_info(),
-        system_program: ctx.accounts.system_program.to_account_info(),
-        state_account: state.to_account_info(),
-        payer_account: ctx.accounts.payer.to_account_info(),
-        sol_user_account: ctx.accounts.sol_user_account.to_account_info(),
-        jito_sol_user_token_account: ctx.accounts.jito_sol_user_token_account.to_account_info(),
-        sol_program_account: ctx.accounts.sol_program_account.to_account_info(),
-        jito_sol_program_token_account: ctx
-            .accounts
-            .jito_sol_program_token_account
-            .to_account_info(),
-    };
-    transfer_to_program(
-        usize::from(i),
-        token_accounts.clone(),
-        dx,
-        &ctx.accounts.stake_pool_account,
-    )?;
-    transfer_from_program(
-        usize::from(j),
-        token_accounts,
-        dy,
-        sol_program_seeds,
-        jito_sol_program_seeds,
-        &ctx.accounts.stake_pool_account,
+    let state_signer_seeds: &[&[&[u8]]] =
+        &[&[state::PoolState::SEED, jito_sol_key.as_ref(), &[state.bump]]];
+    anchor_spl::token::burn(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                from: ctx.accounts.insurance_token_account.to_account_info(),
+                mint: ctx.accounts.mint_account.to_account_info(),
+                authority: state.to_account_info(),
+            },
+            state_signer_seeds,
+        ),
+        amount,
     )?;
 
-    let a: u64 = pool::get_a(state.a, state.a_block, state.future_a, state.future_a_block)
+    let pool_token_supply = ctx
+        .accounts
+        .mint_account
+        .supply
+        .checked_sub(amount)
         .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
-
-    emit!(event::TokenSwapped {
-        swapper: ctx.accounts.payer.key(),
-        a,
-        input_asset: state.tokens[usize::from(i)],
-        output_asset: state.tokens[usize::from(j)],
-        input_amount: dx,
-        min_output_amount: min_dy,
-        balances: state.balances.clone(),
+    state.loss_mode = state.total_supply < pool_token_supply;
+    update_rate_snapshot(
+        &mut ctx.accounts.rate_snapshot,
+        &mut ctx.accounts.mint_account,
+        state.backing_value(),
+    )?;
+    emit!(event::LossCovered {
+        amount,
         total_supply: state.total_supply,
-        output_amount: dy,
+        pool_token_supply,
     });
-
     Ok(())
 }
 
-/// redeem proportionally the pool token to SOL and jitoSOL with respect to the minimum amount
-pub fn redeem_proportion(
-    ctx: Context<RedeemShare>,
+/// move pool tokens out of the insurance buffer, by the treasury while no loss is uncovered
+///
+/// The fees and yields are collected first, a loss since the last operation puts the pool in
+/// loss mode and keeps the buffer in place.
+pub fn withdraw_insurance<'info>(
+    ctx: Context<'_, '_, '_, 'info, WithdrawInsurance<'info>>,
     amount: u64,
-    min_redeem_amounts: Vec<u64>,
 ) -> Result<()> {
     let state = &mut ctx.accounts.state_account;
     let jito_sol_key = ctx.accounts.jito_sol_mint_account.key();
 
-    if ctx.accounts.jito_sol_user_token_account.mint != state.tokens[1] {
-        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    if !state.has_role(state::Role::Treasury, &ctx.accounts.payer.key()) {
+        return Err(errors::ErrorCode::Unauthorized.into());
     }
     if jito_sol_key != state.tokens[1] {
         return Err(errors::ErrorCode::TokenValidationFailure.into());
     }
-    if ctx.accounts.stake_pool_account.key() != state.stake_pool {
+    if ctx.accounts.stake_pool_account.key() != state.stake_pools[1] {
         return Err(errors::ErrorCode::TokenValidationFailure.into());
     }
-    collect_fees(
-        &ctx.accounts.sol_program_account,
-        &ctx.accounts.jito_sol_program_token_account,
-        state,
-        &ctx.accounts.stake_pool_account,
-    )?;
-
-    let pool::RedeemProportionResult {
-        amounts,
-        balances,
-        fee_amount,
-        total_supply,
-        redeem_amount,
-    } = pool::get_redeem_proportion_amount(state, amount)?;
-
-    let sol_program_seeds: &[&[&[u8]]] = &[&[
-        HOLDER_SEED,
-        SOL,
-        jito_sol_key.as_ref(),
-        &[ctx.bumps.sol_program_account],
-    ]];
-    let jito_sol_pub_key = ctx.accounts.jito_sol_mint_account.key();
-    let jito_sol_program_seeds: &[&[&[u8]]] = &[&[
-        HOLDER_SEED,
-        jito_sol_pub_key.as_ref(),
-        &[ctx.bumps.jito_sol_program_token_account],
-    ]];
+    let jito_sol_program_token_account = ctx
+        .accounts
+        .jito_sol_program_token_account
+        .to_account_info();
+    let sol_program_account = ctx.accounts.sol_program_account.to_account_info();
     let token_accounts = TokenAccounts {
         token_program: ctx.accounts.token_program.to_account_info(),
         system_program: ctx.accounts.system_program.to_account_info(),
         state_account: state.to_account_info(),
+        // only pool tokens are transferred, no pool asset
         payer_account: ctx.accounts.payer.to_account_info(),
-        sol_user_account: ctx.accounts.sol_user_account.to_account_info(),
-        jito_sol_user_token_account: ctx.accounts.jito_sol_user_token_account.to_account_info(),
-        sol_program_account: ctx.accounts.sol_program_account.to_account_info(),
-        jito_sol_program_token_account: ctx
-            .accounts
-            .jito_sol_program_token_account
-            .to_account_info(),
+        sol_user_account: sol_program_account.clone(),
+        sol_program_account,
+        lst_accounts: lst_accounts(
+            state,
+            LstAccounts {
+                mint: jito_sol_key,
+                user_token_account: jito_sol_program_token_account.clone(),
+                program_token_account: jito_sol_program_token_account,
+                rate: state.rate_providers[1].read(
+                    &ctx.accounts.stake_pool_account.to_account_info(),
+                    &state.stake_pool_programs,
+                )?,
+            },
+            ctx.remaining_accounts,
+        )?,
+        pool_key: jito_sol_key,
+        holder_bumps: state.holder_bumps.clone(),
     };
-    for i in 0..amounts.len() {
-        if amounts[i] < min_redeem_amounts[i] {
-            return Err(errors::ErrorCode::RedeemUnderMin.into());
-        }
-
-        transfer_from_program(
-            i,
-            token_accounts.clone(),
-            amounts[i],
-            sol_program_seeds,
-            jito_sol_program_seeds,
-            &ctx.accounts.stake_pool_account,
-        )?;
+    collect_fees(
+        &token_accounts,
+        state,
+        ctx.accounts.mint_account.supply,
+        false,
+    )?;
+    if state.loss_mode {
+        return Err(errors::ErrorCode::PoolImbalanced.into());
+    }
+    if amount == 0 || amount > ctx.accounts.insurance_token_account.amount {
+        return Err(errors::ErrorCode::ArgumentValidationFailure.into());
     }
 
-    let cpi_context = CpiContext::new(
-        ctx.accounts.token_program.to_account_info(),
-        Burn {
-            from: ctx.accounts.mint_token_account.to_account_info(),
-            mint: ctx.accounts.mint_account.to_account_info(),
-            authority: ctx.accounts.payer.to_account_info(),
-        },
-    );
-    anchor_spl::token::burn(cpi_context, redeem_amount)?;
-
-    state.total_supply = total_supply;
-    state.balances = balances;
-
-    let a: u64 = pool::get_a(state.a, state.a_block, state.future_a, state.future_a_block)
-        .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
+    let state_signer_seeds: &[&[&[u8]]] =
+        &[&[state::PoolState::SEED, jito_sol_key.as_ref(), &[state.bump]]];
+    anchor_spl::token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.insurance_token_account.to_account_info(),
+                to: ctx.accounts.destination_token_account.to_account_info(),
+                authority: state.to_account_info(),
+            },
+            state_signer_seeds,
+        ),
+        amount,
+    )?;
 
-    emit!(event::RedeemedProportion {
-        redeemer: ctx.accounts.payer.key(),
-        a,
-        input_amount: amount,
-        min_output_amounts: min_redeem_amounts,
-        balances: state.balances.clone(),
-        total_supply: state.total_supply,
-        fee_amount,
-        output_amounts: amounts,
+    emit!(event::InsuranceWithdrawn {
+        destination: ctx.accounts.destination_token_account.key(),
+        amount,
     });
+    Ok(())
+}
+
+/// update the number of epochs an exchange rate may lag behind
+pub fn set_rate_staleness(ctx: Context<ModifyPool>, max_rate_staleness: u64) -> Result<()> {
+    let state = &mut ctx.accounts.state_account;
+    if state.authority != ctx.accounts.payer.key() {
+        return Err(errors::ErrorCode::Unauthorized.into());
+    }
 
+    state.max_rate_staleness = max_rate_staleness;
+    emit!(event::RateStalenessModified { max_rate_staleness });
     Ok(())
 }
 
-/// redeem the pool token to SOL or jitoSOL with respect to the minimum amount
-pub fn redeem_single(
-    ctx: Context<RedeemShare>,
-    amount: u64,
-    i: u16,
-    min_redeem_amount: u64,
+/// update the programs allowed to own the SPL stake pool accounts of the pool assets
+///
+/// The staking information accounts of every asset after SOL are passed as remaining
+/// accounts, the SPL stake pools among them must stay owned by an allowed program.
+pub fn set_stake_pool_programs(
+    ctx: Context<ModifyPool>,
+    stake_pool_programs: Vec<Pubkey>,
 ) -> Result<()> {
     let state = &mut ctx.accounts.state_account;
-    let jito_sol_key = ctx.accounts.jito_sol_mint_account.key();
+    if state.authority != ctx.accounts.payer.key() {
+        return Err(errors::ErrorCode::Unauthorized.into());
+    }
+    if stake_pool_programs.is_empty() || stake_pool_programs.len() > state::MAX_STAKE_POOL_PROGRAMS
+    {
+        return Err(errors::ErrorCode::ArgumentValidationFailure.into());
+    }
+    if ctx.remaining_accounts.len() != state.stake_pools.len() - 1 {
+        return Err(errors::ErrorCode::ArgumentValidationFailure.into());
+    }
+    for (i, account) in ctx.remaining_accounts.iter().enumerate() {
+        if account.key() != state.stake_pools[i + 1] {
+            return Err(errors::ErrorCode::TokenValidationFailure.into());
+        }
+        if let rate::RateProvider::SplStakePool | rate::RateProvider::SanctumSplStakePool =
+            state.rate_providers[i + 1]
+        {
+            if !stake_pool_programs.contains(account.owner) {
+                return Err(errors::ErrorCode::TokenValidationFailure.into());
+            }
+        }
+    }
 
-    if ctx.accounts.jito_sol_user_token_account.mint != state.tokens[1] {
-        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    state.stake_pool_programs = stake_pool_programs;
+    emit!(event::StakePoolProgramsModified {
+        stake_pool_programs: state.stake_pool_programs.clone(),
+    });
+    Ok(())
+}
+
+/// assign an admin role to a key, or revoke it with the default key
+pub fn set_role(ctx: Context<ModifyPool>, role: state::Role, key: Pubkey) -> Result<()> {
+    let state = &mut ctx.accounts.state_account;
+    if state.authority != ctx.accounts.payer.key() {
+        return Err(errors::ErrorCode::Unauthorized.into());
     }
-    if jito_sol_key != state.tokens[1] {
-        return Err(errors::ErrorCode::TokenValidationFailure.into());
+
+    match role {
+        state::Role::FeeManager => state.fee_manager = key,
+        state::Role::CurveManager => state.curve_manager = key,
+        state::Role::Guardian => state.guardian = key,
+        state::Role::Treasury => state.treasury = key,
     }
-    if ctx.accounts.stake_pool_account.key() != state.stake_pool {
-        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    emit!(event::RoleUpdated { role, key });
+    Ok(())
+}
+
+/// update the paused operations, the guardian may only pause more of them
+pub fn set_paused(ctx: Context<ModifyPool>, paused: u8) -> Result<()> {
+    let state = &mut ctx.accounts.state_account;
+    let payer_key = ctx.accounts.payer.key();
+    if !state.has_role(state::Role::Guardian, &payer_key) {
+        return Err(errors::ErrorCode::Unauthorized.into());
+    }
+    if paused & !state::PAUSE_ALL != 0 {
+        return Err(errors::ErrorCode::ArgumentValidationFailure.into());
+    }
+    if state.authority != payer_key && paused & state.paused != state.paused {
+        return Err(errors::ErrorCode::Unauthorized.into());
     }
-    collect_fees(
-        &ctx.accounts.sol_program_account,
-        &ctx.accounts.jito_sol_program_token_account,
-        state,
-        &ctx.accounts.stake_pool_account,
-    )?;
 
-    let pool::RedeemSingleResult {
-        dy,
-        fee_amount,
-        total_supply,
-        balances,
-        redeem_amount,
-    } = pool::get_redeem_single_amount(state, amount, usize::from(i))?;
-    if dy < min_redeem_amount {
-        return Err(errors::ErrorCode::RedeemUnderMin.into());
+    state.paused = paused;
+    emit!(event::PauseUpdated { paused });
+    Ok(())
+}
+
+/// enter or leave emergency mode, the guardian may only enter it
+pub fn set_emergency(ctx: Context<ModifyPool>, emergency: bool) -> Result<()> {
+    let state = &mut ctx.accounts.state_account;
+    let payer_key = ctx.accounts.payer.key();
+    if !state.has_role(state::Role::Guardian, &payer_key) {
+        return Err(errors::ErrorCode::Unauthorized.into());
+    }
+    if state.authority != payer_key && !emergency {
+        return Err(errors::ErrorCode::Unauthorized.into());
     }
 
-    let sol_program_seeds: &[&[&[u8]]] = &[&[
-        HOLDER_SEED,
-        SOL,
-        jito_sol_key.as_ref(),
-        &[ctx.bumps.sol_program_account],
-    ]];
-    let jito_sol_pub_key = ctx.accounts.jito_sol_mint_account.key();
-    let jito_sol_program_seeds: &[&[&[u8]]] = &[&[
-        HOLDER_SEED,
-        jito_sol_pub_key.as_ref(),
-        &[ctx.bumps.jito_sol_program_token_account],
-    ]];
-    let token_accounts = TokenAccounts {
-        token_program: ctx.accounts.token_program.to_account_info(),
-        system_program: ctx.accounts.system_program.to_account_info(),
-        state_account: state.to_account_info(),
-        payer_account: ctx.accounts.payer.to_account_info(),
-        sol_user_account: ctx.accounts.sol_user_account.to_account_info(),
-        jito_sol_user_token_account: ctx.accounts.jito_sol_user_token_account.to_account_info(),
-        sol_program_account: ctx.accounts.sol_program_account.to_account_info(),
-        jito_sol_program_token_account: ctx
-            .accounts
-            .jito_sol_program_token_account
-            .to_account_info(),
-    };
+    state.emergency = emergency;
+    emit!(event::EmergencyUpdated { emergency });
+    Ok(())
+}
 
-    transfer_from_program(
-        usize::from(i),
-        token_accounts,
-        dy,
-        sol_program_seeds,
-        jito_sol_program_seeds,
-        &ctx.accounts.stake_pool_account,
-    )?;
+/// propose a new authority, which only takes over once it accepts
+pub fn propose_authority(ctx: Context<ModifyPool>, new_authority: Pubkey) -> Result<()> {
+    let state = &mut ctx.accounts.state_account;
+    if state.authority != ctx.accounts.payer.key() {
+        return Err(errors::ErrorCode::Unauthorized.into());
+    }
+    if new_authority == Pubkey::default() || new_authority == state.authority {
+        return Err(errors::ErrorCode::ArgumentValidationFailure.into());
+    }
 
-    let cpi_context = CpiContext::new(
-        ctx.accounts.token_program.to_account_info(),
-        Burn {
-            from: ctx.accounts.mint_token_account.to_account_info(),
-            mint: ctx.accounts.mint_account.to_account_info(),
-            authority: ctx.accounts.payer.to_account_info(),
-        },
-    );
-    anchor_spl::token::burn(cpi_context, redeem_amount)?;
+    state.pending_authority = Some(new_authority);
+    emit!(event::AuthorityProposed {
+        authority: state.authority,
+        pending_authority: new_authority,
+    });
+    Ok(())
+}
 
-    let mut amounts: Vec<u64> = Vec::new();
-    for idx in 0..state.balances.len() {
-        if idx == usize::from(i) {
-            amounts.push(dy);
-        } else {
-            amounts.push(0u64);
-        }
+/// take over the pool, signed by the pending authority
+pub fn accept_authority(ctx: Context<ModifyPool>) -> Result<()> {
+    let state = &mut ctx.accounts.state_account;
+    if state.pending_authority != Some(ctx.accounts.payer.key()) {
+        return Err(errors::ErrorCode::Unauthorized.into());
     }
 
-    state.total_supply = total_supply;
-    state.balances = balances;
+    let previous_authority = state.authority;
+    state.authority = ctx.accounts.payer.key();
+    state.pending_authority = None;
+    emit!(event::AuthorityTransferred {
+        previous_authority,
+        authority: state.authority,
+    });
+    Ok(())
+}
+
+/// drop the pending authority proposal
+pub fn cancel_authority_transfer(ctx: Context<ModifyPool>) -> Result<()> {
+    let state = &mut ctx.accounts.state_account;
+    if state.authority != ctx.accounts.payer.key() {
+        return Err(errors::ErrorCode::Unauthorized.into());
+    }
+    let pending_authority = state
+        .pending_authority
+        .take()
+        .ok_or(errors::ErrorCode::ArgumentValidationFailure)?;
+
+    emit!(event::AuthorityTransferCancelled { pending_authority });
+    Ok(())
+}
 
-    let a: u64 = pool::get_a(state.a, state.a_block, state.future_a, state.future_a_block)
+/// ramp the amplitude of the curve up or down until its effective time
+pub fn modify_a(ctx: Context<ModifyA>, a: u64, future_a_time: u64) -> Result<()> {
+    let state = &mut ctx.accounts.state_account;
+    if !state.has_role(state::Role::CurveManager, &ctx.accounts.payer.key()) {
+        return Err(errors::ErrorCode::Unauthorized.into());
+    }
+    let current_time = u64::try_from(Clock::get()?.unix_timestamp)
+        .map_err(|_| errors::ErrorCode::ArithmeticError)?;
+    let initial_a: u64 = pool::get_a(state.a, state.a_time, state.future_a, state.future_a_time)
         .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
 
-    emit!(event::RedeemedSingle {
-        redeemer: ctx.accounts.payer.key(),
-        a,
-        input_amount: amount,
-        output_asset: state.tokens[usize::from(i)],
-        min_output_amount: min_redeem_amount,
-        balances: state.balances.clone(),
-        total_supply: state.total_supply,
-        fee_amount,
-        output_amount: dy,
+    let min_future_a_time = current_time
+        .checked_add(MIN_RAMP_TIME)
+        .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
+    if a == 0 || future_a_time < min_future_a_time {
+        return Err(errors::ErrorCode::ArgumentValidationFailure.into());
+    }
+    let (low_a, high_a) = if a < initial_a {
+        (a, initial_a)
+    } else {
+        (initial_a, a)
+    };
+    let max_a = low_a
+        .checked_mul(MAX_A_CHANGE)
+        .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
+    if high_a > max_a {
+        return Err(errors::ErrorCode::ArgumentValidationFailure.into());
+    }
+
+    state.a = initial_a;
+    state.a_time = current_time;
+    state.future_a = a;
+    state.future_a_time = future_a_time;
+    emit!(event::AModified {
+        value: a,
+        time: future_a_time,
     });
     Ok(())
 }
 
-pub fn transfer_from_program(
-    index: usize,
-    token_accounts: TokenAccounts,
-    amount: u64,
-    sol_program_seeds: &[&[&[u8]]],
-    jito_sol_program_seeds: &[&[&[u8]]],
-    stake_pool: &stake::StakePool,
-) -> Result<()> {
-    if index == 0 {
-        anchor_lang::system_program::transfer(
-            CpiContext::new(
-                token_accounts.system_program,
-                anchor_lang::system_program::Transfer {
-                    from: token_accounts.sol_program_account,
-                    to: token_accounts.sol_user_account,
-                },
-            )
-            .with_signer(sol_program_seeds),
-            amount,
-        )?;
-    } else {
-        let amount_converted = convert_balance_back_from(amount, stake_pool)?;
-        anchor_spl::token::transfer(
-            CpiContext::new_with_signer(
-                token_accounts.token_program,
-                Transfer {
-                    from: token_accounts.jito_sol_program_token_account.clone(),
-                    to: token_accounts.jito_sol_user_token_account,
-                    authority: token_accounts.jito_sol_program_token_account,
-                },
-                jito_sol_program_seeds,
-            ),
-            amount_converted,
-        )?;
+/// stop an in-progress ramp and freeze the amplitude at its current value
+pub fn stop_ramp_a(ctx: Context<ModifyA>) -> Result<()> {
+    let state = &mut ctx.accounts.state_account;
+    if !state.has_role(state::Role::CurveManager, &ctx.accounts.payer.key()) {
+        return Err(errors::ErrorCode::Unauthorized.into());
     }
+    let current_time = u64::try_from(Clock::get()?.unix_timestamp)
+        .map_err(|_| errors::ErrorCode::ArithmeticError)?;
+    let current_a: u64 = pool::get_a(state.a, state.a_time, state.future_a, state.future_a_time)
+        .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
+
+    state.a = current_a;
+    state.a_time = current_time;
+    state.future_a = current_a;
+    state.future_a_time = current_time;
+    emit!(event::RampAStopped {
+        value: current_a,
+        time: current_time,
+    });
     Ok(())
 }
 
-pub fn transfer_to_program(
-    index: usize,
-    token_accounts: TokenAccounts,
-    amount: u64,
-    stake_pool: &stake::StakePool,
-) -> Result<()> {
-    if index == 0 {
+/// rewrite a legacy pool state into the current layout
+///
+/// The epoch-based ramp is converted to unix timestamps: the ramp restarts from
+/// the amplitude reached in the current epoch and ends at the estimated start
+/// time of its target epoch. Legacy fees above `pool::MAX_FEE` fail the migration,
+/// like `set_fees`. The pool needs `refresh_rate` to create its rate snapshot before
+/// any other operation.
+pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+    let state_account = &ctx.accounts.state_account;
+    if *state_account.owner != crate::ID {
+        return Err(errors::ErrorCode::ArgumentValidationFailure.into());
+    }
+    let legacy = {
+        let data = state_account.try_borrow_data()?;
+        if state::PoolState::try_deserialize(&mut &data[..]).is_ok() {
+            return Err(errors::ErrorCode::AccountInitialized.into());
+        }
+        state::LegacyPoolState::try_deserialize(&mut &data[..])?
+    };
+    if legacy.authority != ctx.accounts.payer.key() {
+        return Err(errors::ErrorCode::Unauthorized.into());
+    }
+    validate_fees(legacy.mint_fee, legacy.swap_fee, legacy.redeem_fee)?;
+
+    let clock = Clock::get()?;
+    let current_time =
+        u64::try_from(clock.unix_timestamp).map_err(|_| errors::ErrorCode::ArithmeticError)?;
+    let current_a: u64 = pool::interpolate_a(
+        legacy.a,
+        legacy.a_block,
+        legacy.future_a,
+        legacy.future_a_block,
+        clock.epoch,
+    )
+    .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
+    let mut future_a_time = current_time;
+    if legacy.future_a_block > clock.epoch {
+        let future_slot = EpochSchedule::get()?.get_first_slot_in_epoch(legacy.future_a_block);
+        let remaining_time = future_slot
+            .checked_sub(clock.slot)
+            .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?
+            .checked_mul(DEFAULT_MS_PER_SLOT)
+            .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?
+            .checked_div(1000)
+            .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
+        future_a_time = current_time
+            .checked_add(remaining_time)
+            .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
+    }
+
+    let jito_sol_key = ctx.accounts.jito_sol_mint_account.key();
+    let (_, sol_holder_bump) =
+        Pubkey::find_program_address(&[HOLDER_SEED, SOL, jito_sol_key.as_ref()], &crate::ID);
+    let (_, jito_sol_holder_bump) =
+        Pubkey::find_program_address(&[HOLDER_SEED, jito_sol_key.as_ref()], &crate::ID);
+    let state = state::PoolState {
+        authority: legacy.authority,
+        pool_mint: legacy.pool_mint,
+        mint_fee: legacy.mint_fee,
+        swap_fee: legacy.swap_fee,
+        redeem_fee: legacy.redeem_fee,
+        total_supply: legacy.total_supply,
+        a: current_a,
+        a_time: current_time,
+        future_a: legacy.future_a,
+        future_a_time,
+        balances: legacy.balances,
+        precisions: legacy.precisions,
+        tokens: legacy.tokens,
+        pool_initialized: legacy.pool_initialized,
+        token_initialized: legacy.token_initialized,
+        stake_pools: vec![Pubkey::default(), legacy.stake_pool],
+        holder_bumps: vec![sol_holder_bump, jito_sol_holder_bump],
+        rate_providers: vec![rate::RateProvider::Native, rate::RateProvider::SplStakePool],
+        bump: legacy.bump,
+        version: state::CURRENT_VERSION,
+        pending_authority: None,
+        fee_manager: Pubkey::default(),
+        curve_manager: Pubkey::default(),
+        guardian: Pubkey::default(),
+        treasury: Pubkey::default(),
+        paused: 0,
+        emergency: false,
+        fee_recipient: Pubkey::default(),
+        protocol_fee_share: 0,
+        yield_recipient: Pubkey::default(),
+        performance_fee: 0,
+        pending_yield: 0,
+        rates: Vec::new(),
+        loss_mode: false,
+        max_rate_staleness: 0,
+        stake_pool_programs: default_stake_pool_programs(),
+    };
+
+    let rent_lamports = Rent::get()?.minimum_balance(state::PoolState::SIZE);
+    let top_up = rent_lamports.saturating_sub(state_account.lamports());
+    if top_up > 0 {
         anchor_lang::system_program::transfer(
             CpiContext::new(
-                token_accounts.system_program,
+                ctx.accounts.system_program.to_account_info(),
                 anchor_lang::system_program::Transfer {
-                    from: token_accounts.sol_user_account,
-                    to: token_accounts.sol_program_account,
-                },
-            ),
-            amount,
-        )?;
-    } else {
-        let amount_converted = convert_balance_back_to(amount, stake_pool)?;
-        anchor_spl::token::transfer(
-            CpiContext::new(
-                token_accounts.token_program,
-                Transfer {
-                    from: token_accounts.jito_sol_user_token_account,
-                    to: token_accounts.jito_sol_program_token_account,
-                    authority: token_accounts.payer_account,
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: state_account.to_account_info(),
                 },
             ),
-            amount_converted,
+            top_up,
         )?;
     }
+    state_account.realloc(state::PoolState::SIZE, false)?;
+    state.try_serialize(&mut &mut state_account.try_borrow_mut_data()?[..])?;
+
+    emit!(event::PoolMigrated {
+        version: state::CURRENT_VERSION,
+        a: current_a,
+        future_a: state.future_a,
+        future_a_time,
+    });
     Ok(())
 }
 
-/// collect fees or yields from operation
-pub fn collect_fees(
-    sol_account: &AccountInfo,
-    jito_sol_account: &Account<TokenAccount>,
-    pool_info: &mut Account<state::PoolState>,
-    stake_pool: &stake::StakePool,
+/// mint the jitoSOL pool tokens with respect to the minimum mint amount
+pub fn mint<'info>(
+    ctx: Context<'_, '_, '_, 'info, MintShare<'info>>,
+    amounts: Vec<u64>,
+    min_mint_amount: u64,
 ) -> Result<()> {
-    let jito_sol_balance = convert_balance(jito_sol_account.amount, stake_pool)?;
-    let balances = vec![sol_account.lamports() - INIT_SOL, jito_sol_balance];
-    let a: u64 = pool::get_a(
-        pool_info.a,
-        pool_info.a_block,
-        pool_info.future_a,
-        pool_info.future_a_block,
-    )
-    .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
-    let total_supply: u64 =
-        pool::get_d(&balances, a).ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
-    if total_supply < pool_info.total_supply {
-        return Err(errors::ErrorCode::PoolImbalanced.into());
-    }
-    pool_info.balances = balances;
+    let state = &mut ctx.accounts.state_account;
+    let jito_sol_key = ctx.accounts.jito_sol_mint_account.key();
+
+    if state.is_paused(state::PAUSE_MINT) {
+        return Err(errors::ErrorCode::OperationPaused.into());
+    }
+    if ctx.accounts.jito_sol_user_token_account.mint != state.tokens[1] {
+        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    }
+    if jito_sol_key != state.tokens[1] {
+        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    }
+    if ctx.accounts.stake_pool_account.key() != state.stake_pools[1] {
+        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    }
+    let token_accounts = TokenAccounts {
+        token_program: ctx.accounts.token_program.to_account_info(),
+        system_program: ctx.accounts.system_program.to_account_info(),
+        state_account: state.to_account_info(),
+        payer_account: ctx.accounts.payer.to_account_info(),
+        sol_user_account: ctx.accounts.sol_user_account.to_account_info(),
+        sol_program_account: ctx.accounts.sol_program_account.to_account_info(),
+        lst_accounts: lst_accounts(
+            state,
+            LstAccounts {
+                mint: jito_sol_key,
+                user_token_account: ctx.accounts.jito_sol_user_token_account.to_account_info(),
+                program_token_account: ctx
+                    .accounts
+                    .jito_sol_program_token_account
+                    .to_account_info(),
+                rate: state.rate_providers[1].read(
+                    &ctx.accounts.stake_pool_account.to_account_info(),
+                    &state.stake_pool_programs,
+                )?,
+            },
+            ctx.remaining_accounts,
+        )?,
+        pool_key: jito_sol_key,
+        holder_bumps: state.holder_bumps.clone(),
+    };
+    collect_fees(
+        &token_accounts,
+        state,
+        ctx.accounts.mint_account.supply,
+        false,
+    )?;
+    if state.loss_mode {
+        return Err(errors::ErrorCode::PoolImbalanced.into());
+    }
+    for i in 0..state.tokens.len() {
+        check_rate_fresh(&token_accounts.lst_accounts, state, i)?;
+    }
+
+    let pool::MintResult {
+        mint_amount,
+        fee_amount,
+        balances,
+        total_supply,
+        iterations: _,
+    } = pool::get_mint_amount(state, ctx.accounts.mint_account.supply, &amounts)?;
+
+    let a: u64 = pool::get_a(state.a, state.a_time, state.future_a, state.future_a_time)
+        .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
+    if mint_amount < min_mint_amount {
+        return Err(errors::ErrorCode::MintUnderMin.into());
+    }
+
+    for (i, amount) in amounts.iter().enumerate() {
+        if *amount == 0u64 {
+            continue;
+        }
+        transfer_to_program(i, &token_accounts, *amount)?;
+    }
+
+    let mint_signer_seeds: &[&[&[u8]]] =
+        &[&[MINT_SEED, jito_sol_key.as_ref(), &[ctx.bumps.mint_account]]];
+    anchor_spl::token::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                authority: ctx.accounts.mint_account.to_account_info(),
+                to: ctx.accounts.mint_token_account.to_account_info(),
+                mint: ctx.accounts.mint_account.to_account_info(),
+            },
+            mint_signer_seeds,
+        ),
+        mint_amount,
+    )?;
+    let protocol_fee_amount = pool::get_protocol_fee(fee_amount, state.protocol_fee_share)?;
+    if protocol_fee_amount > 0 {
+        let fee_recipient_account = ctx
+            .accounts
+            .fee_recipient_account
+            .as_ref()
+            .ok_or::<errors::ErrorCode>(errors::ErrorCode::TokenValidationFailure)?;
+        anchor_spl::token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    authority: ctx.accounts.mint_account.to_account_info(),
+                    to: fee_recipient_account.to_account_info(),
+                    mint: ctx.accounts.mint_account.to_account_info(),
+                },
+                mint_signer_seeds,
+            ),
+            protocol_fee_amount,
+        )?;
+    }
+
+    state.total_supply = total_supply;
+    state.balances = balances;
+    update_rate_snapshot(
+        &mut ctx.accounts.rate_snapshot,
+        &mut ctx.accounts.mint_account,
+        state.backing_value(),
+    )?;
+    emit!(event::Minted {
+        minter: ctx.accounts.payer.key(),
+        a,
+        input_amounts: amounts,
+        min_output_amount: min_mint_amount,
+        balances: state.balances.clone(),
+        total_supply: state.total_supply,
+        fee_amount,
+        protocol_fee_amount,
+        output_amount: mint_amount,
+    });
+
+    Ok(())
+}
+
+/// swap between any two pool assets with respect to the minimum output
+pub fn swap<'info>(
+    ctx: Context<'_, '_, '_, 'info, SwapToken<'info>>,
+    i: u16,
+    j: u16,
+    dx: u64,
+    min_dy: u64,
+) -> Result<()> {
+    let state = &mut ctx.accounts.state_account;
+    let jito_sol_key = ctx.accounts.jito_sol_mint_account.key();
+
+    if state.is_paused(state::PAUSE_SWAP) {
+        return Err(errors::ErrorCode::OperationPaused.into());
+    }
+    if ctx.accounts.jito_sol_user_token_account.mint != state.tokens[1] {
+        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    }
+    if jito_sol_key != state.tokens[1] {
+        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    }
+    if ctx.accounts.stake_pool_account.key() != state.stake_pools[1] {
+        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    }
+    let token_accounts = TokenAccounts {
+        token_program: ctx.accounts.token_program.to_account_info(),
+        system_program: ctx.accounts.system_program.to_account_info(),
+        state_account: state.to_account_info(),
+        payer_account: ctx.accounts.payer.to_account_info(),
+        sol_user_account: ctx.accounts.sol_user_account.to_account_info(),
+        sol_program_account: ctx.accounts.sol_program_account.to_account_info(),
+        lst_accounts: lst_accounts(
+            state,
+            LstAccounts {
+                mint: jito_sol_key,
+                user_token_account: ctx.accounts.jito_sol_user_token_account.to_account_info(),
+                program_token_account: ctx
+                    .accounts
+                    .jito_sol_program_token_account
+                    .to_account_info(),
+                rate: state.rate_providers[1].read(
+                    &ctx.accounts.stake_pool_account.to_account_info(),
+                    &state.stake_pool_programs,
+                )?,
+            },
+            ctx.remaining_accounts,
+        )?,
+        pool_key: jito_sol_key,
+        holder_bumps: state.holder_bumps.clone(),
+    };
+    collect_fees(
+        &token_accounts,
+        state,
+        ctx.accounts.mint_account.supply,
+        false,
+    )?;
+    if state.loss_mode {
+        return Err(errors::ErrorCode::PoolImbalanced.into());
+    }
+    check_rate_fresh(&token_accounts.lst_accounts, state, usize::from(j))?;
+
+    let pool::SwapResult {
+        dx: _,
+        dy,
+        y,
+        balance_i,
+        balance_j,
+        fee_amount,
+        protocol_fee_amount,
+        total_supply,
+        iterations: _,
+    } = pool::get_swap_amount(
+        state,
+        ctx.accounts.mint_account.supply,
+        usize::from(i),
+        usize::from(j),
+        dx,
+    )?;
+    if y < min_dy {
+        return Err(errors::ErrorCode::SwapUnderMin.into());
+    }
+
+    state.balances[usize::from(i)] = balance_i;
+    state.balances[usize::from(j)] = balance_j;
+    state.total_supply = total_supply;
+
+    transfer_to_program(usize::from(i), &token_accounts, dx)?;
+    transfer_from_program(usize::from(j), &token_accounts, dy)?;
+    if protocol_fee_amount > 0 {
+        let fee_recipient_account = ctx
+            .accounts
+            .fee_recipient_account
+            .as_ref()
+            .ok_or::<errors::ErrorCode>(errors::ErrorCode::TokenValidationFailure)?;
+        let mint_signer_seeds: &[&[&[u8]]] =
+            &[&[MINT_SEED, jito_sol_key.as_ref(), &[ctx.bumps.mint_account]]];
+        anchor_spl::token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    authority: ctx.accounts.mint_account.to_account_info(),
+                    to: fee_recipient_account.to_account_info(),
+                    mint: ctx.accounts.mint_account.to_account_info(),
+                },
+                mint_signer_seeds,
+            ),
+            protocol_fee_amount,
+        )?;
+    }
+
+    let a: u64 = pool::get_a(state.a, state.a_time, state.future_a, state.future_a_time)
+        .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
+
+    update_rate_snapshot(
+        &mut ctx.accounts.rate_snapshot,
+        &mut ctx.accounts.mint_account,
+        state.backing_value(),
+    )?;
+    emit!(event::TokenSwapped {
+        swapper: ctx.accounts.payer.key(),
+        a,
+        input_asset: state.tokens[usize::from(i)],
+        output_asset: state.tokens[usize::from(j)],
+        input_amount: dx,
+        min_output_amount: min_dy,
+        balances: state.balances.clone(),
+        total_supply: state.total_supply,
+        fee_amount,
+        protocol_fee_amount,
+        output_amount: dy,
+    });
+
+    Ok(())
+}
+
+/// swap between any two pool assets for an exact output with respect to the maximum input
+pub fn swap_exact_out<'info>(
+    ctx: Context<'_, '_, '_, 'info, SwapToken<'info>>,
+    i: u16,
+    j: u16,
+    dy: u64,
+    max_dx: u64,
+) -> Result<()> {
+    let state = &mut ctx.accounts.state_account;
+    let jito_sol_key = ctx.accounts.jito_sol_mint_account.key();
+
+    if state.is_paused(state::PAUSE_SWAP) {
+        return Err(errors::ErrorCode::OperationPaused.into());
+    }
+    if ctx.accounts.jito_sol_user_token_account.mint != state.tokens[1] {
+        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    }
+    if jito_sol_key != state.tokens[1] {
+        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    }
+    if ctx.accounts.stake_pool_account.key() != state.stake_pools[1] {
+        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    }
+    let token_accounts = TokenAccounts {
+        token_program: ctx.accounts.token_program.to_account_info(),
+        system_program: ctx.accounts.system_program.to_account_info(),
+        state_account: state.to_account_info(),
+        payer_account: ctx.accounts.payer.to_account_info(),
+        sol_user_account: ctx.accounts.sol_user_account.to_account_info(),
+        sol_program_account: ctx.accounts.sol_program_account.to_account_info(),
+        lst_accounts: lst_accounts(
+            state,
+            LstAccounts {
+                mint: jito_sol_key,
+                user_token_account: ctx.accounts.jito_sol_user_token_account.to_account_info(),
+                program_token_account: ctx
+                    .accounts
+                    .jito_sol_program_token_account
+                    .to_account_info(),
+                rate: state.rate_providers[1].read(
+                    &ctx.accounts.stake_pool_account.to_account_info(),
+                    &state.stake_pool_programs,
+                )?,
+            },
+            ctx.remaining_accounts,
+        )?,
+        pool_key: jito_sol_key,
+        holder_bumps: state.holder_bumps.clone(),
+    };
+    collect_fees(
+        &token_accounts,
+        state,
+        ctx.accounts.mint_account.supply,
+        false,
+    )?;
+    if state.loss_mode {
+        return Err(errors::ErrorCode::PoolImbalanced.into());
+    }
+    check_rate_fresh(&token_accounts.lst_accounts, state, usize::from(j))?;
+
+    let pool::SwapResult {
+        dx,
+        dy,
+        y: _,
+        balance_i,
+        balance_j,
+        fee_amount,
+        protocol_fee_amount,
+        total_supply,
+        iterations: _,
+    } = pool::get_swap_amount_exact_out(
+        state,
+        ctx.accounts.mint_account.supply,
+        usize::from(i),
+        usize::from(j),
+        dy,
+    )?;
+    if dx > max_dx {
+        return Err(errors::ErrorCode::SwapOverMax.into());
+    }
+
+    state.balances[usize::from(i)] = balance_i;
+    state.balances[usize::from(j)] = balance_j;
+    state.total_supply = total_supply;
+
+    transfer_to_program(usize::from(i), &token_accounts, dx)?;
+    transfer_from_program(usize::from(j), &token_accounts, dy)?;
+    if protocol_fee_amount > 0 {
+        let fee_recipient_account = ctx
+            .accounts
+            .fee_recipient_account
+            .as_ref()
+            .ok_or::<errors::ErrorCode>(errors::ErrorCode::TokenValidationFailure)?;
+        let mint_signer_seeds: &[&[&[u8]]] =
+            &[&[MINT_SEED, jito_sol_key.as_ref(), &[ctx.bumps.mint_account]]];
+        anchor_spl::token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    authority: ctx.accounts.mint_account.to_account_info(),
+                    to: fee_recipient_account.to_account_info(),
+                    mint: ctx.accounts.mint_account.to_account_info(),
+                },
+                mint_signer_seeds,
+            ),
+            protocol_fee_amount,
+        )?;
+    }
+
+    let a: u64 = pool::get_a(state.a, state.a_time, state.future_a, state.future_a_time)
+        .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
+
+    update_rate_snapshot(
+        &mut ctx.accounts.rate_snapshot,
+        &mut ctx.accounts.mint_account,
+        state.backing_value(),
+    )?;
+    emit!(event::TokenSwappedExactOut {
+        swapper: ctx.accounts.payer.key(),
+        a,
+        input_asset: state.tokens[usize::from(i)],
+        output_asset: state.tokens[usize::from(j)],
+        input_amount: dx,
+        max_input_amount: max_dx,
+        balances: state.balances.clone(),
+        total_supply: state.total_supply,
+        fee_amount,
+        protocol_fee_amount,
+        output_amount: dy,
+    });
+
+    Ok(())
+}
+
+/// redeem proportionally the pool token to every pool asset with respect to the minimum amounts
+pub fn redeem_proportion<'info>(
+    ctx: Context<'_, '_, '_, 'info, RedeemShare<'info>>,
+    amount: u64,
+    min_redeem_amounts: Vec<u64>,
+) -> Result<()> {
+    let state = &mut ctx.accounts.state_account;
+    let jito_sol_key = ctx.accounts.jito_sol_mint_account.key();
+
+    if state.is_paused(state::PAUSE_REDEEM_PROPORTION) {
+        return Err(errors::ErrorCode::OperationPaused.into());
+    }
+    if ctx.accounts.jito_sol_user_token_account.mint != state.tokens[1] {
+        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    }
+    if jito_sol_key != state.tokens[1] {
+        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    }
+    if ctx.accounts.stake_pool_account.key() != state.stake_pools[1] {
+        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    }
+    if min_redeem_amounts.len() != state.balances.len() {
+        return Err(errors::ErrorCode::ArgumentValidationFailure.into());
+    }
+    let token_accounts = TokenAccounts {
+        token_program: ctx.accounts.token_program.to_account_info(),
+        system_program: ctx.accounts.system_program.to_account_info(),
+        state_account: state.to_account_info(),
+        payer_account: ctx.accounts.payer.to_account_info(),
+        sol_user_account: ctx.accounts.sol_user_account.to_account_info(),
+        sol_program_account: ctx.accounts.sol_program_account.to_account_info(),
+        lst_accounts: lst_accounts(
+            state,
+            LstAccounts {
+                mint: jito_sol_key,
+                user_token_account: ctx.accounts.jito_sol_user_token_account.to_account_info(),
+                program_token_account: ctx
+                    .accounts
+                    .jito_sol_program_token_account
+                    .to_account_info(),
+                rate: state.rate_providers[1].read(
+                    &ctx.accounts.stake_pool_account.to_account_info(),
+                    &state.stake_pool_programs,
+                )?,
+            },
+            ctx.remaining_accounts,
+        )?,
+        pool_key: jito_sol_key,
+        holder_bumps: state.holder_bumps.clone(),
+    };
+    collect_fees(
+        &token_accounts,
+        state,
+        ctx.accounts.mint_account.supply,
+        true,
+    )?;
+
+    let pool::RedeemProportionResult {
+        amounts,
+        balances,
+        fee_amount,
+        total_supply,
+        redeem_amount: _,
+    } = pool::get_redeem_proportion_amount(state, ctx.accounts.mint_account.supply, amount)?;
+
+    for i in 0..amounts.len() {
+        if amounts[i] < min_redeem_amounts[i] {
+            return Err(errors::ErrorCode::RedeemUnderMin.into());
+        }
+
+        transfer_from_program(i, &token_accounts, amounts[i])?;
+    }
+
+    let protocol_fee_amount = pool::get_protocol_fee(fee_amount, state.protocol_fee_share)?;
+    burn_redeemed(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.mint_account.to_account_info(),
+        ctx.accounts.mint_token_account.to_account_info(),
+        ctx.accounts.payer.to_account_info(),
+        ctx.accounts
+            .fee_recipient_account
+            .as_ref()
+            .map(|account| account.to_account_info()),
+        amount,
+        protocol_fee_amount,
+    )?;
+
+    state.total_supply = total_supply;
+    state.balances = balances;
+
+    let a: u64 = pool::get_a(state.a, state.a_time, state.future_a, state.future_a_time)
+        .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
+
+    update_rate_snapshot(
+        &mut ctx.accounts.rate_snapshot,
+        &mut ctx.accounts.mint_account,
+        state.backing_value(),
+    )?;
+    emit!(event::RedeemedProportion {
+        redeemer: ctx.accounts.payer.key(),
+        a,
+        input_amount: amount,
+        min_output_amounts: min_redeem_amounts,
+        balances: state.balances.clone(),
+        total_supply: state.total_supply,
+        fee_amount,
+        protocol_fee_amount,
+        output_amounts: amounts,
+    });
+
+    Ok(())
+}
+
+/// redeem the pool token to a single pool asset with respect to the minimum amount
+pub fn redeem_single<'info>(
+    ctx: Context<'_, '_, '_, 'info, RedeemShare<'info>>,
+    amount: u64,
+    i: u16,
+    min_redeem_amount: u64,
+) -> Result<()> {
+    let state = &mut ctx.accounts.state_account;
+    let jito_sol_key = ctx.accounts.jito_sol_mint_account.key();
+
+    if state.is_paused(state::PAUSE_REDEEM_SINGLE) {
+        return Err(errors::ErrorCode::OperationPaused.into());
+    }
+    if ctx.accounts.jito_sol_user_token_account.mint != state.tokens[1] {
+        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    }
+    if jito_sol_key != state.tokens[1] {
+        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    }
+    if ctx.accounts.stake_pool_account.key() != state.stake_pools[1] {
+        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    }
+    let token_accounts = TokenAccounts {
+        token_program: ctx.accounts.token_program.to_account_info(),
+        system_program: ctx.accounts.system_program.to_account_info(),
+        state_account: state.to_account_info(),
+        payer_account: ctx.accounts.payer.to_account_info(),
+        sol_user_account: ctx.accounts.sol_user_account.to_account_info(),
+        sol_program_account: ctx.accounts.sol_program_account.to_account_info(),
+        lst_accounts: lst_accounts(
+            state,
+            LstAccounts {
+                mint: jito_sol_key,
+                user_token_account: ctx.accounts.jito_sol_user_token_account.to_account_info(),
+                program_token_account: ctx
+                    .accounts
+                    .jito_sol_program_token_account
+                    .to_account_info(),
+                rate: state.rate_providers[1].read(
+                    &ctx.accounts.stake_pool_account.to_account_info(),
+                    &state.stake_pool_programs,
+                )?,
+            },
+            ctx.remaining_accounts,
+        )?,
+        pool_key: jito_sol_key,
+        holder_bumps: state.holder_bumps.clone(),
+    };
+    collect_fees(
+        &token_accounts,
+        state,
+        ctx.accounts.mint_account.supply,
+        false,
+    )?;
+    if state.loss_mode {
+        return Err(errors::ErrorCode::PoolImbalanced.into());
+    }
+    check_rate_fresh(&token_accounts.lst_accounts, state, usize::from(i))?;
+
+    let pool::RedeemSingleResult {
+        dy,
+        fee_amount,
+        total_supply,
+        balances,
+        redeem_amount: _,
+        iterations: _,
+    } = pool::get_redeem_single_amount(
+        state,
+        ctx.accounts.mint_account.supply,
+        amount,
+        usize::from(i),
+    )?;
+    if dy < min_redeem_amount {
+        return Err(errors::ErrorCode::RedeemUnderMin.into());
+    }
+
+    transfer_from_program(usize::from(i), &token_accounts, dy)?;
+
+    let protocol_fee_amount = pool::get_protocol_fee(fee_amount, state.protocol_fee_share)?;
+    burn_redeemed(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.mint_account.to_account_info(),
+        ctx.accounts.mint_token_account.to_account_info(),
+        ctx.accounts.payer.to_account_info(),
+        ctx.accounts
+            .fee_recipient_account
+            .as_ref()
+            .map(|account| account.to_account_info()),
+        amount,
+        protocol_fee_amount,
+    )?;
+
+    let mut amounts: Vec<u64> = Vec::new();
+    for idx in 0..state.balances.len() {
+        if idx == usize::from(i) {
+            amounts.push(dy);
+        } else {
+            amounts.push(0u64);
+        }
+    }
+
+    state.total_supply = total_supply;
+    state.balances = balances;
+
+    let a: u64 = pool::get_a(state.a, state.a_time, state.future_a, state.future_a_time)
+        .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
+
+    update_rate_snapshot(
+        &mut ctx.accounts.rate_snapshot,
+        &mut ctx.accounts.mint_account,
+        state.backing_value(),
+    )?;
+    emit!(event::RedeemedSingle {
+        redeemer: ctx.accounts.payer.key(),
+        a,
+        input_amount: amount,
+        output_asset: state.tokens[usize::from(i)],
+        min_output_amount: min_redeem_amount,
+        balances: state.balances.clone(),
+        total_supply: state.total_supply,
+        fee_amount,
+        protocol_fee_amount,
+        output_amount: dy,
+    });
+    Ok(())
+}
+
+/// redeem the pool token to exact amounts of the pool assets with respect to the maximum burned
+pub fn redeem_multi<'info>(
+    ctx: Context<'_, '_, '_, 'info, RedeemShare<'info>>,
+    amounts: Vec<u64>,
+    max_redeem_amount: u64,
+) -> Result<()> {
+    let state = &mut ctx.accounts.state_account;
+    let jito_sol_key = ctx.accounts.jito_sol_mint_account.key();
+
+    if state.is_paused(state::PAUSE_REDEEM_MULTI) {
+        return Err(errors::ErrorCode::OperationPaused.into());
+    }
+    if ctx.accounts.jito_sol_user_token_account.mint != state.tokens[1] {
+        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    }
+    if jito_sol_key != state.tokens[1] {
+        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    }
+    if ctx.accounts.stake_pool_account.key() != state.stake_pools[1] {
+        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    }
+    let token_accounts = TokenAccounts {
+        token_program: ctx.accounts.token_program.to_account_info(),
+        system_program: ctx.accounts.system_program.to_account_info(),
+        state_account: state.to_account_info(),
+        payer_account: ctx.accounts.payer.to_account_info(),
+        sol_user_account: ctx.accounts.sol_user_account.to_account_info(),
+        sol_program_account: ctx.accounts.sol_program_account.to_account_info(),
+        lst_accounts: lst_accounts(
+            state,
+            LstAccounts {
+                mint: jito_sol_key,
+                user_token_account: ctx.accounts.jito_sol_user_token_account.to_account_info(),
+                program_token_account: ctx
+                    .accounts
+                    .jito_sol_program_token_account
+                    .to_account_info(),
+                rate: state.rate_providers[1].read(
+                    &ctx.accounts.stake_pool_account.to_account_info(),
+                    &state.stake_pool_programs,
+                )?,
+            },
+            ctx.remaining_accounts,
+        )?,
+        pool_key: jito_sol_key,
+        holder_bumps: state.holder_bumps.clone(),
+    };
+    collect_fees(
+        &token_accounts,
+        state,
+        ctx.accounts.mint_account.supply,
+        false,
+    )?;
+    if state.loss_mode {
+        return Err(errors::ErrorCode::PoolImbalanced.into());
+    }
+    for (i, amount) in amounts.iter().enumerate() {
+        if *amount > 0 {
+            check_rate_fresh(&token_accounts.lst_accounts, state, i)?;
+        }
+    }
+
+    let pool::RedeemMultiResult {
+        fee_amount,
+        total_supply,
+        balances,
+        redeem_amount,
+        iterations: _,
+    } = pool::get_redeem_multi_amount(state, ctx.accounts.mint_account.supply, &amounts)?;
+    if redeem_amount > max_redeem_amount {
+        return Err(errors::ErrorCode::RedeemOverMax.into());
+    }
+
+    for (i, amount) in amounts.iter().enumerate() {
+        if *amount == 0u64 {
+            continue;
+        }
+        transfer_from_program(i, &token_accounts, *amount)?;
+    }
+
+    let protocol_fee_amount = pool::get_protocol_fee(fee_amount, state.protocol_fee_share)?;
+    burn_redeemed(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.mint_account.to_account_info(),
+        ctx.accounts.mint_token_account.to_account_info(),
+        ctx.accounts.payer.to_account_info(),
+        ctx.accounts
+            .fee_recipient_account
+            .as_ref()
+            .map(|account| account.to_account_info()),
+        redeem_amount,
+        protocol_fee_amount,
+    )?;
+
+    state.total_supply = total_supply;
+    state.balances = balances;
+
+    let a: u64 = pool::get_a(state.a, state.a_time, state.future_a, state.future_a_time)
+        .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
+
+    update_rate_snapshot(
+        &mut ctx.accounts.rate_snapshot,
+        &mut ctx.accounts.mint_account,
+        state.backing_value(),
+    )?;
+    emit!(event::RedeemedMulti {
+        redeemer: ctx.accounts.payer.key(),
+        a,
+        input_amount: redeem_amount,
+        max_input_amount: max_redeem_amount,
+        balances: state.balances.clone(),
+        total_supply: state.total_supply,
+        fee_amount,
+        protocol_fee_amount,
+        output_amounts: amounts,
+    });
+    Ok(())
+}
+
+/// burn the redeemed pool token and hand the protocol share of the fee to the fee recipient,
+/// the rest of the fee is burned while its D stays in the pool and raises the worth of every
+/// pool token left
+pub fn burn_redeemed<'info>(
+    token_program: AccountInfo<'info>,
+    mint_account: AccountInfo<'info>,
+    mint_token_account: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    fee_recipient_account: Option<AccountInfo<'info>>,
+    amount: u64,
+    protocol_fee_amount: u64,
+) -> Result<()> {
+    if protocol_fee_amount > 0 {
+        let fee_recipient_account = fee_recipient_account
+            .ok_or::<errors::ErrorCode>(errors::ErrorCode::TokenValidationFailure)?;
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                token_program.clone(),
+                Transfer {
+                    from: mint_token_account.clone(),
+                    to: fee_recipient_account,
+                    authority: payer.clone(),
+                },
+            ),
+            protocol_fee_amount,
+        )?;
+    }
+
+    let cpi_context = CpiContext::new(
+        token_program,
+        Burn {
+            from: mint_token_account,
+            mint: mint_account,
+            authority: payer,
+        },
+    );
+    anchor_spl::token::burn(
+        cpi_context,
+        amount
+            .checked_sub(protocol_fee_amount)
+            .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?,
+    )
+}
+
+pub fn transfer_from_program(
+    index: usize,
+    token_accounts: &TokenAccounts,
+    amount: u64,
+) -> Result<()> {
+    let bump = [token_accounts.holder_bumps[index]];
+    if index == 0 {
+        let sol_key = Pubkey::default();
+        let seeds = holder_seeds(index, &token_accounts.pool_key, &sol_key, &bump);
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                token_accounts.system_program.clone(),
+                anchor_lang::system_program::Transfer {
+                    from: token_accounts.sol_program_account.clone(),
+                    to: token_accounts.sol_user_account.clone(),
+                },
+            )
+            .with_signer(&[&seeds]),
+            amount,
+        )?;
+    } else {
+        let lst = &token_accounts.lst_accounts[index - 1];
+        let seeds = holder_seeds(index, &token_accounts.pool_key, &lst.mint, &bump);
+        let amount_converted = convert_balance_back_from(amount, &lst.rate)?;
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                token_accounts.token_program.clone(),
+                Transfer {
+                    from: lst.program_token_account.clone(),
+                    to: lst.user_token_account.clone(),
+                    authority: lst.program_token_account.clone(),
+                },
+                &[&seeds],
+            ),
+            amount_converted,
+        )?;
+    }
+    Ok(())
+}
+
+pub fn transfer_to_program(
+    index: usize,
+    token_accounts: &TokenAccounts,
+    amount: u64,
+) -> Result<()> {
+    if index == 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                token_accounts.system_program.clone(),
+                anchor_lang::system_program::Transfer {
+                    from: token_accounts.sol_user_account.clone(),
+                    to: token_accounts.sol_program_account.clone(),
+                },
+            ),
+            amount,
+        )?;
+    } else {
+        let lst = &token_accounts.lst_accounts[index - 1];
+        let amount_converted = convert_balance_back_to(amount, &lst.rate)?;
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                token_accounts.token_program.clone(),
+                Transfer {
+                    from: lst.user_token_account.clone(),
+                    to: lst.program_token_account.clone(),
+                    authority: token_accounts.payer_account.clone(),
+                },
+            ),
+            amount_converted,
+        )?;
+    }
+    Ok(())
+}
+
+/// reject the asset when its exchange rate lags behind the staleness tolerance
+pub fn check_rate_fresh(
+    lst_accounts: &[LstAccounts],
+    pool_info: &state::PoolState,
+    index: usize,
+) -> Result<()> {
+    if index == 0 {
+        return Ok(());
+    }
+    let lst = lst_accounts
+        .get(index - 1)
+        .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArgumentValidationFailure)?;
+    if lst
+        .rate
+        .is_stale(Clock::get()?.epoch, pool_info.max_rate_staleness)
+    {
+        return Err(errors::ErrorCode::StaleExchangeRate.into());
+    }
+    Ok(())
+}
+
+/// collect fees or yields from operation
+///
+/// `keep_unsolved` keeps the recorded D when the invariant of the current balances does not
+/// converge, for `redeem_proportion` which never solves it and must stay open to exit the pool
+pub fn collect_fees(
+    token_accounts: &TokenAccounts,
+    pool_info: &mut Account<state::PoolState>,
+    pool_token_supply: u64,
+    keep_unsolved: bool,
+) -> Result<()> {
+    let (balances, total_supply) = get_pool_balances(
+        &token_accounts.sol_program_account,
+        &token_accounts.lst_accounts,
+        pool_info,
+        keep_unsolved,
+    )?;
+    // a rounding drop of D is no loss while the pool tokens stay backed
+    if total_supply < pool_token_supply
+        || pool_info.total_supply.saturating_sub(total_supply) > LOSS_TOLERANCE
+    {
+        emit!(event::LossRecorded {
+            previous_total_supply: pool_info.total_supply,
+            total_supply,
+            pool_token_supply,
+        });
+    }
+    let a: u64 = pool::get_a(
+        pool_info.a,
+        pool_info.a_time,
+        pool_info.future_a,
+        pool_info.future_a_time,
+    )
+    .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
+    let amounts = get_holder_amounts(
+        &token_accounts.sol_program_account,
+        &token_accounts.lst_accounts,
+    )?;
+    let rates = std::iter::once(rate::ExchangeRate::NATIVE)
+        .chain(token_accounts.lst_accounts.iter().map(|lst| lst.rate))
+        .collect();
+    pool_info.record_rates(&amounts, rates, total_supply, a)?;
+    pool_info.balances = balances;
     pool_info.total_supply = total_supply;
+    pool_info.loss_mode = total_supply < pool_token_supply;
+    Ok(())
+}
+
+/// holdings of every pool asset in its own token, the lamports above `INIT_SOL` for SOL
+pub fn get_holder_amounts(
+    sol_program_account: &AccountInfo,
+    lst_accounts: &[LstAccounts],
+) -> Result<Vec<u64>> {
+    let mut amounts = vec![sol_program_account.lamports() - INIT_SOL];
+    for lst in lst_accounts.iter() {
+        let holder =
+            TokenAccount::try_deserialize(&mut &lst.program_token_account.try_borrow_data()?[..])?;
+        amounts.push(holder.amount);
+    }
+    Ok(amounts)
+}
+
+/// balances held by the pool in SOL and the invariant they imply, or the recorded invariant
+/// with `keep_unsolved` when theirs does not converge
+pub fn get_pool_balances(
+    sol_program_account: &AccountInfo,
+    lst_accounts: &[LstAccounts],
+    pool_info: &state::PoolState,
+    keep_unsolved: bool,
+) -> Result<(Vec<u64>, u64)> {
+    let amounts = get_holder_amounts(sol_program_account, lst_accounts)?;
+    let mut balances = vec![amounts[0]];
+    for (amount, lst) in amounts[1..].iter().zip(lst_accounts.iter()) {
+        balances.push(convert_balance(*amount, &lst.rate)?);
+    }
+    let a: u64 = pool::get_a(
+        pool_info.a,
+        pool_info.a_time,
+        pool_info.future_a,
+        pool_info.future_a_time,
+    )
+    .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
+    let total_supply: u64 = match tapio_sol_math::get_d(&balances, a) {
+        Ok((total_supply, _)) => total_supply,
+        Err(tapio_sol_math::MathError::InvariantDidNotConverge) if keep_unsolved => {
+            pool_info.total_supply
+        }
+        Err(error) => return Err(errors::ErrorCode::from(error).into()),
+    };
+    Ok((balances, total_supply))
+}
+
+/// copy of the pool state with the fees and yields collected, as the next
+/// state-changing instruction would see it
+///
+/// The returned state is never written back, quotes leave the pool untouched.
+pub fn quote_state<'info>(
+    accounts: &Quote<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    keep_unsolved: bool,
+) -> Result<(Account<'info, state::PoolState>, Vec<LstAccounts<'info>>)> {
+    let mut state = accounts.state_account.clone();
+    let jito_sol_key = accounts.jito_sol_mint_account.key();
+
+    if jito_sol_key != state.tokens[1] {
+        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    }
+    if accounts.stake_pool_account.key() != state.stake_pools[1] {
+        return Err(errors::ErrorCode::TokenValidationFailure.into());
+    }
+    let jito_sol_program_token_account = accounts.jito_sol_program_token_account.to_account_info();
+    let lst_accounts = lst_accounts(
+        &state,
+        LstAccounts {
+            mint: jito_sol_key,
+            // quotes never transfer, the user token account is not read
+            user_token_account: jito_sol_program_token_account.clone(),
+            program_token_account: jito_sol_program_token_account,
+            rate: state.rate_providers[1].read(
+                &accounts.stake_pool_account.to_account_info(),
+                &state.stake_pool_programs,
+            )?,
+        },
+        remaining_accounts,
+    )?;
+    let (balances, total_supply) = get_pool_balances(
+        &accounts.sol_program_account.to_account_info(),
+        &lst_accounts,
+        &state,
+        keep_unsolved,
+    )?;
+    state.balances = balances;
+    state.total_supply = total_supply;
+    state.loss_mode = total_supply < accounts.mint_account.supply;
+    Ok((state, lst_accounts))
+}
+
+/// quote the pool tokens minted for the given amounts
+pub fn quote_mint<'info>(
+    ctx: Context<'_, '_, '_, 'info, Quote<'info>>,
+    amounts: Vec<u64>,
+) -> Result<pool::MintResult> {
+    if ctx.accounts.state_account.is_paused(state::PAUSE_MINT) {
+        return Err(errors::ErrorCode::OperationPaused.into());
+    }
+    let (state, lst_accounts) = quote_state(ctx.accounts, ctx.remaining_accounts, false)?;
+    if state.loss_mode {
+        return Err(errors::ErrorCode::PoolImbalanced.into());
+    }
+    for i in 0..state.tokens.len() {
+        check_rate_fresh(&lst_accounts, &state, i)?;
+    }
+    pool::get_mint_amount(&state, ctx.accounts.mint_account.supply, &amounts)
+}
+
+/// quote the output of swapping `dx` of asset `i` to asset `j`
+pub fn quote_swap<'info>(
+    ctx: Context<'_, '_, '_, 'info, Quote<'info>>,
+    i: u16,
+    j: u16,
+    dx: u64,
+) -> Result<pool::SwapResult> {
+    if ctx.accounts.state_account.is_paused(state::PAUSE_SWAP) {
+        return Err(errors::ErrorCode::OperationPaused.into());
+    }
+    let (state, lst_accounts) = quote_state(ctx.accounts, ctx.remaining_accounts, false)?;
+    if state.loss_mode {
+        return Err(errors::ErrorCode::PoolImbalanced.into());
+    }
+    check_rate_fresh(&lst_accounts, &state, usize::from(j))?;
+    pool::get_swap_amount(
+        &state,
+        ctx.accounts.mint_account.supply,
+        usize::from(i),
+        usize::from(j),
+        dx,
+    )
+}
+
+/// quote the pool assets returned for redeeming `amount` pool tokens proportionally
+pub fn quote_redeem_proportion<'info>(
+    ctx: Context<'_, '_, '_, 'info, Quote<'info>>,
+    amount: u64,
+) -> Result<pool::RedeemProportionResult> {
+    if ctx
+        .accounts
+        .state_account
+        .is_paused(state::PAUSE_REDEEM_PROPORTION)
+    {
+        return Err(errors::ErrorCode::OperationPaused.into());
+    }
+    let (state, _) = quote_state(ctx.accounts, ctx.remaining_accounts, true)?;
+    pool::get_redeem_proportion_amount(&state, ctx.accounts.mint_account.supply, amount)
+}
+
+/// quote the asset `i` returned for redeeming `amount` pool tokens
+pub fn quote_redeem_single<'info>(
+    ctx: Context<'_, '_, '_, 'info, Quote<'info>>,
+    amount: u64,
+    i: u16,
+) -> Result<pool::RedeemSingleResult> {
+    if ctx
+        .accounts
+        .state_account
+        .is_paused(state::PAUSE_REDEEM_SINGLE)
+    {
+        return Err(errors::ErrorCode::OperationPaused.into());
+    }
+    let (state, lst_accounts) = quote_state(ctx.accounts, ctx.remaining_accounts, false)?;
+    if state.loss_mode {
+        return Err(errors::ErrorCode::PoolImbalanced.into());
+    }
+    check_rate_fresh(&lst_accounts, &state, usize::from(i))?;
+    pool::get_redeem_single_amount(
+        &state,
+        ctx.accounts.mint_account.supply,
+        amount,
+        usize::from(i),
+    )
+}
+
+/// SOL value of one pool token scaled by `pool::VIRTUAL_PRICE_PRECISION`, with the
+/// exchange rates of the liquid staking tokens read at the current slot
+pub fn get_virtual_price<'info>(ctx: Context<'_, '_, '_, 'info, Quote<'info>>) -> Result<u64> {
+    let (state, _) = quote_state(ctx.accounts, ctx.remaining_accounts, false)?;
+    pool::get_virtual_price(&state, ctx.accounts.mint_account.supply)
+}
+
+/// write the current pool rate into the rate snapshot and create the snapshot on first use
+pub fn refresh_rate<'info>(ctx: Context<'_, '_, '_, 'info, RefreshRate<'info>>) -> Result<()> {
+    let (state, _) = quote_state(&ctx.accounts.quote, ctx.remaining_accounts, false)?;
+    let pool_token_supply = ctx.accounts.quote.mint_account.supply;
+    let rate_snapshot = &mut ctx.accounts.rate_snapshot;
+    if rate_snapshot.version == 0 {
+        rate_snapshot.init(
+            ctx.bumps.rate_snapshot,
+            ctx.accounts.quote.jito_sol_mint_account.key(),
+            ctx.accounts.quote.mint_account.key(),
+        );
+    }
+    let clock = Clock::get()?;
+    let total_sol_value = state.backing_value();
+    rate_snapshot.refresh(pool_token_supply, total_sol_value, &clock);
+    emit!(event::RateRefreshed {
+        total_supply: pool_token_supply,
+        total_sol_value,
+        epoch: clock.epoch,
+    });
+    Ok(())
+}
+
+/// keep the rate snapshot in step with the pool after a state-changing instruction
+pub fn update_rate_snapshot<'info>(
+    rate_snapshot: &mut Account<'info, state::RateSnapshot>,
+    mint_account: &mut Account<'info, Mint>,
+    total_sol_value: u64,
+) -> Result<()> {
+    // the pool token supply moved through CPIs since the mint was loaded
+    mint_account.reload()?;
+    rate_snapshot.refresh(mint_account.supply, total_sol_value, &Clock::get()?);
     Ok(())
 }
 
-pub fn convert_balance(balance: u64, stake_pool: &stake::StakePool) -> Result<u64> {
-    let sol_balance = stake_pool.total_lamports;
-    let total_supply = stake_pool.pool_token_supply;
+pub fn convert_balance(balance: u64, rate: &rate::ExchangeRate) -> Result<u64> {
+    let sol_balance = rate.lamports;
+    let total_supply = rate.supply;
     let result: u128 = u128::from(balance)
         .checked_mul(u128::from(sol_balance))
         .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?
@@ -718,9 +2261,9 @@ pub fn convert_balance(balance: u64, stake_pool: &stake::StakePool) -> Result<u6
     u64::try_from(result).map_err(|_| errors::ErrorCode::ArithmeticError.into())
 }
 
-pub fn convert_balance_back(balance: u64, stake_pool: &stake::StakePool) -> Result<u64> {
-    let sol_balance = stake_pool.total_lamports;
-    let total_supply = stake_pool.pool_token_supply;
+pub fn convert_balance_back(balance: u64, rate: &rate::ExchangeRate) -> Result<u64> {
+    let sol_balance = rate.lamports;
+    let total_supply = rate.supply;
     let result: u128 = u128::from(balance)
         .checked_mul(u128::from(total_supply))
         .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?
@@ -729,101 +2272,456 @@ pub fn convert_balance_back(balance: u64, stake_pool: &stake::StakePool) -> Resu
     u64::try_from(result).map_err(|_| errors::ErrorCode::ArithmeticError.into())
 }
 
-pub fn convert_balance_back_to(balance: u64, stake_pool: &stake::StakePool) -> Result<u64> {
-    let converted = convert_balance_back(balance, stake_pool)?;
-    let result: u128 = u128::from(converted)
-        .checked_add(1u128)
-        .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
-    u64::try_from(result).map_err(|_| errors::ErrorCode::ArithmeticError.into())
+pub fn convert_balance_back_to(balance: u64, rate: &rate::ExchangeRate) -> Result<u64> {
+    let converted = convert_balance_back(balance, rate)?;
+    let result: u128 = u128::from(converted)
+        .checked_add(1u128)
+        .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
+    u64::try_from(result).map_err(|_| errors::ErrorCode::ArithmeticError.into())
+}
+
+pub fn convert_balance_back_from(balance: u64, rate: &rate::ExchangeRate) -> Result<u64> {
+    let converted = convert_balance_back(balance, rate)?;
+    let result: u128 = u128::from(converted)
+        .checked_sub(1u128)
+        .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
+    u64::try_from(result).map_err(|_| errors::ErrorCode::ArithmeticError.into())
+}
+
+/// stake pool programs allowed for a new pool, the SPL and Sanctum deployments
+pub fn default_stake_pool_programs() -> Vec<Pubkey> {
+    vec![stake::STAKE_POOL_ID, stake::SANCTUM_STAKE_POOL_ID]
+}
+
+/// check every fee against its hard cap
+pub fn validate_fees(mint_fee: u64, swap_fee: u64, redeem_fee: u64) -> Result<()> {
+    if mint_fee > pool::MAX_FEE || swap_fee > pool::MAX_FEE || redeem_fee > pool::MAX_FEE {
+        return Err(errors::ErrorCode::ArgumentValidationFailure.into());
+    }
+    Ok(())
+}
+
+/// seeds of the holder PDA keeping the pool asset at `index`
+///
+/// SOL and jitoSOL keep their original seeds, every liquid staking token added
+/// afterwards is keyed by the pool and its own mint.
+pub fn holder_seeds<'a>(
+    index: usize,
+    pool_key: &'a Pubkey,
+    token: &'a Pubkey,
+    bump: &'a [u8],
+) -> Vec<&'a [u8]> {
+    match index {
+        0 => vec![HOLDER_SEED, SOL, pool_key.as_ref(), bump],
+        1 => vec![HOLDER_SEED, pool_key.as_ref(), bump],
+        _ => vec![HOLDER_SEED, pool_key.as_ref(), token.as_ref(), bump],
+    }
+}
+
+/// accounts of every liquid staking token in the pool, starting with jitoSOL
+///
+/// Tokens after jitoSOL are passed as remaining accounts, `ACCOUNTS_PER_ASSET`
+/// per token in pool order: the user token account, the pool holder and the
+/// staking information account.
+pub fn lst_accounts<'info>(
+    pool_info: &state::PoolState,
+    jito_sol_accounts: LstAccounts<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<Vec<LstAccounts<'info>>> {
+    let token_size = pool_info.tokens.len();
+    if remaining_accounts.len() != (token_size - 2) * ACCOUNTS_PER_ASSET {
+        return Err(errors::ErrorCode::ArgumentValidationFailure.into());
+    }
+
+    let pool_key = jito_sol_accounts.mint;
+    let mut result = vec![jito_sol_accounts];
+    for (offset, accounts) in remaining_accounts.chunks(ACCOUNTS_PER_ASSET).enumerate() {
+        let i = offset + 2;
+        let mint = pool_info.tokens[i];
+        let bump = [pool_info.holder_bumps[i]];
+        let holder_key =
+            Pubkey::create_program_address(&holder_seeds(i, &pool_key, &mint, &bump), &crate::ID)
+                .map_err(|_| errors::ErrorCode::TokenValidationFailure)?;
+        if accounts[1].key() != holder_key {
+            return Err(errors::ErrorCode::TokenValidationFailure.into());
+        }
+        if accounts[2].key() != pool_info.stake_pools[i] {
+            return Err(errors::ErrorCode::TokenValidationFailure.into());
+        }
+        let rate =
+            pool_info.rate_providers[i].read(&accounts[2], &pool_info.stake_pool_programs)?;
+        result.push(LstAccounts {
+            mint,
+            user_token_account: accounts[0].clone(),
+            program_token_account: accounts[1].clone(),
+            rate,
+        });
+    }
+    Ok(result)
+}
+
+/// internal structure for necessary accounts to transfer SOL and liquid staking tokens
+#[derive(Clone, Debug)]
+pub struct TokenAccounts<'info> {
+    pub token_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub state_account: AccountInfo<'info>,
+    pub payer_account: AccountInfo<'info>,
+    pub sol_user_account: AccountInfo<'info>,
+    pub sol_program_account: AccountInfo<'info>,
+    /// accounts of the pool assets from index 1 onwards
+    pub lst_accounts: Vec<LstAccounts<'info>>,
+    /// the jitoSOL mint the pool PDAs are derived from
+    pub pool_key: Pubkey,
+    pub holder_bumps: Vec<u8>,
+}
+
+/// internal structure for the accounts of a single liquid staking token
+#[derive(Clone, Debug)]
+pub struct LstAccounts<'info> {
+    pub mint: Pubkey,
+    pub user_token_account: AccountInfo<'info>,
+    pub program_token_account: AccountInfo<'info>,
+    pub rate: rate::ExchangeRate,
+}
+
+/// account structure for initialize pool
+#[derive(Accounts)]
+pub struct InitializePool<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// the jitoSOL token mint
+    pub jito_sol_mint_account: Box<Account<'info, Mint>>,
+
+    /// pool state account
+    #[account(
+        init_if_needed,
+        seeds = [state::PoolState::SEED, jito_sol_mint_account.key().as_ref()],
+        bump,
+        payer = payer,
+        space = state::PoolState::SIZE
+    )]
+    pub state_account: Box<Account<'info, state::PoolState>>,
+
+    /// pool holder of SOL
+    #[account(
+        mut,
+        seeds = [HOLDER_SEED, SOL, jito_sol_mint_account.key().as_ref()],
+        bump
+    )]
+    pub sol_program_account: SystemAccount<'info>,
+
+    /// pool holder of jitoSOL
+    #[account(
+        init_if_needed,
+        seeds = [HOLDER_SEED, jito_sol_mint_account.key().as_ref()],
+        bump,
+        payer = payer,
+        token::mint = jito_sol_mint_account,
+        token::authority = jito_sol_program_token_account,
+    )]
+    pub jito_sol_program_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: owner, layout and mint are validated against the allowed stake pool programs
+    /// account of jitoSOL staking information
+    pub stake_pool_account: UncheckedAccount<'info>,
+
+    /// rate snapshot of the pool
+    #[account(
+        init_if_needed,
+        seeds = [state::RateSnapshot::SEED, jito_sol_mint_account.key().as_ref()],
+        bump,
+        payer = payer,
+        space = state::RateSnapshot::SIZE
+    )]
+    pub rate_snapshot: Box<Account<'info, state::RateSnapshot>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// account structures to initialize pool token
+#[derive(Accounts)]
+pub struct InitializeToken<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// the jitoSOL token mint
+    pub jito_sol_mint_account: Box<Account<'info, Mint>>,
+
+    /// pool token mint
+    #[account(
+        init_if_needed,
+        seeds = [MINT_SEED, jito_sol_mint_account.key().as_ref()],
+        bump,
+        payer = payer,
+        mint::decimals = 9,
+        mint::authority = mint_account.key(),
+        mint::freeze_authority = mint_account.key(),
+
+    )]
+    pub mint_account: Account<'info, Mint>,
+
+    /// state_account is the pool state account
+    #[account(
+        mut,
+        seeds = [state::PoolState::SEED, jito_sol_mint_account.key().as_ref()],
+        bump,
+    )]
+    pub state_account: Account<'info, state::PoolState>,
+
+    /// CHECK: Validate address by deriving pda
+    /// pool token metadata account
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint_account.key().as_ref()],
+        bump,
+        seeds::program = token_metadata_program.key(),
+    )]
+    pub metadata_account: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// account structures to add a liquid staking token to a pool
+#[derive(Accounts)]
+pub struct AddToken<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// the jitoSOL token mint
+    pub jito_sol_mint_account: Box<Account<'info, Mint>>,
+
+    /// state_account is the pool state account
+    #[account(
+        mut,
+        seeds = [state::PoolState::SEED, jito_sol_mint_account.key().as_ref()],
+        bump,
+    )]
+    pub state_account: Box<Account<'info, state::PoolState>>,
+
+    /// the added liquid staking token mint
+    pub token_mint_account: Box<Account<'info, Mint>>,
+
+    /// pool holder of the added token
+    #[account(
+        init,
+        seeds = [HOLDER_SEED, jito_sol_mint_account.key().as_ref(), token_mint_account.key().as_ref()],
+        bump,
+        payer = payer,
+        token::mint = token_mint_account,
+        token::authority = token_program_account,
+    )]
+    pub token_program_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: owner and layout are validated by the rate provider of the added token
+    /// account of the added token staking information
+    pub stake_pool_account: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// account structures for admin updates of a pool
+#[derive(Accounts)]
+pub struct ModifyPool<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// the jitoSOL token mint
+    pub jito_sol_mint_account: Box<Account<'info, Mint>>,
+
+    /// state_account is the pool state account
+    #[account(
+        mut,
+        seeds = [state::PoolState::SEED, jito_sol_mint_account.key().as_ref()],
+        bump,
+    )]
+    pub state_account: Account<'info, state::PoolState>,
+
+    pub system_program: Program<'info, System>,
 }
 
-pub fn convert_balance_back_from(balance: u64, stake_pool: &stake::StakePool) -> Result<u64> {
-    let converted = convert_balance_back(balance, stake_pool)?;
-    let result: u128 = u128::from(converted)
-        .checked_sub(1u128)
-        .ok_or::<errors::ErrorCode>(errors::ErrorCode::ArithmeticError)?;
-    u64::try_from(result).map_err(|_| errors::ErrorCode::ArithmeticError.into())
-}
+/// account structures for updating the fee recipient of a pool
+#[derive(Accounts)]
+pub struct SetFeeRecipient<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
 
-/// internal structure for necessary accounts to transfer SOL and jitoSOL
-#[derive(Clone, Debug)]
-pub struct TokenAccounts<'info> {
-    pub token_program: AccountInfo<'info>,
-    pub system_program: AccountInfo<'info>,
-    pub state_account: AccountInfo<'info>,
-    pub payer_account: AccountInfo<'info>,
-    pub sol_user_account: AccountInfo<'info>,
-    pub jito_sol_user_token_account: AccountInfo<'info>,
-    pub sol_program_account: AccountInfo<'info>,
-    pub jito_sol_program_token_account: AccountInfo<'info>,
+    /// the jitoSOL token mint
+    pub jito_sol_mint_account: Box<Account<'info, Mint>>,
+
+    /// state_account is the pool state account
+    #[account(
+        mut,
+        seeds = [state::PoolState::SEED, jito_sol_mint_account.key().as_ref()],
+        bump,
+    )]
+    pub state_account: Account<'info, state::PoolState>,
+
+    /// pool token account receiving the protocol fees
+    #[account(
+        constraint = fee_recipient_account.mint == state_account.pool_mint @ errors::ErrorCode::TokenValidationFailure,
+    )]
+    pub fee_recipient_account: Account<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
 }
 
-/// account structure for initialize pool
+/// account structures for updating the yield recipient of a pool
 #[derive(Accounts)]
-pub struct InitializePool<'info> {
+pub struct SetYieldRecipient<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
 
     /// the jitoSOL token mint
     pub jito_sol_mint_account: Box<Account<'info, Mint>>,
 
-    /// pool state account
+    /// state_account is the pool state account
     #[account(
-        init_if_needed,
+        mut,
         seeds = [state::PoolState::SEED, jito_sol_mint_account.key().as_ref()],
         bump,
-        payer = payer,
-        space = state::PoolState::SIZE
     )]
-    pub state_account: Box<Account<'info, state::PoolState>>,
+    pub state_account: Account<'info, state::PoolState>,
 
-    /// pool holder of SOL
+    /// pool token account receiving the yield
+    #[account(
+        constraint = yield_recipient_account.mint == state_account.pool_mint @ errors::ErrorCode::TokenValidationFailure,
+    )]
+    pub yield_recipient_account: Account<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// account structures for minting the accumulated yield of a pool
+#[derive(Accounts)]
+pub struct DistributeYield<'info> {
+    /// pool token mint
+    #[account(
+        mut,
+        seeds = [MINT_SEED, jito_sol_mint_account.key().as_ref()],
+        bump
+    )]
+    pub mint_account: Box<Account<'info, Mint>>,
+
+    /// the jitoSOL token mint
+    pub jito_sol_mint_account: Box<Account<'info, Mint>>,
+
+    /// state_account is the pool state account
     #[account(
         mut,
+        seeds = [state::PoolState::SEED, jito_sol_mint_account.key().as_ref()],
+        bump,
+    )]
+    pub state_account: Account<'info, state::PoolState>,
+
+    /// SOL account of the pool
+    #[account(
         seeds = [HOLDER_SEED, SOL, jito_sol_mint_account.key().as_ref()],
         bump
     )]
     pub sol_program_account: SystemAccount<'info>,
-
-    /// pool holder of jitoSOL
+    /// jitoSOL account of the pool
     #[account(
-        init_if_needed,
         seeds = [HOLDER_SEED, jito_sol_mint_account.key().as_ref()],
         bump,
-        payer = payer,
         token::mint = jito_sol_mint_account,
-        token::authority = jito_sol_program_token_account,
     )]
     pub jito_sol_program_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: owner and layout are validated by the rate provider of jitoSOL
     /// account of jitoSOL staking information
-    pub stake_pool_account: Box<Account<'info, stake::StakePool>>,
+    pub stake_pool_account: UncheckedAccount<'info>,
+
+    /// pool token account of the yield recipient
+    #[account(
+        mut,
+        address = state_account.yield_recipient @ errors::ErrorCode::TokenValidationFailure,
+    )]
+    pub yield_recipient_account: Box<Account<'info, TokenAccount>>,
+
+    /// pool token account of the treasury, or of the authority while no treasury is set,
+    /// required when a performance fee is charged
+    #[account(
+        mut,
+        token::mint = mint_account,
+        token::authority = state_account.role_or_authority(state::Role::Treasury),
+    )]
+    pub treasury_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// rate snapshot of the pool, refreshed by the operation
+    #[account(
+        mut,
+        seeds = [state::RateSnapshot::SEED, jito_sol_mint_account.key().as_ref()],
+        bump = rate_snapshot.bump,
+    )]
+    pub rate_snapshot: Box<Account<'info, state::RateSnapshot>>,
 
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
 }
 
-/// account structures to initialize pool token
+/// account structures for covering a loss of a pool with its insurance buffer
 #[derive(Accounts)]
-pub struct InitializeToken<'info> {
-    #[account(mut)]
+pub struct CoverLoss<'info> {
     pub payer: Signer<'info>,
 
+    /// pool token mint
+    #[account(
+        mut,
+        seeds = [MINT_SEED, jito_sol_mint_account.key().as_ref()],
+        bump
+    )]
+    pub mint_account: Box<Account<'info, Mint>>,
+
     /// the jitoSOL token mint
     pub jito_sol_mint_account: Box<Account<'info, Mint>>,
 
-    /// pool token mint
+    /// state_account is the pool state account
     #[account(
-        init_if_needed,
-        seeds = [MINT_SEED, jito_sol_mint_account.key().as_ref()],
+        mut,
+        seeds = [state::PoolState::SEED, jito_sol_mint_account.key().as_ref()],
         bump,
-        payer = payer,
-        mint::decimals = 9,
-        mint::authority = mint_account.key(),
-        mint::freeze_authority = mint_account.key(),
+    )]
+    pub state_account: Account<'info, state::PoolState>,
 
+    /// insurance buffer, the pool token ATA of the pool state
+    #[account(
+        mut,
+        associated_token::mint = mint_account,
+        associated_token::authority = state_account,
     )]
-    pub mint_account: Account<'info, Mint>,
+    pub insurance_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// rate snapshot of the pool, refreshed by the operation
+    #[account(
+        mut,
+        seeds = [state::RateSnapshot::SEED, jito_sol_mint_account.key().as_ref()],
+        bump = rate_snapshot.bump,
+    )]
+    pub rate_snapshot: Box<Account<'info, state::RateSnapshot>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// account structures for withdrawing from the insurance buffer of a pool
+#[derive(Accounts)]
+pub struct WithdrawInsurance<'info> {
+    pub payer: Signer<'info>,
+
+    /// pool token mint
+    #[account(
+        seeds = [MINT_SEED, jito_sol_mint_account.key().as_ref()],
+        bump
+    )]
+    pub mint_account: Box<Account<'info, Mint>>,
+
+    /// the jitoSOL token mint
+    pub jito_sol_mint_account: Box<Account<'info, Mint>>,
 
     /// state_account is the pool state account
     #[account(
@@ -833,20 +2731,40 @@ pub struct InitializeToken<'info> {
     )]
     pub state_account: Account<'info, state::PoolState>,
 
-    /// CHECK: Validate address by deriving pda
-    /// pool token metadata account
+    /// SOL account of the pool
     #[account(
-        mut,
-        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint_account.key().as_ref()],
+        seeds = [HOLDER_SEED, SOL, jito_sol_mint_account.key().as_ref()],
+        bump
+    )]
+    pub sol_program_account: SystemAccount<'info>,
+    /// jitoSOL account of the pool
+    #[account(
+        seeds = [HOLDER_SEED, jito_sol_mint_account.key().as_ref()],
         bump,
-        seeds::program = token_metadata_program.key(),
+        token::mint = jito_sol_mint_account,
     )]
-    pub metadata_account: UncheckedAccount<'info>,
+    pub jito_sol_program_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: owner and layout are validated by the rate provider of jitoSOL
+    /// account of jitoSOL staking information
+    pub stake_pool_account: UncheckedAccount<'info>,
+
+    /// insurance buffer, the pool token ATA of the pool state
+    #[account(
+        mut,
+        associated_token::mint = mint_account,
+        associated_token::authority = state_account,
+    )]
+    pub insurance_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// pool token account receiving the withdrawn tokens
+    #[account(
+        mut,
+        token::mint = mint_account,
+    )]
+    pub destination_token_account: Box<Account<'info, TokenAccount>>,
 
     pub token_program: Program<'info, Token>,
-    pub token_metadata_program: Program<'info, Metadata>,
     pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
 }
 
 /// account structures for modify amplitude of a pool
@@ -870,6 +2788,27 @@ pub struct ModifyA<'info> {
     pub rent: Sysvar<'info, Rent>,
 }
 
+/// account structures to migrate a legacy pool state
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// the jitoSOL token mint
+    pub jito_sol_mint_account: Box<Account<'info, Mint>>,
+
+    /// CHECK: legacy layout, decoded and rewritten by the handler
+    /// state_account is the pool state account
+    #[account(
+        mut,
+        seeds = [state::PoolState::SEED, jito_sol_mint_account.key().as_ref()],
+        bump,
+    )]
+    pub state_account: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
 /// account structures for mint tokens
 #[derive(Accounts)]
 pub struct MintShare<'info> {
@@ -922,8 +2861,23 @@ pub struct MintShare<'info> {
         token::mint = jito_sol_mint_account,
     )]
     pub jito_sol_program_token_account: Account<'info, TokenAccount>,
+    /// CHECK: owner and layout are validated by the rate provider of jitoSOL
     /// account of jitoSOL staking information
-    pub stake_pool_account: Account<'info, stake::StakePool>,
+    pub stake_pool_account: UncheckedAccount<'info>,
+    /// pool token account of the fee recipient, required when a protocol fee is charged
+    #[account(
+        mut,
+        address = state_account.fee_recipient @ errors::ErrorCode::TokenValidationFailure,
+    )]
+    pub fee_recipient_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// rate snapshot of the pool, refreshed by the operation
+    #[account(
+        mut,
+        seeds = [state::RateSnapshot::SEED, jito_sol_mint_account.key().as_ref()],
+        bump = rate_snapshot.bump,
+    )]
+    pub rate_snapshot: Box<Account<'info, state::RateSnapshot>>,
 
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
@@ -936,6 +2890,14 @@ pub struct SwapToken<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
 
+    /// pool token mint
+    #[account(
+        mut,
+        seeds = [MINT_SEED, jito_sol_mint_account.key().as_ref()],
+        bump
+    )]
+    pub mint_account: Account<'info, Mint>,
+
     /// the jitoSOL token mint
     pub jito_sol_mint_account: Account<'info, Mint>,
     /// state_account is the pool state account
@@ -967,8 +2929,23 @@ pub struct SwapToken<'info> {
         token::mint = jito_sol_mint_account,
     )]
     pub jito_sol_program_token_account: Account<'info, TokenAccount>,
+    /// CHECK: owner and layout are validated by the rate provider of jitoSOL
     /// account of jitoSOL staking information
-    pub stake_pool_account: Account<'info, stake::StakePool>,
+    pub stake_pool_account: UncheckedAccount<'info>,
+    /// pool token account of the fee recipient, required when a protocol fee is charged
+    #[account(
+        mut,
+        address = state_account.fee_recipient @ errors::ErrorCode::TokenValidationFailure,
+    )]
+    pub fee_recipient_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// rate snapshot of the pool, refreshed by the operation
+    #[account(
+        mut,
+        seeds = [state::RateSnapshot::SEED, jito_sol_mint_account.key().as_ref()],
+        bump = rate_snapshot.bump,
+    )]
+    pub rate_snapshot: Box<Account<'info, state::RateSnapshot>>,
 
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
@@ -1027,10 +3004,85 @@ pub struct RedeemShare<'info> {
         token::mint = jito_sol_mint_account,
     )]
     pub jito_sol_program_token_account: Account<'info, TokenAccount>,
+    /// CHECK: owner and layout are validated by the rate provider of jitoSOL
     /// account of jitoSOL staking information
-    pub stake_pool_account: Account<'info, stake::StakePool>,
+    pub stake_pool_account: UncheckedAccount<'info>,
+    /// pool token account of the fee recipient, required when a protocol fee is charged
+    #[account(
+        mut,
+        address = state_account.fee_recipient @ errors::ErrorCode::TokenValidationFailure,
+    )]
+    pub fee_recipient_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// rate snapshot of the pool, refreshed by the operation
+    #[account(
+        mut,
+        seeds = [state::RateSnapshot::SEED, jito_sol_mint_account.key().as_ref()],
+        bump = rate_snapshot.bump,
+    )]
+    pub rate_snapshot: Box<Account<'info, state::RateSnapshot>>,
 
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
+
+/// account structures for read-only quotes
+///
+/// Tokens after jitoSOL are passed as remaining accounts in the same layout as
+/// for the state-changing instructions.
+#[derive(Accounts)]
+pub struct Quote<'info> {
+    /// pool token mint
+    #[account(
+        seeds = [MINT_SEED, jito_sol_mint_account.key().as_ref()],
+        bump
+    )]
+    pub mint_account: Account<'info, Mint>,
+
+    /// the jitoSOL token mint
+    pub jito_sol_mint_account: Account<'info, Mint>,
+    /// state_account is the pool state account
+    #[account(
+        seeds = [state::PoolState::SEED, jito_sol_mint_account.key().as_ref()],
+        bump
+    )]
+    pub state_account: Account<'info, state::PoolState>,
+    /// SOL account of the pool
+    #[account(
+        seeds = [HOLDER_SEED, SOL, jito_sol_mint_account.key().as_ref()],
+        bump
+    )]
+    pub sol_program_account: SystemAccount<'info>,
+    /// jitoSOL account of the pool
+    #[account(
+        seeds = [HOLDER_SEED, jito_sol_mint_account.key().as_ref()],
+        bump,
+        token::mint = jito_sol_mint_account,
+    )]
+    pub jito_sol_program_token_account: Account<'info, TokenAccount>,
+    /// CHECK: owner and layout are validated by the rate provider of jitoSOL
+    /// account of jitoSOL staking information
+    pub stake_pool_account: UncheckedAccount<'info>,
+}
+
+/// account structures for refreshing the rate snapshot of a pool
+#[derive(Accounts)]
+pub struct RefreshRate<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub quote: Quote<'info>,
+
+    /// rate snapshot of the pool, created on the first refresh
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = state::RateSnapshot::SIZE,
+        seeds = [state::RateSnapshot::SEED, quote.jito_sol_mint_account.key().as_ref()],
+        bump,
+    )]
+    pub rate_snapshot: Box<Account<'info, state::RateSnapshot>>,
+
+    pub system_program: Program<'info, System>,
+}
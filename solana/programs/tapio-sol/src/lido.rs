@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+
+pub const LIDO_ID: Pubkey = pubkey!("CrX7kMhLC3cSsXJdT7JDgqrRVWGnUpX3gfEfxxU2NVLi");
+
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum AccountType {
+    /// If the account has not been initialized, the enum will be 0
+    #[default]
+    Uninitialized,
+    /// Lido state
+    Lido,
+    /// Validator list
+    Validator,
+    /// Maintainer list
+    Maintainer,
+}
+
+/// stSOL exchange rate, computed once per epoch
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct ExchangeRate {
+    /// Epoch in which the exchange rate was last computed
+    pub computed_in_epoch: u64,
+
+    /// Amount of stSOL that existed at that time
+    pub st_sol_supply: u64,
+
+    /// Amount of SOL managed by Lido at that time
+    pub sol_balance: u64,
+}
+
+/// Lido (Solido) state, up to the exchange rate.
+///
+/// Only the leading fields are mirrored, the remaining bytes of the account
+/// are left untouched when deserializing.
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct Lido {
+    /// Account type, must be `Lido`
+    pub account_type: AccountType,
+
+    /// Version number of the Lido state
+    pub lido_version: u8,
+
+    /// Manager authority
+    pub manager: Pubkey,
+
+    /// The SPL Token mint address for stSOL
+    pub st_sol_mint: Pubkey,
+
+    /// Exchange rate to use when depositing
+    pub exchange_rate: ExchangeRate,
+}
+
+impl AccountDeserialize for Lido {
+    fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+        let lido = Self::try_deserialize_unchecked(buf)?;
+        if lido.account_type != AccountType::Lido {
+            return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+        }
+        Ok(lido)
+    }
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+        AnchorDeserialize::deserialize(buf).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+
+impl Owner for Lido {
+    fn owner() -> Pubkey {
+        LIDO_ID
+    }
+}
@@ -0,0 +1,105 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+pub const MARINADE_ID: Pubkey = pubkey!("MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD");
+
+/// `msol_price` is expressed in lamports per `PRICE_DENOMINATOR` mSOL
+pub const PRICE_DENOMINATOR: u64 = 0x1_0000_0000;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct Fee {
+    pub basis_points: u32,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct List {
+    pub account: Pubkey,
+    pub item_size: u32,
+    pub count: u32,
+    pub new_account: Pubkey,
+    pub copied_count: u32,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct StakeSystem {
+    pub stake_list: List,
+    pub delayed_unstake_cooling_down: u64,
+    pub stake_deposit_bump_seed: u8,
+    pub stake_withdraw_bump_seed: u8,
+    pub slots_for_stake_delta: u64,
+    pub last_stake_delta_epoch: u64,
+    pub min_stake: u64,
+    pub extra_stake_delta_runs: u32,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct ValidatorSystem {
+    pub validator_list: List,
+    pub manager_authority: Pubkey,
+    pub total_validator_score: u32,
+    pub total_active_balance: u64,
+    pub auto_add_validator_enabled: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct LiqPool {
+    pub lp_mint: Pubkey,
+    pub lp_mint_authority_bump_seed: u8,
+    pub sol_leg_bump_seed: u8,
+    pub msol_leg_authority_bump_seed: u8,
+    pub msol_leg: Pubkey,
+    pub lp_liquidity_target: u64,
+    pub lp_max_fee: Fee,
+    pub lp_min_fee: Fee,
+    pub treasury_cut: Fee,
+    pub lp_supply: u64,
+    pub lent_from_sol_leg: u64,
+    pub liquidity_sol_cap: u64,
+}
+
+/// Marinade liquid staking state, up to the mSOL price.
+///
+/// Only the leading fields are mirrored, the remaining bytes of the account
+/// are left untouched when deserializing.
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct State {
+    pub msol_mint: Pubkey,
+    pub admin_authority: Pubkey,
+    pub operational_sol_account: Pubkey,
+    pub treasury_msol_account: Pubkey,
+    pub reserve_bump_seed: u8,
+    pub msol_mint_authority_bump_seed: u8,
+    pub rent_exempt_for_token_acc: u64,
+    pub reward_fee: Fee,
+    pub stake_system: StakeSystem,
+    pub validator_system: ValidatorSystem,
+    pub liq_pool: LiqPool,
+    pub available_reserve_balance: u64,
+    pub msol_supply: u64,
+
+    /// Lamports per `PRICE_DENOMINATOR` mSOL, updated once per epoch
+    pub msol_price: u64,
+}
+
+impl AccountDeserialize for State {
+    fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+        if buf.len() < Self::DISCRIMINATOR.len() || buf[..8] != Self::DISCRIMINATOR {
+            return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+        let mut data: &[u8] = &buf[8..];
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+impl Discriminator for State {
+    const DISCRIMINATOR: [u8; 8] = [216, 146, 107, 94, 104, 75, 182, 177];
+}
+
+impl Owner for State {
+    fn owner() -> Pubkey {
+        MARINADE_ID
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{errors, lido, marinade, stake};
+use anchor_lang::prelude::*;
+
+/// exchange rate of a pool asset: `lamports` of SOL for every `supply` tokens
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Eq, Debug, InitSpace)]
+pub struct ExchangeRate {
+    pub lamports: u64,
+    pub supply: u64,
+    /// epoch the rate was last updated in, `None` when the source does not record it
+    pub updated_epoch: Option<u64>,
+}
+
+impl ExchangeRate {
+    pub const NATIVE: ExchangeRate = ExchangeRate {
+        lamports: 1,
+        supply: 1,
+        updated_epoch: None,
+    };
+
+    /// whether the rate was updated more than `max_staleness` epochs before `current_epoch`
+    pub fn is_stale(&self, current_epoch: u64, max_staleness: u64) -> bool {
+        self.updated_epoch
+            .is_some_and(|epoch| current_epoch.saturating_sub(epoch) > max_staleness)
+    }
+}
+
+/// source of the exchange rate of a pool asset, selected per asset in the pool state
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Eq, Default, Debug, InitSpace,
+)]
+pub enum RateProvider {
+    /// native SOL
+    #[default]
+    Native,
+    /// SPL stake pool, `total_lamports / pool_token_supply`
+    SplStakePool,
+    /// Sanctum single-validator SPL stake pool, same layout as `SplStakePool`
+    SanctumSplStakePool,
+    /// Marinade state, `msol_price / PRICE_DENOMINATOR`
+    Marinade,
+    /// Lido state, `sol_balance / st_sol_supply`
+    Lido,
+}
+
+impl RateProvider {
+    /// program owning the staking information account of the provider, the default allowed
+    /// stake pool program for SPL stake pools
+    pub fn owner(&self) -> Option<Pubkey> {
+        match self {
+            RateProvider::Native => None,
+            RateProvider::SplStakePool => Some(stake::STAKE_POOL_ID),
+            RateProvider::SanctumSplStakePool => Some(stake::SANCTUM_STAKE_POOL_ID),
+            RateProvider::Marinade => Some(marinade::MARINADE_ID),
+            RateProvider::Lido => Some(lido::LIDO_ID),
+        }
+    }
+
+    /// read the exchange rate from the staking information account of the provider, SPL stake
+    /// pools may be owned by any of the allowed stake pool programs
+    pub fn read(
+        &self,
+        account: &AccountInfo,
+        stake_pool_programs: &[Pubkey],
+    ) -> Result<ExchangeRate> {
+        let owner_allowed = match self {
+            RateProvider::Native => return Ok(ExchangeRate::NATIVE),
+            RateProvider::SplStakePool | RateProvider::SanctumSplStakePool => {
+                stake_pool_programs.contains(account.owner)
+            }
+            RateProvider::Marinade | RateProvider::Lido => self.owner() == Some(*account.owner),
+        };
+        if !owner_allowed {
+            return Err(errors::ErrorCode::TokenValidationFailure.into());
+        }
+        let data = account.try_borrow_data()?;
+        let rate = match self {
+            RateProvider::Native => ExchangeRate::NATIVE,
+            RateProvider::SplStakePool | RateProvider::SanctumSplStakePool => {
+                let stake_pool = stake::StakePool::try_deserialize(&mut &data[..])?;
+                ExchangeRate {
+                    lamports: stake_pool.total_lamports,
+                    supply: stake_pool.pool_token_supply,
+                    updated_epoch: Some(stake_pool.last_update_epoch),
+                }
+            }
+            RateProvider::Marinade => {
+                let state = marinade::State::try_deserialize(&mut &data[..])?;
+                ExchangeRate {
+                    lamports: state.msol_price,
+                    supply: marinade::PRICE_DENOMINATOR,
+                    // the mSOL price is updated by the stake delta run of every epoch
+                    updated_epoch: Some(state.stake_system.last_stake_delta_epoch),
+                }
+            }
+            RateProvider::Lido => {
+                let lido = lido::Lido::try_deserialize(&mut &data[..])?;
+                ExchangeRate {
+                    lamports: lido.exchange_rate.sol_balance,
+                    supply: lido.exchange_rate.st_sol_supply,
+                    updated_epoch: Some(lido.exchange_rate.computed_in_epoch),
+                }
+            }
+        };
+        Ok(rate)
+    }
+
+    /// check the staking information account backs the token mint it records
+    pub fn validate(
+        &self,
+        account: &AccountInfo,
+        stake_pool_programs: &[Pubkey],
+        mint: &Pubkey,
+    ) -> Result<()> {
+        self.read(account, stake_pool_programs)?;
+        let data = account.try_borrow_data()?;
+        let backs_mint = match self {
+            RateProvider::Native => true,
+            RateProvider::SplStakePool | RateProvider::SanctumSplStakePool => {
+                let stake_pool = stake::StakePool::try_deserialize(&mut &data[..])?;
+                stake_pool.account_type == stake::AccountType::StakePool
+                    && stake_pool.pool_mint == *mint
+            }
+            RateProvider::Marinade => {
+                marinade::State::try_deserialize(&mut &data[..])?.msol_mint == *mint
+            }
+            RateProvider::Lido => lido::Lido::try_deserialize(&mut &data[..])?.st_sol_mint == *mint,
+        };
+        if !backs_mint {
+            return Err(errors::ErrorCode::TokenValidationFailure.into());
+        }
+        Ok(())
+    }
+}
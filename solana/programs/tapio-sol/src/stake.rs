@@ -1,7 +1,9 @@
 use anchor_lang::prelude::*;
 use std::io::Write;
 
-const STAKE_POOL_ID: Pubkey = pubkey!("SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy");
+pub const STAKE_POOL_ID: Pubkey = pubkey!("SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy");
+/// Sanctum deployment of the SPL stake pool program for single-validator pools
+pub const SANCTUM_STAKE_POOL_ID: Pubkey = pubkey!("SP12tWFxD9oJsVWNavTTBZvMbA6gkAmxtVgxdqvyvhY");
 
 #[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Eq, Default, Debug)]
 pub enum AccountType {
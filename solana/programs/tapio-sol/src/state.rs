@@ -1,4 +1,15 @@
+use crate::rate::{ExchangeRate, RateProvider};
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+/// maximum number of assets held by a pool, native SOL included
+pub const MAX_TOKENS: usize = 8;
+
+/// maximum number of stake pool programs allowed to own SPL stake pool accounts
+pub const MAX_STAKE_POOL_PROGRAMS: usize = 4;
+
+/// layout version written by `initialize_pool` and `migrate_pool`
+pub const CURRENT_VERSION: u8 = 1;
 
 #[account]
 #[derive(Default, InitSpace)]
@@ -12,26 +23,261 @@ pub struct PoolState {
     pub redeem_fee: u64,
     pub total_supply: u64,
     pub a: u64,
-    pub a_block: u64,
+    /// Unix timestamp the current amplitude ramp started at.
+    pub a_time: u64,
     pub future_a: u64,
-    pub future_a_block: u64,
+    /// Unix timestamp the current amplitude ramp ends at.
+    pub future_a_time: u64,
 
-    #[max_len(2)]
+    #[max_len(MAX_TOKENS)]
     pub balances: Vec<u64>,
-    #[max_len(2)]
+    #[max_len(MAX_TOKENS)]
     pub precisions: Vec<u64>,
-    #[max_len(2)]
+    #[max_len(MAX_TOKENS)]
     pub tokens: Vec<Pubkey>,
     pub pool_initialized: bool,
     pub token_initialized: bool,
-    pub stake_pool: Pubkey,
+    /// Staking information account of each asset, the default key for native SOL.
+    #[max_len(MAX_TOKENS)]
+    pub stake_pools: Vec<Pubkey>,
+    /// Bumps of the holder PDAs keeping each asset.
+    #[max_len(MAX_TOKENS)]
+    pub holder_bumps: Vec<u8>,
+    /// Exchange rate source of each asset.
+    #[max_len(MAX_TOKENS)]
+    pub rate_providers: Vec<RateProvider>,
 
     /// The bump used to generate this account
     pub bump: u8,
+
+    /// Layout version of this account, zero for legacy accounts.
+    pub version: u8,
+
+    /// Authority proposed by `propose_authority`, waiting to be accepted.
+    pub pending_authority: Option<Pubkey>,
+
+    /// Key allowed to update the fees, the default key when unset.
+    pub fee_manager: Pubkey,
+    /// Key allowed to ramp the amplitude, the default key when unset.
+    pub curve_manager: Pubkey,
+    /// Key allowed to pause the pool, the default key when unset.
+    pub guardian: Pubkey,
+    /// Key allowed to withdraw the insurance buffer or burn it against a loss, the default key
+    /// when unset.
+    pub treasury: Pubkey,
+
+    /// Bits of the paused operations, see `PAUSE_MINT` and friends.
+    pub paused: u8,
+    /// Emergency mode, leaving only `redeem_proportion` enabled.
+    pub emergency: bool,
+
+    /// Pool token account receiving the protocol share of the fees.
+    pub fee_recipient: Pubkey,
+    /// Protocol share of every fee over `FEE_PRECISION`, at most `MAX_PROTOCOL_FEE_SHARE`,
+    /// minted to the fee recipient. The rest stays in D and raises the worth of every pool token.
+    pub protocol_fee_share: u64,
+
+    /// Pool token account receiving the realized staking yield.
+    pub yield_recipient: Pubkey,
+    /// Treasury share of the realized yield over `FEE_PRECISION`, at most `MAX_PERFORMANCE_FEE`.
+    pub performance_fee: u64,
+    /// Growth of D from the asset exchange rates not minted by `distribute_yield` yet.
+    pub pending_yield: u64,
+    /// Exchange rate of each asset at the last operation, empty until the first one.
+    #[max_len(MAX_TOKENS)]
+    pub rates: Vec<ExchangeRate>,
+
+    /// Set while D is below the pool token supply, leaving only `redeem_proportion` enabled.
+    pub loss_mode: bool,
+
+    /// Number of epochs an asset exchange rate may lag behind the current epoch.
+    pub max_rate_staleness: u64,
+
+    /// Programs allowed to own the SPL stake pool accounts of the pool assets.
+    #[max_len(MAX_STAKE_POOL_PROGRAMS)]
+    pub stake_pool_programs: Vec<Pubkey>,
+}
+
+pub const PAUSE_MINT: u8 = 1 << 0;
+pub const PAUSE_SWAP: u8 = 1 << 1;
+pub const PAUSE_REDEEM_PROPORTION: u8 = 1 << 2;
+pub const PAUSE_REDEEM_SINGLE: u8 = 1 << 3;
+pub const PAUSE_REDEEM_MULTI: u8 = 1 << 4;
+pub const PAUSE_ALL: u8 =
+    PAUSE_MINT | PAUSE_SWAP | PAUSE_REDEEM_PROPORTION | PAUSE_REDEEM_SINGLE | PAUSE_REDEEM_MULTI;
+
+/// admin roles delegated by the authority
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Role {
+    FeeManager,
+    CurveManager,
+    Guardian,
+    Treasury,
 }
 
 const HEADER_SIZE: usize = 8;
 impl PoolState {
     pub const SEED: &'static [u8] = b"state";
     pub const SIZE: usize = HEADER_SIZE + PoolState::INIT_SPACE;
+
+    /// key currently holding the role
+    pub fn role(&self, role: Role) -> Pubkey {
+        match role {
+            Role::FeeManager => self.fee_manager,
+            Role::CurveManager => self.curve_manager,
+            Role::Guardian => self.guardian,
+            Role::Treasury => self.treasury,
+        }
+    }
+
+    /// key acting as the role, the authority while the role is unset
+    pub fn role_or_authority(&self, role: Role) -> Pubkey {
+        match self.role(role) {
+            key if key == Pubkey::default() => self.authority,
+            key => key,
+        }
+    }
+
+    /// SOL value backing the pool tokens, D without the yield pending `distribute_yield`
+    pub fn backing_value(&self) -> u64 {
+        self.total_supply.saturating_sub(self.pending_yield)
+    }
+
+    /// whether the key may act as the role, the authority holds every role
+    pub fn has_role(&self, role: Role, key: &Pubkey) -> bool {
+        self.authority == *key || (*key != Pubkey::default() && self.role(role) == *key)
+    }
+
+    /// record the exchange rates of an operation, the growth of `total_supply`, D at these
+    /// rates, over D of the same `amounts` at the recorded rates is yield pending
+    /// `distribute_yield` and a drop is taken from the pending yield first
+    ///
+    /// `amounts` are the holdings of every asset in its own token. Fees kept in them since the
+    /// last operation raise both invariants alike and stay with the liquidity providers.
+    pub fn record_rates(
+        &mut self,
+        amounts: &[u64],
+        rates: Vec<ExchangeRate>,
+        total_supply: u64,
+        a: u64,
+    ) -> Result<()> {
+        if self.rates.len() == amounts.len() {
+            let mut balances = Vec::with_capacity(amounts.len());
+            for (amount, rate) in amounts.iter().zip(self.rates.iter()) {
+                balances.push(crate::convert_balance(*amount, rate)?);
+            }
+            // holdings without a converging invariant at the former rates leave it unchanged
+            if let Ok((recorded_d, _)) = tapio_sol_math::get_d(&balances, a) {
+                if total_supply >= recorded_d {
+                    self.pending_yield =
+                        self.pending_yield.saturating_add(total_supply - recorded_d);
+                } else {
+                    self.pending_yield =
+                        self.pending_yield.saturating_sub(recorded_d - total_supply);
+                }
+            }
+        }
+        self.rates = rates;
+        Ok(())
+    }
+
+    /// whether the operation is disabled, `redeem_proportion` stays open in emergency mode
+    pub fn is_paused(&self, operation: u8) -> bool {
+        if self.emergency {
+            return operation != PAUSE_REDEEM_PROPORTION;
+        }
+        self.paused & operation != 0
+    }
+}
+
+/// Rate of a pool in a fixed byte layout, read by cross-chain consumers such as
+/// `TapSOLRate.sol` through Wormhole Queries without decoding `PoolState`.
+///
+/// The three rate fields must stay at `RATE_OFFSET` as consecutive little-endian
+/// u64s, new fields may only take bytes of `reserved`.
+#[account]
+#[derive(InitSpace)]
+pub struct RateSnapshot {
+    /// Layout version of this account.
+    pub version: u8,
+    /// The bump used to generate this account
+    pub bump: u8,
+    /// The jitoSOL mint the pool is keyed by.
+    pub pool: Pubkey,
+    pub pool_mint: Pubkey,
+    /// Slot of the last refresh.
+    pub last_update_slot: u64,
+    pub reserved: [u8; 176],
+
+    /// Pool token supply.
+    pub total_supply: u64,
+    /// SOL value backing the pool tokens, D with the liquid staking tokens converted to SOL
+    /// less the yield pending `distribute_yield`, over `total_supply` the virtual price.
+    pub total_sol_value: u64,
+    /// Epoch of the last refresh.
+    pub last_update_epoch: u64,
+}
+
+impl RateSnapshot {
+    pub const SEED: &'static [u8] = b"rate";
+    pub const SIZE: usize = HEADER_SIZE + RateSnapshot::INIT_SPACE;
+    /// layout version written by `initialize_pool` and `refresh_rate`
+    pub const CURRENT_VERSION: u8 = 1;
+    /// byte offset of `total_supply`, followed by `total_sol_value` and `last_update_epoch`
+    pub const RATE_OFFSET: usize = 258;
+
+    /// set the header of a newly created snapshot
+    pub fn init(&mut self, bump: u8, pool: Pubkey, pool_mint: Pubkey) {
+        self.version = RateSnapshot::CURRENT_VERSION;
+        self.bump = bump;
+        self.pool = pool;
+        self.pool_mint = pool_mint;
+    }
+
+    /// record the pool rate at the given clock
+    pub fn refresh(&mut self, total_supply: u64, total_sol_value: u64, clock: &Clock) {
+        self.total_supply = total_supply;
+        self.total_sol_value = total_sol_value;
+        self.last_update_slot = clock.slot;
+        self.last_update_epoch = clock.epoch;
+    }
+}
+
+const _: () = assert!(RateSnapshot::SIZE == RateSnapshot::RATE_OFFSET + 24);
+
+/// Pool state written before versioning, with a two-asset layout and the
+/// amplitude ramp recorded in epochs. Only read by `migrate_pool`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, Debug)]
+pub struct LegacyPoolState {
+    pub authority: Pubkey,
+    pub pool_mint: Pubkey,
+    pub mint_fee: u64,
+    pub swap_fee: u64,
+    pub redeem_fee: u64,
+    pub total_supply: u64,
+    pub a: u64,
+    pub a_block: u64,
+    pub future_a: u64,
+    pub future_a_block: u64,
+    pub balances: Vec<u64>,
+    pub precisions: Vec<u64>,
+    pub tokens: Vec<Pubkey>,
+    pub pool_initialized: bool,
+    pub token_initialized: bool,
+    pub stake_pool: Pubkey,
+    pub bump: u8,
+}
+
+impl AccountDeserialize for LegacyPoolState {
+    fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+        if buf.len() < HEADER_SIZE || buf[..HEADER_SIZE] != PoolState::DISCRIMINATOR {
+            return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+        let mut data: &[u8] = &buf[HEADER_SIZE..];
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
 }
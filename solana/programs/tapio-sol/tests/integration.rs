@@ -0,0 +1,1648 @@
+//! End-to-end tests running tapio-sol and the stake pool, Marinade and Lido mocks
+//! natively in a `solana-program-test` bank, without a validator or network access.
+//!
+//! The token metadata program is loaded from `tests/fixtures/mpl_token_metadata.so`.
+
+use anchor_lang::{
+    prelude::{AccountInfo, Pubkey},
+    solana_program::{
+        entrypoint::ProgramResult,
+        instruction::{AccountMeta, Instruction},
+        system_instruction,
+    },
+    system_program, AccountDeserialize, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{
+    associated_token::{
+        self, get_associated_token_address,
+        spl_associated_token_account::instruction::create_associated_token_account,
+    },
+    metadata::mpl_token_metadata,
+    token::{self, spl_token},
+};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    program_pack::Pack,
+    signature::{Keypair, Signer},
+    sysvar,
+    transaction::Transaction,
+};
+use tapio_sol::{
+    rate::RateProvider,
+    state::{PoolState, RateSnapshot},
+};
+
+const MINT_SEED: &[u8] = b"mint";
+const HOLDER_SEED: &[u8] = b"holder";
+const SOL: &[u8] = b"sol";
+const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+fn tapio_sol_entry(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    tapio_sol::entry(program_id, accounts, data)
+}
+
+fn stake_pool_test_entry(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    stake_pool_test::entry(program_id, accounts, data)
+}
+
+fn marinade_test_entry(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    marinade_test::entry(program_id, accounts, data)
+}
+
+fn lido_test_entry(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    lido_test::entry(program_id, accounts, data)
+}
+
+struct TestPool {
+    context: ProgramTestContext,
+    jito_sol: Pubkey,
+    stake_pool: Pubkey,
+    state: Pubkey,
+    mint: Pubkey,
+    sol_holder: Pubkey,
+    jito_sol_holder: Pubkey,
+    rate_snapshot: Pubkey,
+}
+
+impl TestPool {
+    /// start a bank with a jitoSOL mint priced at 1.1 SOL, a pool initialized with
+    /// its token and one SOL worth of jitoSOL in the payer account
+    async fn new() -> Self {
+        let mut program_test =
+            ProgramTest::new("tapio_sol", tapio_sol::ID, processor!(tapio_sol_entry));
+        program_test.add_program(
+            "stake_pool_test",
+            stake_pool_test::ID,
+            processor!(stake_pool_test_entry),
+        );
+        program_test.add_program(
+            "marinade_test",
+            marinade_test::ID,
+            processor!(marinade_test_entry),
+        );
+        program_test.add_program("lido_test", lido_test::ID, processor!(lido_test_entry));
+        // the token metadata program runs from its mainnet build in `tests/fixtures`
+        program_test.prefer_bpf(true);
+        program_test.add_program("mpl_token_metadata", mpl_token_metadata::ID, None);
+        let context = program_test.start_with_context().await;
+
+        let jito_sol = Keypair::new();
+        let payer = context.payer.pubkey();
+        let jito_sol_key = jito_sol.pubkey();
+        let find = |seeds: &[&[u8]], program_id: &Pubkey| {
+            Pubkey::find_program_address(seeds, program_id).0
+        };
+        let mut pool = TestPool {
+            jito_sol: jito_sol_key,
+            stake_pool: find(&[b"test", jito_sol_key.as_ref()], &stake_pool_test::ID),
+            state: find(&[PoolState::SEED, jito_sol_key.as_ref()], &tapio_sol::ID),
+            mint: find(&[MINT_SEED, jito_sol_key.as_ref()], &tapio_sol::ID),
+            sol_holder: find(&[HOLDER_SEED, SOL, jito_sol_key.as_ref()], &tapio_sol::ID),
+            jito_sol_holder: find(&[HOLDER_SEED, jito_sol_key.as_ref()], &tapio_sol::ID),
+            rate_snapshot: find(&[RateSnapshot::SEED, jito_sol_key.as_ref()], &tapio_sol::ID),
+            context,
+        };
+
+        let rent = pool.context.banks_client.get_rent().await.unwrap();
+        let user_jito_sol = get_associated_token_address(&payer, &jito_sol_key);
+        pool.process(
+            &[
+                system_instruction::create_account(
+                    &payer,
+                    &jito_sol_key,
+                    rent.minimum_balance(spl_token::state::Mint::LEN),
+                    spl_token::state::Mint::LEN as u64,
+                    &token::ID,
+                ),
+                spl_token::instruction::initialize_mint(&token::ID, &jito_sol_key, &payer, None, 9)
+                    .unwrap(),
+                create_associated_token_account(&payer, &payer, &jito_sol_key, &token::ID),
+                spl_token::instruction::mint_to(
+                    &token::ID,
+                    &jito_sol_key,
+                    &user_jito_sol,
+                    &payer,
+                    &[],
+                    LAMPORTS_PER_SOL,
+                )
+                .unwrap(),
+            ],
+            &[&jito_sol],
+        )
+        .await
+        .unwrap();
+
+        pool.process(
+            &[Instruction {
+                program_id: stake_pool_test::ID,
+                accounts: stake_pool_test::accounts::Initialize {
+                    payer,
+                    pool_mint: jito_sol_key,
+                    stake_pool_account: pool.stake_pool,
+                    stake_pool_program: stake_pool_test::ID,
+                    system_program: system_program::ID,
+                    rent: sysvar::rent::ID,
+                }
+                .to_account_metas(None),
+                data: stake_pool_test::instruction::Initialize {
+                    total_lamports: 11,
+                    pool_token_supply: 10,
+                }
+                .data(),
+            }],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        pool.process(
+            &[Instruction {
+                program_id: tapio_sol::ID,
+                accounts: tapio_sol::accounts::InitializePool {
+                    payer,
+                    jito_sol_mint_account: jito_sol_key,
+                    state_account: pool.state,
+                    sol_program_account: pool.sol_holder,
+                    jito_sol_program_token_account: pool.jito_sol_holder,
+                    stake_pool_account: pool.stake_pool,
+                    rate_snapshot: pool.rate_snapshot,
+                    token_program: token::ID,
+                    system_program: system_program::ID,
+                    rent: sysvar::rent::ID,
+                }
+                .to_account_metas(None),
+                data: tapio_sol::instruction::InitializePool {
+                    mint_fee: 0,
+                    swap_fee: 25_000_000,
+                    redeem_fee: 30_000_000,
+                    a: 1000,
+                }
+                .data(),
+            }],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let metadata = find(
+            &[
+                b"metadata",
+                mpl_token_metadata::ID.as_ref(),
+                pool.mint.as_ref(),
+            ],
+            &mpl_token_metadata::ID,
+        );
+        pool.process(
+            &[Instruction {
+                program_id: tapio_sol::ID,
+                accounts: tapio_sol::accounts::InitializeToken {
+                    payer,
+                    jito_sol_mint_account: jito_sol_key,
+                    mint_account: pool.mint,
+                    state_account: pool.state,
+                    metadata_account: metadata,
+                    token_program: token::ID,
+                    token_metadata_program: mpl_token_metadata::ID,
+                    system_program: system_program::ID,
+                    rent: sysvar::rent::ID,
+                }
+                .to_account_metas(None),
+                data: tapio_sol::instruction::InitializeToken {
+                    token_name: Box::new("Tapio Sol".to_string()),
+                    token_symbol: Box::new("tapSOL".to_string()),
+                    token_uri: Box::new("https://example.com".to_string()),
+                }
+                .data(),
+            }],
+            &[],
+        )
+        .await
+        .unwrap();
+        pool
+    }
+
+    async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let blockhash = self.context.get_new_latest_blockhash().await?;
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.context.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+        self.context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+    }
+
+    async fn pool_state(&mut self) -> PoolState {
+        let account = self
+            .context
+            .banks_client
+            .get_account(self.state)
+            .await
+            .unwrap()
+            .unwrap();
+        PoolState::try_deserialize(&mut &account.data[..]).unwrap()
+    }
+
+    async fn rate_snapshot(&mut self) -> RateSnapshot {
+        let account = self
+            .context
+            .banks_client
+            .get_account(self.rate_snapshot)
+            .await
+            .unwrap()
+            .unwrap();
+        RateSnapshot::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    async fn token_balance(&mut self, mint: &Pubkey) -> u64 {
+        let address = get_associated_token_address(&self.context.payer.pubkey(), mint);
+        self.token_account_balance(address).await
+    }
+
+    async fn token_account_balance(&mut self, address: Pubkey) -> u64 {
+        let account = self
+            .context
+            .banks_client
+            .get_account(address)
+            .await
+            .unwrap()
+            .unwrap();
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    /// `get_virtual_price` of the pool, read from the return data of a simulation
+    async fn virtual_price(&mut self) -> u64 {
+        let instruction = Instruction {
+            program_id: tapio_sol::ID,
+            accounts: tapio_sol::accounts::Quote {
+                mint_account: self.mint,
+                jito_sol_mint_account: self.jito_sol,
+                state_account: self.state,
+                sol_program_account: self.sol_holder,
+                jito_sol_program_token_account: self.jito_sol_holder,
+                stake_pool_account: self.stake_pool,
+            }
+            .to_account_metas(None),
+            data: tapio_sol::instruction::GetVirtualPrice {}.data(),
+        };
+        let blockhash = self.context.get_new_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&self.context.payer.pubkey()),
+            &[&self.context.payer],
+            blockhash,
+        );
+        let simulation = self
+            .context
+            .banks_client
+            .simulate_transaction(transaction)
+            .await
+            .unwrap();
+        let return_data = simulation.simulation_details.unwrap().return_data.unwrap();
+        u64::from_le_bytes(return_data.data.try_into().unwrap())
+    }
+
+    async fn lamports(&mut self, address: Pubkey) -> u64 {
+        self.context
+            .banks_client
+            .get_balance(address)
+            .await
+            .unwrap()
+    }
+
+    /// create a token mint with 9 decimals and the payer as its authority
+    async fn create_token(&mut self) -> Pubkey {
+        let mint = Keypair::new();
+        let payer = self.context.payer.pubkey();
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        self.process(
+            &[
+                system_instruction::create_account(
+                    &payer,
+                    &mint.pubkey(),
+                    rent.minimum_balance(spl_token::state::Mint::LEN),
+                    spl_token::state::Mint::LEN as u64,
+                    &token::ID,
+                ),
+                spl_token::instruction::initialize_mint(
+                    &token::ID,
+                    &mint.pubkey(),
+                    &payer,
+                    None,
+                    9,
+                )
+                .unwrap(),
+            ],
+            &[&mint],
+        )
+        .await
+        .unwrap();
+        mint.pubkey()
+    }
+
+    /// create the mocked Marinade state of `msol_mint`, mSOL priced at 1.2 SOL
+    async fn create_marinade_state(&mut self, msol_mint: Pubkey) -> Pubkey {
+        let payer = self.context.payer.pubkey();
+        let state =
+            Pubkey::find_program_address(&[b"test", msol_mint.as_ref()], &marinade_test::ID).0;
+        let instruction = Instruction {
+            program_id: marinade_test::ID,
+            accounts: marinade_test::accounts::Initialize {
+                payer,
+                msol_mint,
+                marinade_state_account: state,
+                marinade_program: marinade_test::ID,
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: marinade_test::instruction::Initialize {
+                msol_price: 5_153_960_755,
+            }
+            .data(),
+        };
+        self.process(&[instruction], &[]).await.unwrap();
+        state
+    }
+
+    /// create the mocked Lido state of `st_sol_mint`, stSOL priced at 1.2 SOL
+    async fn create_lido_state(&mut self, st_sol_mint: Pubkey) -> Pubkey {
+        let payer = self.context.payer.pubkey();
+        let state =
+            Pubkey::find_program_address(&[b"test", st_sol_mint.as_ref()], &lido_test::ID).0;
+        let instruction = Instruction {
+            program_id: lido_test::ID,
+            accounts: lido_test::accounts::Initialize {
+                payer,
+                st_sol_mint,
+                lido_account: state,
+                lido_program: lido_test::ID,
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: lido_test::instruction::Initialize {
+                sol_balance: 12,
+                st_sol_supply: 10,
+            }
+            .data(),
+        };
+        self.process(&[instruction], &[]).await.unwrap();
+        state
+    }
+
+    fn add_token_instruction(
+        &self,
+        token_mint: Pubkey,
+        stake_pool: Pubkey,
+        rate_provider: RateProvider,
+    ) -> Instruction {
+        let holder = Pubkey::find_program_address(
+            &[HOLDER_SEED, self.jito_sol.as_ref(), token_mint.as_ref()],
+            &tapio_sol::ID,
+        )
+        .0;
+        Instruction {
+            program_id: tapio_sol::ID,
+            accounts: tapio_sol::accounts::AddToken {
+                payer: self.context.payer.pubkey(),
+                jito_sol_mint_account: self.jito_sol,
+                state_account: self.state,
+                token_mint_account: token_mint,
+                token_program_account: holder,
+                stake_pool_account: stake_pool,
+                token_program: token::ID,
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: tapio_sol::instruction::AddToken { rate_provider }.data(),
+        }
+    }
+
+    /// add a liquid staking token backed by a mocked Marinade or Lido state and give the
+    /// payer one token of it, returning the remaining accounts of the asset
+    async fn add_lst(&mut self, rate_provider: RateProvider) -> Vec<AccountMeta> {
+        let payer = self.context.payer.pubkey();
+        let token_mint = self.create_token().await;
+        let stake_pool = match rate_provider {
+            RateProvider::Marinade => self.create_marinade_state(token_mint).await,
+            _ => self.create_lido_state(token_mint).await,
+        };
+        let user_token_account = get_associated_token_address(&payer, &token_mint);
+        let instructions = [
+            self.add_token_instruction(token_mint, stake_pool, rate_provider),
+            create_associated_token_account(&payer, &payer, &token_mint, &token::ID),
+            spl_token::instruction::mint_to(
+                &token::ID,
+                &token_mint,
+                &user_token_account,
+                &payer,
+                &[],
+                LAMPORTS_PER_SOL,
+            )
+            .unwrap(),
+        ];
+        self.process(&instructions, &[]).await.unwrap();
+        let holder = Pubkey::find_program_address(
+            &[HOLDER_SEED, self.jito_sol.as_ref(), token_mint.as_ref()],
+            &tapio_sol::ID,
+        )
+        .0;
+        vec![
+            AccountMeta::new(user_token_account, false),
+            AccountMeta::new(holder, false),
+            AccountMeta::new_readonly(stake_pool, false),
+        ]
+    }
+
+    /// instruction setting the exchange rate of a mocked staking information account to
+    /// 1.2 SOL in the current epoch
+    fn update_rate_instruction(
+        &self,
+        rate_provider: RateProvider,
+        stake_pool: Pubkey,
+    ) -> Instruction {
+        let payer = self.context.payer.pubkey();
+        match rate_provider {
+            RateProvider::Marinade => Instruction {
+                program_id: marinade_test::ID,
+                accounts: marinade_test::accounts::Update {
+                    payer,
+                    marinade_state_account: stake_pool,
+                }
+                .to_account_metas(None),
+                data: marinade_test::instruction::Update {
+                    msol_price: 5_153_960_755,
+                }
+                .data(),
+            },
+            RateProvider::Lido => Instruction {
+                program_id: lido_test::ID,
+                accounts: lido_test::accounts::Update {
+                    payer,
+                    lido_account: stake_pool,
+                }
+                .to_account_metas(None),
+                data: lido_test::instruction::Update {
+                    sol_balance: 12,
+                    st_sol_supply: 10,
+                }
+                .data(),
+            },
+            _ => Instruction {
+                program_id: stake_pool_test::ID,
+                accounts: stake_pool_test::accounts::Update {
+                    payer,
+                    stake_pool_account: stake_pool,
+                }
+                .to_account_metas(None),
+                data: stake_pool_test::instruction::Update {
+                    total_lamports: 11,
+                    pool_token_supply: 10,
+                }
+                .data(),
+            },
+        }
+    }
+
+    fn mint_accounts(&self) -> tapio_sol::accounts::MintShare {
+        let payer = self.context.payer.pubkey();
+        tapio_sol::accounts::MintShare {
+            payer,
+            mint_account: self.mint,
+            jito_sol_mint_account: self.jito_sol,
+            state_account: self.state,
+            mint_token_account: get_associated_token_address(&payer, &self.mint),
+            sol_user_account: payer,
+            jito_sol_user_token_account: get_associated_token_address(&payer, &self.jito_sol),
+            sol_program_account: self.sol_holder,
+            jito_sol_program_token_account: self.jito_sol_holder,
+            stake_pool_account: self.stake_pool,
+            fee_recipient_account: None,
+            rate_snapshot: self.rate_snapshot,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        }
+    }
+
+    fn swap_accounts(&self) -> tapio_sol::accounts::SwapToken {
+        let payer = self.context.payer.pubkey();
+        tapio_sol::accounts::SwapToken {
+            payer,
+            mint_account: self.mint,
+            jito_sol_mint_account: self.jito_sol,
+            state_account: self.state,
+            sol_user_account: payer,
+            jito_sol_user_token_account: get_associated_token_address(&payer, &self.jito_sol),
+            sol_program_account: self.sol_holder,
+            jito_sol_program_token_account: self.jito_sol_holder,
+            stake_pool_account: self.stake_pool,
+            fee_recipient_account: None,
+            rate_snapshot: self.rate_snapshot,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        }
+    }
+
+    fn redeem_accounts(&self) -> tapio_sol::accounts::RedeemShare {
+        let payer = self.context.payer.pubkey();
+        tapio_sol::accounts::RedeemShare {
+            payer,
+            mint_account: self.mint,
+            jito_sol_mint_account: self.jito_sol,
+            state_account: self.state,
+            mint_token_account: get_associated_token_address(&payer, &self.mint),
+            sol_user_account: payer,
+            jito_sol_user_token_account: get_associated_token_address(&payer, &self.jito_sol),
+            sol_program_account: self.sol_holder,
+            jito_sol_program_token_account: self.jito_sol_holder,
+            stake_pool_account: self.stake_pool,
+            fee_recipient_account: None,
+            rate_snapshot: self.rate_snapshot,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        }
+    }
+
+    async fn mint(&mut self, amounts: Vec<u64>, min_mint_amount: u64) {
+        let instruction = Instruction {
+            program_id: tapio_sol::ID,
+            accounts: self.mint_accounts().to_account_metas(None),
+            data: tapio_sol::instruction::Mint {
+                amounts,
+                min_mint_amount,
+            }
+            .data(),
+        };
+        self.process(&[instruction], &[]).await.unwrap();
+    }
+
+    /// mint 0.1 SOL and 0.1 SOL worth of jitoSOL
+    async fn mint_initial(&mut self) {
+        self.mint(vec![100_000_000, 100_000_000], 0).await;
+    }
+}
+
+#[tokio::test]
+async fn initialize_success() {
+    let mut pool = TestPool::new().await;
+    let state = pool.pool_state().await;
+
+    assert_eq!(state.authority, pool.context.payer.pubkey());
+    assert_eq!(state.tokens, vec![system_program::ID, pool.jito_sol]);
+    assert_eq!(state.stake_pools, vec![Pubkey::default(), pool.stake_pool]);
+    assert_eq!(state.balances, vec![0, 0]);
+    assert_eq!(state.swap_fee, 25_000_000);
+    assert_eq!(state.redeem_fee, 30_000_000);
+    assert_eq!(state.a, 1000);
+    assert!(state.pool_initialized);
+    assert!(state.token_initialized);
+    assert_eq!(state.pool_mint, pool.mint);
+
+    let metadata_address = mpl_token_metadata::accounts::Metadata::find_pda(&pool.mint).0;
+    let account = pool
+        .context
+        .banks_client
+        .get_account(metadata_address)
+        .await
+        .unwrap()
+        .unwrap();
+    let metadata = mpl_token_metadata::accounts::Metadata::from_bytes(&account.data).unwrap();
+    assert_eq!(metadata.mint, pool.mint);
+    assert_eq!(metadata.update_authority, pool.mint);
+    assert_eq!(metadata.symbol.trim_end_matches('\0'), "tapSOL");
+}
+
+#[tokio::test]
+async fn mint_success() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+    let state = pool.pool_state().await;
+
+    assert_eq!(state.total_supply, 200_000_000);
+    assert_eq!(state.balances, vec![100_000_000, 100_000_000]);
+    assert_eq!(pool.token_balance(&pool.mint.clone()).await, 200_000_000);
+    assert_eq!(
+        pool.token_balance(&pool.jito_sol.clone()).await,
+        LAMPORTS_PER_SOL - 90_909_091
+    );
+}
+
+#[tokio::test]
+async fn mint_under_min_failure() {
+    let mut pool = TestPool::new().await;
+    let instruction = Instruction {
+        program_id: tapio_sol::ID,
+        accounts: pool.mint_accounts().to_account_metas(None),
+        data: tapio_sol::instruction::Mint {
+            amounts: vec![100_000_000, 100_000_000],
+            min_mint_amount: 200_000_001,
+        }
+        .data(),
+    };
+    assert!(pool.process(&[instruction], &[]).await.is_err());
+    assert_eq!(pool.pool_state().await.total_supply, 0);
+}
+
+/// mint instruction with the remaining accounts of the assets after jitoSOL
+fn mint_instruction(
+    pool: &TestPool,
+    amounts: Vec<u64>,
+    remaining_accounts: Vec<AccountMeta>,
+) -> Instruction {
+    let mut accounts = pool.mint_accounts().to_account_metas(None);
+    accounts.extend(remaining_accounts);
+    Instruction {
+        program_id: tapio_sol::ID,
+        accounts,
+        data: tapio_sol::instruction::Mint {
+            amounts,
+            min_mint_amount: 0,
+        }
+        .data(),
+    }
+}
+
+#[tokio::test]
+async fn mint_with_stale_stake_pool_rate_failure() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+    pool.context.warp_to_epoch(1).unwrap();
+
+    let instruction = mint_instruction(&pool, vec![1_000_000, 1_000_000], Vec::new());
+    assert!(pool
+        .process(std::slice::from_ref(&instruction), &[])
+        .await
+        .is_err());
+
+    let update = pool.update_rate_instruction(RateProvider::SplStakePool, pool.stake_pool);
+    pool.process(&[update, instruction], &[]).await.unwrap();
+    assert_eq!(pool.pool_state().await.balances[0], 101_000_000);
+}
+
+/// mint into a pool holding an asset of `rate_provider` once its rate is an epoch old,
+/// which fails until the rate is updated
+async fn mint_with_stale_rate(rate_provider: RateProvider) {
+    let mut pool = TestPool::new().await;
+    let remaining_accounts = pool.add_lst(rate_provider).await;
+    let stake_pool = remaining_accounts[2].pubkey;
+    let amounts = vec![100_000_000, 100_000_000, 100_000_000];
+    let instruction = mint_instruction(&pool, amounts, remaining_accounts.clone());
+    pool.process(&[instruction], &[]).await.unwrap();
+
+    pool.context.warp_to_epoch(1).unwrap();
+    let update = pool.update_rate_instruction(RateProvider::SplStakePool, pool.stake_pool);
+    let amounts = vec![1_000_000, 1_000_000, 1_000_000];
+    let instruction = mint_instruction(&pool, amounts, remaining_accounts);
+    assert!(pool
+        .process(&[update.clone(), instruction.clone()], &[])
+        .await
+        .is_err());
+
+    let update_lst = pool.update_rate_instruction(rate_provider, stake_pool);
+    pool.process(&[update, update_lst, instruction], &[])
+        .await
+        .unwrap();
+    assert_eq!(pool.pool_state().await.balances[0], 101_000_000);
+}
+
+#[tokio::test]
+async fn mint_with_stale_marinade_rate_failure() {
+    mint_with_stale_rate(RateProvider::Marinade).await;
+}
+
+#[tokio::test]
+async fn mint_with_stale_lido_rate_failure() {
+    mint_with_stale_rate(RateProvider::Lido).await;
+}
+
+#[tokio::test]
+async fn add_token_marinade_of_another_mint_failure() {
+    let mut pool = TestPool::new().await;
+    let msol = pool.create_token().await;
+    let other_mint = pool.create_token().await;
+    let other_state = pool.create_marinade_state(other_mint).await;
+    let instruction = pool.add_token_instruction(msol, other_state, RateProvider::Marinade);
+    assert!(pool.process(&[instruction], &[]).await.is_err());
+
+    let state = pool.create_marinade_state(msol).await;
+    let instruction = pool.add_token_instruction(msol, state, RateProvider::Marinade);
+    pool.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(pool.pool_state().await.tokens[2], msol);
+}
+
+#[tokio::test]
+async fn add_token_lido_of_another_mint_failure() {
+    let mut pool = TestPool::new().await;
+    let st_sol = pool.create_token().await;
+    let other_mint = pool.create_token().await;
+    let other_state = pool.create_lido_state(other_mint).await;
+    let instruction = pool.add_token_instruction(st_sol, other_state, RateProvider::Lido);
+    assert!(pool.process(&[instruction], &[]).await.is_err());
+
+    let state = pool.create_lido_state(st_sol).await;
+    let instruction = pool.add_token_instruction(st_sol, state, RateProvider::Lido);
+    pool.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(pool.pool_state().await.tokens[2], st_sol);
+}
+
+#[tokio::test]
+async fn swap_success() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+    let sol_holder_before = pool.lamports(pool.sol_holder).await;
+    let jito_sol_before = pool.token_balance(&pool.jito_sol.clone()).await;
+
+    let instruction = Instruction {
+        program_id: tapio_sol::ID,
+        accounts: pool.swap_accounts().to_account_metas(None),
+        data: tapio_sol::instruction::Swap {
+            i: 0,
+            j: 1,
+            dx: 1_000_000,
+            min_dy: 0,
+        }
+        .data(),
+    };
+    pool.process(&[instruction], &[]).await.unwrap();
+    let state = pool.pool_state().await;
+
+    assert_eq!(
+        pool.lamports(pool.sol_holder).await,
+        sol_holder_before + 1_000_000
+    );
+    assert!(pool.token_balance(&pool.jito_sol.clone()).await > jito_sol_before);
+    assert_eq!(state.balances[0], 101_000_000);
+    assert!(state.balances[1] < 100_000_000);
+    assert!(state.total_supply >= 200_000_000);
+}
+
+#[tokio::test]
+async fn swap_refreshes_rate_snapshot() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+
+    let instruction = Instruction {
+        program_id: tapio_sol::ID,
+        accounts: pool.swap_accounts().to_account_metas(None),
+        data: tapio_sol::instruction::Swap {
+            i: 0,
+            j: 1,
+            dx: 1_000_000,
+            min_dy: 0,
+        }
+        .data(),
+    };
+    pool.process(&[instruction], &[]).await.unwrap();
+    let state = pool.pool_state().await;
+    let snapshot = pool.rate_snapshot().await;
+
+    assert_eq!(snapshot.total_sol_value, state.backing_value());
+    assert_eq!(snapshot.total_supply, 200_000_000);
+}
+
+#[tokio::test]
+async fn swap_with_another_rate_snapshot_failure() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+
+    let mut accounts = pool.swap_accounts();
+    accounts.rate_snapshot = pool.state;
+    let instruction = Instruction {
+        program_id: tapio_sol::ID,
+        accounts: accounts.to_account_metas(None),
+        data: tapio_sol::instruction::Swap {
+            i: 0,
+            j: 1,
+            dx: 1_000_000,
+            min_dy: 0,
+        }
+        .data(),
+    };
+    assert!(pool.process(&[instruction], &[]).await.is_err());
+    assert_eq!(pool.pool_state().await.balances[0], 100_000_000);
+}
+
+#[tokio::test]
+async fn redeem_proportion_success() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+
+    let instruction = Instruction {
+        program_id: tapio_sol::ID,
+        accounts: pool.redeem_accounts().to_account_metas(None),
+        data: tapio_sol::instruction::RedeemProportion {
+            amount: 1_000_000,
+            min_redeem_amounts: vec![0, 0],
+        }
+        .data(),
+    };
+    pool.process(&[instruction], &[]).await.unwrap();
+    let state = pool.pool_state().await;
+
+    assert_eq!(pool.token_balance(&pool.mint.clone()).await, 199_000_000);
+    assert_eq!(state.total_supply, 199_003_000);
+    assert_eq!(state.balances, vec![99_501_500, 99_501_500]);
+}
+
+#[tokio::test]
+async fn redeem_fee_goes_to_liquidity_providers() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+
+    // another liquidity provider holds half of the pool tokens while the payer redeems the rest
+    let payer = pool.context.payer.pubkey();
+    let provider = Keypair::new();
+    let provider_account = get_associated_token_address(&provider.pubkey(), &pool.mint);
+    let instructions = [
+        create_associated_token_account(&payer, &provider.pubkey(), &pool.mint, &token::ID),
+        spl_token::instruction::transfer(
+            &token::ID,
+            &get_associated_token_address(&payer, &pool.mint),
+            &provider_account,
+            &payer,
+            &[],
+            100_000_000,
+        )
+        .unwrap(),
+        Instruction {
+            program_id: tapio_sol::ID,
+            accounts: pool.redeem_accounts().to_account_metas(None),
+            data: tapio_sol::instruction::RedeemProportion {
+                amount: 100_000_000,
+                min_redeem_amounts: vec![0, 0],
+            }
+            .data(),
+        },
+    ];
+    pool.process(&instructions, &[]).await.unwrap();
+    let state = pool.pool_state().await;
+    let supply = pool.token_account_balance(provider_account).await;
+    assert_eq!(pool.token_balance(&pool.mint.clone()).await, 0);
+    assert_eq!(supply, 100_000_000);
+
+    // the whole 0.3% redeem fee of the payer stays in D behind the tokens of the provider
+    let fee = 300_000;
+    assert!(state.total_supply.abs_diff(supply + fee) <= 1);
+    let result = tapio_sol_math::get_redeem_proportion_amount(
+        &tapio_sol::pool::math_pool_at(&state, supply, 0),
+        supply,
+    )
+    .unwrap();
+    // the provider pays the redeem fee on its own tokens but takes out the fee of the payer
+    assert!(result.redeem_amount >= supply - fee + fee * (supply - fee) / supply - 1);
+}
+
+#[tokio::test]
+async fn virtual_price_matches_redeem_proportion() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+    let redeem = |amount: u64| Instruction {
+        program_id: tapio_sol::ID,
+        accounts: pool.redeem_accounts().to_account_metas(None),
+        data: tapio_sol::instruction::RedeemProportion {
+            amount,
+            min_redeem_amounts: vec![0, 0],
+        }
+        .data(),
+    };
+    let amount = 100_000_000;
+    let first_redeem = redeem(10_000_000);
+    let second_redeem = redeem(amount);
+    pool.process(&[first_redeem], &[]).await.unwrap();
+    let virtual_price = pool.virtual_price().await;
+    // the redeem fee kept by the pool raised the worth of every pool token
+    assert!(virtual_price > tapio_sol::pool::VIRTUAL_PRICE_PRECISION);
+
+    let sol_before = pool.lamports(pool.sol_holder).await;
+    let jito_sol_before = pool.token_account_balance(pool.jito_sol_holder).await;
+    pool.process(&[second_redeem], &[]).await.unwrap();
+    let sol_out = sol_before - pool.lamports(pool.sol_holder).await;
+    let jito_sol_out = jito_sol_before - pool.token_account_balance(pool.jito_sol_holder).await;
+
+    // the payout in SOL, jitoSOL at 1.1 SOL, is the virtual price of the tokens net of the
+    // 0.3% redeem fee, rounded down by a few lamports for the pool in each conversion
+    let payout = sol_out + jito_sol_out * 11 / 10;
+    let expected = (amount - amount * 3 / 1000) * virtual_price / 1_000_000_000;
+    assert!(payout <= expected);
+    assert!(expected - payout <= 4);
+}
+
+#[tokio::test]
+async fn redeem_proportion_without_converging_invariant() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+    // 635 SOL donated to the holder leaves an invariant that does not converge
+    let payer = pool.context.payer.pubkey();
+    let donation = system_instruction::transfer(&payer, &pool.sol_holder, 635 * LAMPORTS_PER_SOL);
+    pool.process(&[donation], &[]).await.unwrap();
+
+    let swap = Instruction {
+        program_id: tapio_sol::ID,
+        accounts: pool.swap_accounts().to_account_metas(None),
+        data: tapio_sol::instruction::Swap {
+            i: 0,
+            j: 1,
+            dx: 1_000_000,
+            min_dy: 0,
+        }
+        .data(),
+    };
+    assert!(pool.process(&[swap], &[]).await.is_err());
+
+    let sol_before = pool.lamports(payer).await;
+    let redeem = Instruction {
+        program_id: tapio_sol::ID,
+        accounts: pool.redeem_accounts().to_account_metas(None),
+        data: tapio_sol::instruction::RedeemProportion {
+            amount: 1_000_000,
+            min_redeem_amounts: vec![0, 0],
+        }
+        .data(),
+    };
+    pool.process(&[redeem], &[]).await.unwrap();
+
+    // the share of the donation is redeemed against the recorded D
+    assert_eq!(pool.token_balance(&pool.mint.clone()).await, 199_000_000);
+    assert!(pool.lamports(payer).await > sol_before + 3 * LAMPORTS_PER_SOL);
+    assert_eq!(pool.pool_state().await.total_supply, 199_003_000);
+}
+
+#[tokio::test]
+async fn redeem_single_success() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+    let sol_holder_before = pool.lamports(pool.sol_holder).await;
+
+    let instruction = Instruction {
+        program_id: tapio_sol::ID,
+        accounts: pool.redeem_accounts().to_account_metas(None),
+        data: tapio_sol::instruction::RedeemSingle {
+            amount: 1_000_000,
+            i: 0,
+            min_redeem_amount: 0,
+        }
+        .data(),
+    };
+    pool.process(&[instruction], &[]).await.unwrap();
+    let state = pool.pool_state().await;
+
+    assert_eq!(pool.token_balance(&pool.mint.clone()).await, 199_000_000);
+    let redeemed = sol_holder_before - pool.lamports(pool.sol_holder).await;
+    assert!(redeemed > 0 && redeemed < 1_000_000);
+    // the pool keeps the rounding of the output
+    assert!(100_000_000 - redeemed - state.balances[0] <= 1);
+    assert_eq!(state.balances[1], 100_000_000);
+}
+
+#[tokio::test]
+async fn redeem_under_min_failure() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+
+    let instruction = Instruction {
+        program_id: tapio_sol::ID,
+        accounts: pool.redeem_accounts().to_account_metas(None),
+        data: tapio_sol::instruction::RedeemSingle {
+            amount: 1_000_000,
+            i: 0,
+            min_redeem_amount: 1_000_000,
+        }
+        .data(),
+    };
+    assert!(pool.process(&[instruction], &[]).await.is_err());
+    assert_eq!(pool.token_balance(&pool.mint.clone()).await, 200_000_000);
+}
+
+#[tokio::test]
+async fn distribute_yield_leaves_swap_fees() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+    let payer = pool.context.payer.pubkey();
+    let swap = |dx: u64| Instruction {
+        program_id: tapio_sol::ID,
+        accounts: pool.swap_accounts().to_account_metas(None),
+        data: tapio_sol::instruction::Swap {
+            i: 0,
+            j: 1,
+            dx,
+            min_dy: 0,
+        }
+        .data(),
+    };
+    let first_swap = swap(10_000_000);
+    let second_swap = swap(1_000_000);
+    pool.process(&[first_swap], &[]).await.unwrap();
+    assert_eq!(pool.pool_state().await.pending_yield, 0);
+
+    // jitoSOL appreciates from 1.1 to 1.2 SOL, recorded by the next swap
+    let update = Instruction {
+        program_id: stake_pool_test::ID,
+        accounts: stake_pool_test::accounts::Update {
+            payer,
+            stake_pool_account: pool.stake_pool,
+        }
+        .to_account_metas(None),
+        data: stake_pool_test::instruction::Update {
+            total_lamports: 12,
+            pool_token_supply: 10,
+        }
+        .data(),
+    };
+    let state_before = pool.pool_state().await;
+    pool.process(&[update, second_swap], &[]).await.unwrap();
+    let state = pool.pool_state().await;
+    assert!(state.pending_yield > 0);
+    assert!(state.total_supply > state_before.total_supply + state.pending_yield);
+    // the rate snapshot leaves out the yield not minted yet, like the virtual price
+    let snapshot = pool.rate_snapshot().await;
+    assert_eq!(
+        snapshot.total_sol_value,
+        state.total_supply - state.pending_yield
+    );
+
+    let recipient_owner = Keypair::new();
+    let recipient = get_associated_token_address(&recipient_owner.pubkey(), &pool.mint);
+    let set_yield_recipient = set_yield_recipient_instruction(&pool, payer, recipient, 0);
+    let distribute_yield = distribute_yield_instruction(&pool, recipient, None);
+    pool.process(
+        &[
+            create_associated_token_account(
+                &payer,
+                &recipient_owner.pubkey(),
+                &pool.mint,
+                &token::ID,
+            ),
+            set_yield_recipient,
+            distribute_yield,
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // the minted pool tokens are worth the yield at the worth of the pool tokens already
+    // issued, D now holds the fee of the second swap as well
+    let pending_yield = state.pending_yield;
+    let holder_supply = pool.token_balance(&pool.mint.clone()).await;
+    let minted = pool.token_account_balance(recipient).await;
+    let state = pool.pool_state().await;
+    assert_eq!(state.pending_yield, 0);
+    assert_eq!(
+        u128::from(minted),
+        u128::from(pending_yield) * u128::from(holder_supply)
+            / u128::from(state.total_supply - pending_yield)
+    );
+    // the liquidity provider share of both swap fees raises the worth of every pool token
+    assert!(state.total_supply > holder_supply + minted);
+}
+
+fn set_fee_recipient_instruction(
+    pool: &TestPool,
+    signer: Pubkey,
+    recipient: Pubkey,
+    protocol_fee_share: u64,
+) -> Instruction {
+    Instruction {
+        program_id: tapio_sol::ID,
+        accounts: tapio_sol::accounts::SetFeeRecipient {
+            payer: signer,
+            jito_sol_mint_account: pool.jito_sol,
+            state_account: pool.state,
+            fee_recipient_account: recipient,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: tapio_sol::instruction::SetFeeRecipient { protocol_fee_share }.data(),
+    }
+}
+
+#[tokio::test]
+async fn set_recipients_by_fee_manager_failure() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+    let payer = pool.context.payer.pubkey();
+    let fee_manager = Keypair::new();
+    let set_fee_manager = Instruction {
+        program_id: tapio_sol::ID,
+        accounts: tapio_sol::accounts::ModifyPool {
+            payer,
+            jito_sol_mint_account: pool.jito_sol,
+            state_account: pool.state,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: tapio_sol::instruction::SetRole {
+            role: tapio_sol::state::Role::FeeManager,
+            key: fee_manager.pubkey(),
+        }
+        .data(),
+    };
+    let recipient = get_associated_token_address(&fee_manager.pubkey(), &pool.mint);
+    pool.process(
+        &[
+            set_fee_manager,
+            create_associated_token_account(&payer, &fee_manager.pubkey(), &pool.mint, &token::ID),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let instruction = set_fee_recipient_instruction(&pool, fee_manager.pubkey(), recipient, 0);
+    assert!(pool.process(&[instruction], &[&fee_manager]).await.is_err());
+    let instruction = set_yield_recipient_instruction(&pool, fee_manager.pubkey(), recipient, 0);
+    assert!(pool.process(&[instruction], &[&fee_manager]).await.is_err());
+    let state = pool.pool_state().await;
+    assert_eq!(state.fee_recipient, Pubkey::default());
+    assert_eq!(state.yield_recipient, Pubkey::default());
+}
+
+#[tokio::test]
+async fn set_fee_recipient_above_cap_failure() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+    let payer = pool.context.payer.pubkey();
+    let recipient = get_associated_token_address(&payer, &pool.mint);
+
+    let instruction = set_fee_recipient_instruction(
+        &pool,
+        payer,
+        recipient,
+        tapio_sol::MAX_PROTOCOL_FEE_SHARE + 1,
+    );
+    assert!(pool.process(&[instruction], &[]).await.is_err());
+    let instruction =
+        set_fee_recipient_instruction(&pool, payer, recipient, tapio_sol::MAX_PROTOCOL_FEE_SHARE);
+    pool.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(
+        pool.pool_state().await.protocol_fee_share,
+        tapio_sol::MAX_PROTOCOL_FEE_SHARE
+    );
+}
+
+#[tokio::test]
+async fn set_yield_recipient_above_cap_failure() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+    let payer = pool.context.payer.pubkey();
+    let recipient = get_associated_token_address(&payer, &pool.mint);
+
+    let instruction = set_yield_recipient_instruction(
+        &pool,
+        payer,
+        recipient,
+        tapio_sol::MAX_PERFORMANCE_FEE + 1,
+    );
+    assert!(pool.process(&[instruction], &[]).await.is_err());
+    let instruction =
+        set_yield_recipient_instruction(&pool, payer, recipient, tapio_sol::MAX_PERFORMANCE_FEE);
+    pool.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(
+        pool.pool_state().await.performance_fee,
+        tapio_sol::MAX_PERFORMANCE_FEE
+    );
+}
+
+fn set_yield_recipient_instruction(
+    pool: &TestPool,
+    signer: Pubkey,
+    recipient: Pubkey,
+    performance_fee: u64,
+) -> Instruction {
+    Instruction {
+        program_id: tapio_sol::ID,
+        accounts: tapio_sol::accounts::SetYieldRecipient {
+            payer: signer,
+            jito_sol_mint_account: pool.jito_sol,
+            state_account: pool.state,
+            yield_recipient_account: recipient,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: tapio_sol::instruction::SetYieldRecipient { performance_fee }.data(),
+    }
+}
+
+fn distribute_yield_instruction(
+    pool: &TestPool,
+    recipient: Pubkey,
+    treasury: Option<Pubkey>,
+) -> Instruction {
+    Instruction {
+        program_id: tapio_sol::ID,
+        accounts: tapio_sol::accounts::DistributeYield {
+            mint_account: pool.mint,
+            jito_sol_mint_account: pool.jito_sol,
+            state_account: pool.state,
+            sol_program_account: pool.sol_holder,
+            jito_sol_program_token_account: pool.jito_sol_holder,
+            stake_pool_account: pool.stake_pool,
+            yield_recipient_account: recipient,
+            treasury_token_account: treasury,
+            rate_snapshot: pool.rate_snapshot,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: tapio_sol::instruction::DistributeYield {}.data(),
+    }
+}
+
+#[tokio::test]
+async fn distribute_yield_performance_fee_without_treasury() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+    let payer = pool.context.payer.pubkey();
+
+    // jitoSOL appreciates from 1.1 to 1.2 SOL, recorded by the distribution
+    let update = Instruction {
+        program_id: stake_pool_test::ID,
+        accounts: stake_pool_test::accounts::Update {
+            payer,
+            stake_pool_account: pool.stake_pool,
+        }
+        .to_account_metas(None),
+        data: stake_pool_test::instruction::Update {
+            total_lamports: 12,
+            pool_token_supply: 10,
+        }
+        .data(),
+    };
+    let recipient_owner = Keypair::new();
+    let recipient = get_associated_token_address(&recipient_owner.pubkey(), &pool.mint);
+    // the treasury is unset, the authority takes the 10% performance fee
+    let authority_account = get_associated_token_address(&payer, &pool.mint);
+    let nobody_account = get_associated_token_address(&Pubkey::default(), &pool.mint);
+    pool.process(
+        &[
+            update,
+            create_associated_token_account(
+                &payer,
+                &recipient_owner.pubkey(),
+                &pool.mint,
+                &token::ID,
+            ),
+            create_associated_token_account(&payer, &Pubkey::default(), &pool.mint, &token::ID),
+            set_yield_recipient_instruction(&pool, payer, recipient, 1_000_000_000),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    let distribute_to_nobody = distribute_yield_instruction(&pool, recipient, Some(nobody_account));
+    assert!(pool.process(&[distribute_to_nobody], &[]).await.is_err());
+
+    let distribute_yield = distribute_yield_instruction(&pool, recipient, Some(authority_account));
+    pool.process(&[distribute_yield], &[]).await.unwrap();
+    let minted = pool.token_account_balance(recipient).await;
+    let performance_fee = pool.token_balance(&pool.mint.clone()).await - 200_000_000;
+    assert!(minted > 0);
+    assert_eq!(performance_fee, (minted + performance_fee) / 10);
+}
+
+/// pool token ATA of the pool state holding `amount` pool tokens from the payer
+async fn fund_insurance(pool: &mut TestPool, amount: u64) -> Pubkey {
+    let payer = pool.context.payer.pubkey();
+    let insurance = get_associated_token_address(&pool.state, &pool.mint);
+    let instructions = [
+        create_associated_token_account(&payer, &pool.state, &pool.mint, &token::ID),
+        spl_token::instruction::transfer(
+            &token::ID,
+            &get_associated_token_address(&payer, &pool.mint),
+            &insurance,
+            &payer,
+            &[],
+            amount,
+        )
+        .unwrap(),
+    ];
+    pool.process(&instructions, &[]).await.unwrap();
+    insurance
+}
+
+fn set_treasury_instruction(pool: &TestPool, key: Pubkey) -> Instruction {
+    Instruction {
+        program_id: tapio_sol::ID,
+        accounts: tapio_sol::accounts::ModifyPool {
+            payer: pool.context.payer.pubkey(),
+            jito_sol_mint_account: pool.jito_sol,
+            state_account: pool.state,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: tapio_sol::instruction::SetRole {
+            role: tapio_sol::state::Role::Treasury,
+            key,
+        }
+        .data(),
+    }
+}
+
+fn withdraw_insurance_instruction(
+    pool: &TestPool,
+    signer: Pubkey,
+    destination: Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: tapio_sol::ID,
+        accounts: tapio_sol::accounts::WithdrawInsurance {
+            payer: signer,
+            mint_account: pool.mint,
+            jito_sol_mint_account: pool.jito_sol,
+            state_account: pool.state,
+            sol_program_account: pool.sol_holder,
+            jito_sol_program_token_account: pool.jito_sol_holder,
+            stake_pool_account: pool.stake_pool,
+            insurance_token_account: get_associated_token_address(&pool.state, &pool.mint),
+            destination_token_account: destination,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: tapio_sol::instruction::WithdrawInsurance { amount }.data(),
+    }
+}
+
+#[tokio::test]
+async fn withdraw_insurance_success() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+    let insurance = fund_insurance(&mut pool, 10_000_000).await;
+    let treasury = Keypair::new();
+    let set_role = set_treasury_instruction(&pool, treasury.pubkey());
+    pool.process(&[set_role], &[]).await.unwrap();
+
+    let destination = get_associated_token_address(&pool.context.payer.pubkey(), &pool.mint);
+    let instruction =
+        withdraw_insurance_instruction(&pool, treasury.pubkey(), destination, 4_000_000);
+    pool.process(&[instruction], &[&treasury]).await.unwrap();
+
+    assert_eq!(pool.token_account_balance(insurance).await, 6_000_000);
+    assert_eq!(pool.token_balance(&pool.mint.clone()).await, 194_000_000);
+}
+
+#[tokio::test]
+async fn withdraw_insurance_unauthorized() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+    let insurance = fund_insurance(&mut pool, 10_000_000).await;
+    let other = Keypair::new();
+
+    let destination = get_associated_token_address(&pool.context.payer.pubkey(), &pool.mint);
+    let instruction = withdraw_insurance_instruction(&pool, other.pubkey(), destination, 4_000_000);
+    assert!(pool.process(&[instruction], &[&other]).await.is_err());
+    assert_eq!(pool.token_account_balance(insurance).await, 10_000_000);
+}
+
+#[tokio::test]
+async fn withdraw_insurance_after_rate_drop_failure() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+    let insurance = fund_insurance(&mut pool, 10_000_000).await;
+    let treasury = Keypair::new();
+    let set_role = set_treasury_instruction(&pool, treasury.pubkey());
+    pool.process(&[set_role], &[]).await.unwrap();
+
+    // jitoSOL drops from 1.1 to 1 SOL, no pool operation records the loss before the withdrawal
+    let update = Instruction {
+        program_id: stake_pool_test::ID,
+        accounts: stake_pool_test::accounts::Update {
+            payer: pool.context.payer.pubkey(),
+            stake_pool_account: pool.stake_pool,
+        }
+        .to_account_metas(None),
+        data: stake_pool_test::instruction::Update {
+            total_lamports: 10,
+            pool_token_supply: 10,
+        }
+        .data(),
+    };
+    pool.process(&[update], &[]).await.unwrap();
+
+    let destination = get_associated_token_address(&pool.context.payer.pubkey(), &pool.mint);
+    let instruction =
+        withdraw_insurance_instruction(&pool, treasury.pubkey(), destination, 4_000_000);
+    assert!(pool.process(&[instruction], &[&treasury]).await.is_err());
+    assert_eq!(pool.token_account_balance(insurance).await, 10_000_000);
+}
+
+fn cover_loss_instruction(pool: &TestPool, signer: Pubkey) -> Instruction {
+    Instruction {
+        program_id: tapio_sol::ID,
+        accounts: tapio_sol::accounts::CoverLoss {
+            payer: signer,
+            mint_account: pool.mint,
+            jito_sol_mint_account: pool.jito_sol,
+            state_account: pool.state,
+            insurance_token_account: get_associated_token_address(&pool.state, &pool.mint),
+            rate_snapshot: pool.rate_snapshot,
+            token_program: token::ID,
+        }
+        .to_account_metas(None),
+        data: tapio_sol::instruction::CoverLoss {}.data(),
+    }
+}
+
+/// drop jitoSOL from 1.1 to 1 SOL and record the loss with a proportional redeem
+async fn record_loss(pool: &mut TestPool) {
+    let update = Instruction {
+        program_id: stake_pool_test::ID,
+        accounts: stake_pool_test::accounts::Update {
+            payer: pool.context.payer.pubkey(),
+            stake_pool_account: pool.stake_pool,
+        }
+        .to_account_metas(None),
+        data: stake_pool_test::instruction::Update {
+            total_lamports: 10,
+            pool_token_supply: 10,
+        }
+        .data(),
+    };
+    let redeem = Instruction {
+        program_id: tapio_sol::ID,
+        accounts: pool.redeem_accounts().to_account_metas(None),
+        data: tapio_sol::instruction::RedeemProportion {
+            amount: 1_000_000,
+            min_redeem_amounts: vec![0, 0],
+        }
+        .data(),
+    };
+    pool.process(&[update, redeem], &[]).await.unwrap();
+    assert!(pool.pool_state().await.loss_mode);
+}
+
+#[tokio::test]
+async fn cover_loss_success() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+    let insurance = fund_insurance(&mut pool, 10_000_000).await;
+    let treasury = Keypair::new();
+    let set_role = set_treasury_instruction(&pool, treasury.pubkey());
+    pool.process(&[set_role], &[]).await.unwrap();
+    record_loss(&mut pool).await;
+
+    let instruction = cover_loss_instruction(&pool, treasury.pubkey());
+    pool.process(&[instruction], &[&treasury]).await.unwrap();
+    let state = pool.pool_state().await;
+
+    assert!(!state.loss_mode);
+    let burned = 10_000_000 - pool.token_account_balance(insurance).await;
+    assert_eq!(state.total_supply, 199_000_000 - burned);
+}
+
+#[tokio::test]
+async fn cover_loss_unauthorized() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+    let insurance = fund_insurance(&mut pool, 10_000_000).await;
+    record_loss(&mut pool).await;
+    let other = Keypair::new();
+
+    let instruction = cover_loss_instruction(&pool, other.pubkey());
+    assert!(pool.process(&[instruction], &[&other]).await.is_err());
+    assert_eq!(pool.token_account_balance(insurance).await, 10_000_000);
+    assert!(pool.pool_state().await.loss_mode);
+}
+
+#[tokio::test]
+async fn cover_loss_without_loss_failure() {
+    let mut pool = TestPool::new().await;
+    pool.mint_initial().await;
+    let insurance = fund_insurance(&mut pool, 10_000_000).await;
+
+    let instruction = cover_loss_instruction(&pool, pool.context.payer.pubkey());
+    assert!(pool.process(&[instruction], &[]).await.is_err());
+    assert_eq!(pool.token_account_balance(insurance).await, 10_000_000);
+}
+
+fn set_stake_pool_programs_instruction(
+    pool: &TestPool,
+    stake_pool_programs: Vec<Pubkey>,
+) -> Instruction {
+    let mut accounts = tapio_sol::accounts::ModifyPool {
+        payer: pool.context.payer.pubkey(),
+        jito_sol_mint_account: pool.jito_sol,
+        state_account: pool.state,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    accounts.push(AccountMeta::new_readonly(pool.stake_pool, false));
+    Instruction {
+        program_id: tapio_sol::ID,
+        accounts,
+        data: tapio_sol::instruction::SetStakePoolPrograms {
+            stake_pool_programs,
+        }
+        .data(),
+    }
+}
+
+#[tokio::test]
+async fn set_stake_pool_programs_success() {
+    let mut pool = TestPool::new().await;
+    let instruction = set_stake_pool_programs_instruction(&pool, vec![stake_pool_test::ID]);
+    pool.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(
+        pool.pool_state().await.stake_pool_programs,
+        vec![stake_pool_test::ID]
+    );
+    pool.mint_initial().await;
+}
+
+#[tokio::test]
+async fn set_stake_pool_programs_dropping_owner_failure() {
+    let mut pool = TestPool::new().await;
+    let programs_before = pool.pool_state().await.stake_pool_programs;
+    // the jitoSOL stake pool is owned by the stake pool mock
+    let instruction = set_stake_pool_programs_instruction(&pool, vec![marinade_test::ID]);
+    assert!(pool.process(&[instruction], &[]).await.is_err());
+    assert_eq!(pool.pool_state().await.stake_pool_programs, programs_before);
+}
+
+#[tokio::test]
+async fn modify_a_success() {
+    let mut pool = TestPool::new().await;
+    let clock = pool
+        .context
+        .banks_client
+        .get_sysvar::<solana_sdk::clock::Clock>()
+        .await
+        .unwrap();
+    let future_a_time = clock.unix_timestamp as u64 + 2 * 86400;
+
+    let instruction = Instruction {
+        program_id: tapio_sol::ID,
+        accounts: tapio_sol::accounts::ModifyA {
+            payer: pool.context.payer.pubkey(),
+            jito_sol_mint_account: pool.jito_sol,
+            state_account: pool.state,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        }
+        .to_account_metas(None),
+        data: tapio_sol::instruction::ModifyA {
+            a: 2000,
+            future_a_time,
+        }
+        .data(),
+    };
+    pool.process(&[instruction], &[]).await.unwrap();
+    let state = pool.pool_state().await;
+
+    assert_eq!(state.a, 1000);
+    assert_eq!(state.future_a, 2000);
+    assert_eq!(state.future_a_time, future_a_time);
+}
+
+#[tokio::test]
+async fn modify_a_unauthorized() {
+    let mut pool = TestPool::new().await;
+    let other = Keypair::new();
+    let instruction = Instruction {
+        program_id: tapio_sol::ID,
+        accounts: tapio_sol::accounts::ModifyA {
+            payer: other.pubkey(),
+            jito_sol_mint_account: pool.jito_sol,
+            state_account: pool.state,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        }
+        .to_account_metas(None),
+        data: tapio_sol::instruction::ModifyA {
+            a: 2000,
+            future_a_time: u64::MAX,
+        }
+        .data(),
+    };
+    assert!(pool.process(&[instruction], &[&other]).await.is_err());
+    assert_eq!(pool.pool_state().await.future_a, 1000);
+}
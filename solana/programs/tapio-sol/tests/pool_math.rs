@@ -0,0 +1,372 @@
+//! Property tests of the StableSwap math in `pool`, run on the host.
+//!
+//! Failing cases shrink to the smallest balances and amplitude reproducing them
+//! and are recorded in `pool_math.proptest-regressions` next to this file.
+//!
+//! `reference` keeps the former `u128` solvers, the 256-bit ones must agree with
+//! them wherever those do not overflow.
+
+use anchor_lang::{
+    prelude::{Account, AccountInfo, Clock, Pubkey},
+    solana_program::program_stubs::{set_syscall_stubs, SyscallStubs},
+    AccountSerialize,
+};
+use proptest::{prelude::*, test_runner::FileFailurePersistence};
+use std::sync::Once;
+use tapio_sol::{errors::ErrorCode, pool, state::PoolState};
+
+/// `Clock::get` at unix timestamp zero, the ramps below are always finished
+struct ClockStubs;
+
+impl SyscallStubs for ClockStubs {
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Clock) = Clock::default() };
+        0
+    }
+}
+
+/// `get_d` and `get_y` as they were with `u128` intermediates
+mod reference {
+    const A_PRECISION: u128 = 100;
+
+    pub fn get_d(balances: &[u64], a: u64) -> Option<u64> {
+        let one: u128 = 1;
+        let mut sum: u128 = 0;
+        let mut ann: u128 = u128::from(a);
+        let balance_size: u128 = u128::try_from(balances.len()).ok()?;
+        for x in balances.iter() {
+            sum = sum.checked_add(u128::from(*x))?;
+            ann = ann.checked_mul(balance_size)?;
+        }
+        if sum == 0 {
+            return Some(0);
+        }
+
+        let mut d: u128 = sum;
+        for _i in 0..255 {
+            let mut p_d: u128 = d;
+            for x in balances.iter() {
+                let div_op: u128 = u128::from(*x).checked_mul(balance_size)?;
+                p_d = p_d.checked_mul(d)?.checked_div(div_op)?;
+            }
+            let prev_d = d;
+            let t1: u128 = p_d.checked_mul(balance_size)?;
+            let t2: u128 = balance_size.checked_add(one)?.checked_mul(p_d)?;
+            let t3: u128 = ann
+                .checked_sub(A_PRECISION)?
+                .checked_mul(d)?
+                .checked_div(A_PRECISION)?
+                .checked_add(t2)?;
+            d = ann
+                .checked_mul(sum)?
+                .checked_div(A_PRECISION)?
+                .checked_add(t1)?
+                .checked_mul(d)?
+                .checked_div(t3)?;
+            if d.abs_diff(prev_d) <= one {
+                break;
+            }
+        }
+        u64::try_from(d).ok()
+    }
+
+    pub fn get_y(
+        balances: &[u64],
+        token_index: usize,
+        target_d: u64,
+        amplitude: u64,
+    ) -> Option<u64> {
+        let one: u128 = 1;
+        let mut c: u128 = u128::from(target_d);
+        let mut sum: u128 = 0;
+        let mut ann: u128 = u128::from(amplitude);
+        let balance_size: u128 = u128::try_from(balances.len()).ok()?;
+        let target_d: u128 = u128::from(target_d);
+
+        for (i, x) in balances.iter().enumerate() {
+            let balance: u128 = u128::from(*x);
+            ann = ann.checked_mul(balance_size)?;
+            if i == token_index {
+                continue;
+            }
+            sum = sum.checked_add(balance)?;
+            let div_op: u128 = balance.checked_mul(balance_size)?;
+            c = c.checked_mul(target_d)?.checked_div(div_op)?
+        }
+
+        c = c
+            .checked_mul(target_d)?
+            .checked_mul(A_PRECISION)?
+            .checked_div(ann.checked_mul(balance_size)?)?;
+        let b: u128 = sum.checked_add(target_d.checked_mul(A_PRECISION)?.checked_div(ann)?)?;
+        let mut y: u128 = target_d;
+        for _i in 0..255 {
+            let prev_y = y;
+            y = y
+                .checked_mul(y)?
+                .checked_add(c)?
+                .checked_div(y.checked_mul(2)?.checked_add(b)?.checked_sub(target_d)?)?;
+            if y.abs_diff(prev_y) <= one {
+                break;
+            }
+        }
+        u64::try_from(y).ok()
+    }
+}
+
+/// pool state account of the balances, failing where their invariant does not converge
+///
+/// The properties below issue the pool tokens one to one with D, passing D as their supply.
+fn pool_account(
+    balances: Vec<u64>,
+    a: u64,
+    fee: u64,
+) -> anchor_lang::Result<Account<'static, PoolState>> {
+    static STUBS: Once = Once::new();
+    STUBS.call_once(|| {
+        set_syscall_stubs(Box::new(ClockStubs));
+    });
+
+    let state = PoolState {
+        mint_fee: fee,
+        swap_fee: fee,
+        redeem_fee: fee,
+        total_supply: pool::get_d(&balances, a)?.0,
+        a,
+        future_a: a,
+        precisions: vec![1; balances.len()],
+        tokens: vec![Pubkey::default(); balances.len()],
+        balances,
+        ..PoolState::default()
+    };
+    let mut data = Vec::new();
+    state.try_serialize(&mut data)?;
+    let info = Box::leak(Box::new(AccountInfo::new(
+        Box::leak(Box::new(Pubkey::new_unique())),
+        false,
+        false,
+        Box::leak(Box::new(0u64)),
+        Box::leak(data.into_boxed_slice()),
+        &tapio_sol::ID,
+        false,
+        0,
+    )));
+    Account::try_from(&*info)
+}
+
+/// whether the operation failed because Newton's method did not converge, a nearly
+/// emptied asset leaves an invariant no solver iteration settles
+fn did_not_converge<T>(result: &anchor_lang::Result<T>) -> bool {
+    matches!(result, Err(error) if *error == ErrorCode::InvariantDidNotConverge.into())
+}
+
+/// 2 to 4 balances between 0.001 and 1M SOL, at most 100 times apart
+fn balances() -> impl Strategy<Value = Vec<u64>> {
+    (2usize..=4, 1_000_000u64..=10_000_000_000_000)
+        .prop_flat_map(|(n, base)| proptest::collection::vec(base..=base.saturating_mul(100), n))
+}
+
+/// 2 to 4 balances up to 1B SOL, far past where `u128` overflows but with a D
+/// still fitting in u64
+fn wide_balances() -> impl Strategy<Value = Vec<u64>> {
+    (2usize..=4, 1_000_000u64..=10_000_000_000_000_000)
+        .prop_flat_map(|(n, base)| proptest::collection::vec(base..=base.saturating_mul(100), n))
+}
+
+/// amplitude with `A_PRECISION`, from 1 to 5000
+fn amplitude() -> impl Strategy<Value = u64> {
+    100u64..=500_000
+}
+
+/// fee over `FEE_PRECISION`, up to `MAX_FEE`
+fn fee() -> impl Strategy<Value = u64> {
+    0u64..=pool::MAX_FEE
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        failure_persistence: Some(Box::new(FileFailurePersistence::WithSource("proptest-regressions"))),
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn get_d_converges_to_get_y(balances in balances(), a in amplitude()) {
+        let (d, _) = pool::get_d(&balances, a).unwrap();
+        let sum: u64 = balances.iter().sum();
+        prop_assert!(d <= sum);
+        for (i, balance) in balances.iter().enumerate() {
+            let (y, _) = pool::get_y(&balances, i, d, a).unwrap();
+            // both solvers stop within one unit of their fixed point
+            prop_assert!(y.abs_diff(*balance) <= balances.len() as u64 + 2);
+        }
+    }
+
+    #[test]
+    fn mint_never_decreases_d(
+        balances in balances(),
+        a in amplitude(),
+        fee in fee(),
+        amount in 1_000u64..=1_000_000_000_000,
+        index in 0usize..4,
+    ) {
+        let pool_info = pool_account(balances.clone(), a, fee).unwrap();
+        let mut amounts = vec![0; balances.len()];
+        amounts[index % balances.len()] = amount;
+        let result = pool::get_mint_amount(&pool_info, pool_info.total_supply, &amounts).unwrap();
+
+        prop_assert!(result.total_supply >= pool_info.total_supply);
+        prop_assert_eq!(
+            result.mint_amount + result.fee_amount,
+            result.total_supply - pool_info.total_supply
+        );
+    }
+
+    #[test]
+    fn swap_never_decreases_d(
+        balances in balances(),
+        a in amplitude(),
+        fee in fee(),
+        dx in 1_000u64..=1_000_000_000_000,
+        (i, offset) in (0usize..4, 0usize..3),
+    ) {
+        let n = balances.len();
+        let i = i % n;
+        let j = (i + 1 + offset % (n - 1)) % n;
+        let pool_info = pool_account(balances, a, fee).unwrap();
+        let result = pool::get_swap_amount(&pool_info, pool_info.total_supply, i, j, dx);
+        prop_assume!(!did_not_converge(&result));
+        let result = result.unwrap();
+
+        prop_assert!(result.total_supply >= pool_info.total_supply);
+        prop_assert!(result.balance_i > pool_info.balances[i]);
+        prop_assert!(result.balance_j < pool_info.balances[j]);
+    }
+
+    #[test]
+    fn swap_round_trip_never_profits(
+        balances in balances(),
+        a in amplitude(),
+        fee in fee(),
+        dx in 1_000u64..=1_000_000_000_000,
+    ) {
+        // larger inputs only drain the output asset further
+        let dx = dx.min(balances[0]);
+        let pool_info = pool_account(balances.clone(), a, fee).unwrap();
+        let forward = pool::get_swap_amount(&pool_info, pool_info.total_supply, 0, 1, dx);
+        prop_assume!(!did_not_converge(&forward));
+        let forward = forward.unwrap();
+        prop_assume!(forward.dy > 0);
+
+        let mut swapped = balances;
+        swapped[0] = forward.balance_i;
+        swapped[1] = forward.balance_j;
+        // a nearly emptied output asset leaves an invariant that does not converge
+        let pool_info = pool_account(swapped, a, fee);
+        prop_assume!(pool_info.is_ok());
+        let pool_info = pool_info.unwrap();
+        let backward = pool::get_swap_amount(&pool_info, pool_info.total_supply, 1, 0, forward.dy);
+        prop_assume!(!did_not_converge(&backward));
+        let backward = backward.unwrap();
+        prop_assert!(backward.dy <= dx);
+    }
+
+    #[test]
+    fn redeem_proportion_never_exceeds_share(
+        balances in balances(),
+        a in amplitude(),
+        fee in fee(),
+        share in 1u64..=1_000_000,
+    ) {
+        let pool_info = pool_account(balances.clone(), a, fee).unwrap();
+        let supply = pool_info.total_supply;
+        let amount = (u128::from(supply) * u128::from(share) / 1_000_000) as u64;
+        prop_assume!(amount > 0);
+        let result = pool::get_redeem_proportion_amount(&pool_info, supply, amount).unwrap();
+
+        for (output, balance) in result.amounts.iter().zip(balances.iter()) {
+            prop_assert!(
+                u128::from(*output) * u128::from(supply)
+                    <= u128::from(*balance) * u128::from(amount)
+            );
+        }
+        prop_assert!(result.total_supply <= supply);
+    }
+
+    #[test]
+    fn redeem_single_never_exceeds_share(
+        balances in balances(),
+        a in amplitude(),
+        fee in fee(),
+        share in 1u64..=100_000,
+        index in 0usize..4,
+    ) {
+        let i = index % balances.len();
+        let pool_info = pool_account(balances.clone(), a, fee).unwrap();
+        let supply = pool_info.total_supply;
+        let amount = (u128::from(supply) * u128::from(share) / 1_000_000) as u64;
+        prop_assume!(amount > 1);
+        let result = pool::get_redeem_single_amount(&pool_info, supply, amount, i);
+        prop_assume!(!did_not_converge(&result));
+        let result = result.unwrap();
+
+        prop_assert!(result.dy < balances[i]);
+        prop_assert!(result.total_supply >= supply - amount);
+        // the kept balance is within one unit of the exact solution for the recorded D
+        let mut above = result.balances.clone();
+        above[i] += 1;
+        prop_assert!(pool::get_d(&above, a).unwrap().0 >= result.total_supply);
+        // at the rate and precision of one used here, the scarcest asset pays at most the
+        // proportional share of D net of the fee, a more abundant one is worth less than one
+        // D each and pays out more of it
+        let share_value = u128::from(amount - result.fee_amount)
+            * u128::from(pool_info.total_supply)
+            / u128::from(supply);
+        if balances.iter().all(|balance| *balance >= balances[i]) {
+            prop_assert!(u128::from(result.dy) <= share_value);
+        }
+        // minting the payout back never returns more pool tokens than were redeemed
+        let redeemed = pool_account(result.balances.clone(), a, fee);
+        prop_assume!(redeemed.is_ok());
+        let redeemed = redeemed.unwrap();
+        let mut amounts = vec![0; balances.len()];
+        amounts[i] = result.dy;
+        prop_assume!(result.dy > 0);
+        let minted = pool::get_mint_amount(&redeemed, redeemed.total_supply, &amounts).unwrap();
+        prop_assert!(minted.mint_amount <= amount);
+    }
+
+    #[test]
+    fn get_d_matches_u128(balances in wide_balances(), a in amplitude()) {
+        let (wide, iterations) = pool::get_d(&balances, a).unwrap();
+        prop_assert!(iterations > 0);
+        if let Some(narrow) = reference::get_d(&balances, a) {
+            prop_assert_eq!(wide, narrow);
+        }
+    }
+
+    #[test]
+    fn get_y_matches_u128(balances in wide_balances(), a in amplitude(), index in 0usize..4) {
+        let i = index % balances.len();
+        let (d, _) = pool::get_d(&balances, a).unwrap();
+        let (wide, iterations) = pool::get_y(&balances, i, d, a).unwrap();
+        prop_assert!(iterations > 0);
+        if let Some(narrow) = reference::get_y(&balances, i, d, a) {
+            prop_assert_eq!(wide, narrow);
+        }
+    }
+}
+
+#[test]
+fn get_d_at_tens_of_millions_of_sol() {
+    // 4 x 20M SOL at A = 2000 overflows the u128 solver in its final product
+    let balances = [20_000_000_000_000_000u64; 4];
+    let a = 200_000;
+    assert_eq!(reference::get_d(&balances, a), None);
+    assert_eq!(pool::get_d(&balances, a).unwrap().0, 80_000_000_000_000_000);
+    assert_eq!(
+        pool::get_y(&balances, 0, 80_000_000_000_000_000, a)
+            .unwrap()
+            .0,
+        20_000_000_000_000_000
+    );
+}
@@ -2,6 +2,8 @@ import * as anchor from "@coral-xyz/anchor";
 import { AnchorError, AnchorProvider, Program } from "@coral-xyz/anchor";
 import { TapioSol } from "../target/types/tapio_sol";
 import { StakePoolTest } from "../target/types/stake_pool_test";
+import { MarinadeTest } from "../target/types/marinade_test";
+import { LidoTest } from "../target/types/lido_test";
 
 import {
   createMint,
@@ -22,6 +24,9 @@ describe("tapio-sol", () => {
   const tapioSolProgram = anchor.workspace.TapioSol as Program<TapioSol>;
   const stakeTestProgram = anchor.workspace
     .StakePoolTest as Program<StakePoolTest>;
+  const marinadeTestProgram = anchor.workspace
+    .MarinadeTest as Program<MarinadeTest>;
+  const lidoTestProgram = anchor.workspace.LidoTest as Program<LidoTest>;
   const MINT_SEED = "mint";
   const STATE_SEED = "state";
   const HOLDER_SEED = "holder";
@@ -29,11 +34,9 @@ describe("tapio-sol", () => {
   const mintAuthSC = anchor.web3.Keypair.generate();
   const tokenPayer = anchor.web3.Keypair.generate();
 
-  const [testState] = anchor.web3.PublicKey.findProgramAddressSync(
-    [Buffer.from("test")],
-    stakeTestProgram.programId,
-  );
   const provider = anchor.AnchorProvider.env();
+  // a week ahead, beyond the minimum ramp duration
+  const futureATime = new anchor.BN(Math.floor(Date.now() / 1000) + 604800);
   const payer = provider.wallet as anchor.Wallet;
 
   before(async () => {
@@ -70,7 +73,7 @@ describe("tapio-sol", () => {
         )
         .accounts({
           jitoSolMintAccount: poolAccounts.jitoSol,
-          stakePoolAccount: testState,
+          stakePoolAccount: poolAccounts.stakePool,
         })
         .rpc();
       assert.ok(false);
@@ -99,6 +102,85 @@ describe("tapio-sol", () => {
     }
   });
 
+  it("initialized with stake pool of another mint failure", async () => {
+    const jitoSol = await createToken(provider, mintAuthSC, tokenPayer);
+    const otherMint = await createToken(provider, mintAuthSC, tokenPayer);
+    const otherStakePool = await createTestStakePool(otherMint);
+    try {
+      await tapioSolProgram.methods
+        .initializePool(
+          new anchor.BN(0),
+          new anchor.BN(25000000),
+          new anchor.BN(30000000),
+          new anchor.BN(1000),
+        )
+        .accounts({
+          jitoSolMintAccount: jitoSol,
+          stakePoolAccount: otherStakePool,
+        })
+        .rpc();
+      assert.ok(false);
+    } catch (_err) {
+      assert.isTrue(_err instanceof AnchorError);
+      const err: AnchorError = _err;
+      const errMsg = "Token failed validation.";
+      assert.strictEqual(err.error.errorMessage, errMsg);
+      assert.strictEqual(err.error.errorCode.number, 6003);
+    }
+  });
+
+  it("setStakePoolPrograms success", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    assert.strictEqual(
+      (await tapioSolProgram.account.poolState.fetch(poolAccounts.state))
+        .stakePoolPrograms.length,
+      2,
+    );
+    await tapioSolProgram.methods
+      .setStakePoolPrograms([stakeTestProgram.programId])
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+      })
+      .remainingAccounts([
+        { pubkey: poolAccounts.stakePool, isSigner: false, isWritable: false },
+      ])
+      .rpc();
+    const poolState = await tapioSolProgram.account.poolState.fetch(
+      poolAccounts.state,
+    );
+    assert.strictEqual(poolState.stakePoolPrograms.length, 1);
+    assert.ok(
+      poolState.stakePoolPrograms[0].equals(stakeTestProgram.programId),
+    );
+    await mintInitial(poolAccounts);
+  });
+
+  it("setStakePoolPrograms dropping the owner of a stake pool failure", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    try {
+      await tapioSolProgram.methods
+        .setStakePoolPrograms([marinadeTestProgram.programId])
+        .accounts({
+          jitoSolMintAccount: poolAccounts.jitoSol,
+        })
+        .remainingAccounts([
+          {
+            pubkey: poolAccounts.stakePool,
+            isSigner: false,
+            isWritable: false,
+          },
+        ])
+        .rpc();
+      assert.ok(false);
+    } catch (_err) {
+      assert.isTrue(_err instanceof AnchorError);
+      const err: AnchorError = _err;
+      const errMsg = "Token failed validation.";
+      assert.strictEqual(err.error.errorMessage, errMsg);
+      assert.strictEqual(err.error.errorCode.number, 6003);
+    }
+  });
+
   it("mint success", async () => {
     const poolAccounts = await createPool(provider, payer);
     await tapioSolProgram.methods
@@ -114,7 +196,7 @@ describe("tapio-sol", () => {
         ),
         solUserAccount: payer.publicKey,
         jitoSolMintAccount: poolAccounts.jitoSol,
-        stakePoolAccount: testState,
+        stakePoolAccount: poolAccounts.stakePool,
       })
       .rpc();
     await logBalance(
@@ -156,6 +238,192 @@ describe("tapio-sol", () => {
     assert.ok(poolState.balances[1].eq(new anchor.BN(100000000)));
   });
 
+  it("add token success", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    const mSol = await createToken(provider, mintAuthSC, tokenPayer);
+    await mintToken(
+      provider,
+      tokenPayer,
+      mSol,
+      mintAuthSC,
+      LAMPORTS_PER_SOL,
+      payer.publicKey,
+    );
+    const [mSolHolder] = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from(HOLDER_SEED),
+        poolAccounts.jitoSol.toBytes(),
+        mSol.toBytes(),
+      ],
+      tapioSolProgram.programId,
+    );
+    const mSolStakePool = await createTestStakePool(mSol);
+    await tapioSolProgram.methods
+      .addToken({ splStakePool: {} })
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+        tokenMintAccount: mSol,
+        stakePoolAccount: mSolStakePool,
+      })
+      .rpc();
+
+    await tapioSolProgram.methods
+      .mint(
+        [
+          new anchor.BN(100000000),
+          new anchor.BN(100000000),
+          new anchor.BN(100000000),
+        ],
+        new anchor.BN(0),
+      )
+      .accounts({
+        payer: payer.publicKey,
+        jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
+          poolAccounts.jitoSol,
+          payer.publicKey,
+        ),
+        solUserAccount: payer.publicKey,
+        jitoSolMintAccount: poolAccounts.jitoSol,
+        stakePoolAccount: poolAccounts.stakePool,
+      })
+      .remainingAccounts([
+        {
+          pubkey: getAssociatedTokenAddressSync(mSol, payer.publicKey),
+          isSigner: false,
+          isWritable: true,
+        },
+        { pubkey: mSolHolder, isSigner: false, isWritable: true },
+        { pubkey: mSolStakePool, isSigner: false, isWritable: false },
+      ])
+      .rpc();
+
+    const poolState = await tapioSolProgram.account.poolState.fetch(
+      poolAccounts.state,
+    );
+    assert.ok(poolState.tokens[2].equals(mSol));
+    assert.ok(poolState.precisions[2].eq(new anchor.BN(1)));
+    assert.ok(
+      (await getTokenBalance(provider, poolAccounts.mint, payer.publicKey)) ==
+        0.3,
+    );
+    assert.ok(
+      (await getTokenBalanceWithTokenAccount(provider, mSolHolder)) ==
+        0.090909091,
+    );
+    assert.ok(poolState.totalSupply.eq(new anchor.BN(300000000)));
+    assert.ok(poolState.balances[2].eq(new anchor.BN(100000000)));
+  });
+
+  it("add token with marinade and lido rates success", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    const remainingAccounts = [];
+    const holders = [];
+    for (const [rateProvider, createRateAccount] of [
+      [{ marinade: {} }, createTestMarinadeState],
+      [{ lido: {} }, createTestLidoState],
+    ]) {
+      const lst = await createToken(provider, mintAuthSC, tokenPayer);
+      const rateAccount = await createRateAccount(lst);
+      await mintToken(
+        provider,
+        tokenPayer,
+        lst,
+        mintAuthSC,
+        LAMPORTS_PER_SOL,
+        payer.publicKey,
+      );
+      const [holder] = anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from(HOLDER_SEED),
+          poolAccounts.jitoSol.toBytes(),
+          lst.toBytes(),
+        ],
+        tapioSolProgram.programId,
+      );
+      await tapioSolProgram.methods
+        .addToken(rateProvider)
+        .accounts({
+          jitoSolMintAccount: poolAccounts.jitoSol,
+          tokenMintAccount: lst,
+          stakePoolAccount: rateAccount,
+        })
+        .rpc();
+      holders.push(holder);
+      remainingAccounts.push(
+        {
+          pubkey: getAssociatedTokenAddressSync(lst, payer.publicKey),
+          isSigner: false,
+          isWritable: true,
+        },
+        { pubkey: holder, isSigner: false, isWritable: true },
+        { pubkey: rateAccount, isSigner: false, isWritable: false },
+      );
+    }
+
+    await tapioSolProgram.methods
+      .mint(
+        [
+          new anchor.BN(100000000),
+          new anchor.BN(100000000),
+          new anchor.BN(100000000),
+          new anchor.BN(100000000),
+        ],
+        new anchor.BN(0),
+      )
+      .accounts({
+        payer: payer.publicKey,
+        jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
+          poolAccounts.jitoSol,
+          payer.publicKey,
+        ),
+        solUserAccount: payer.publicKey,
+        jitoSolMintAccount: poolAccounts.jitoSol,
+        stakePoolAccount: poolAccounts.stakePool,
+      })
+      .remainingAccounts(remainingAccounts)
+      .rpc();
+
+    const poolState = await tapioSolProgram.account.poolState.fetch(
+      poolAccounts.state,
+    );
+    assert.ok(poolState.totalSupply.eq(new anchor.BN(400000000)));
+    for (const holder of holders) {
+      assert.ok(
+        (await getTokenBalanceWithTokenAccount(provider, holder)) ==
+          0.083333334,
+      );
+    }
+  });
+
+  it("add token with marinade and lido rates of another mint failure", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    for (const [rateProvider, createRateAccount] of [
+      [{ marinade: {} }, createTestMarinadeState],
+      [{ lido: {} }, createTestLidoState],
+    ]) {
+      const lst = await createToken(provider, mintAuthSC, tokenPayer);
+      const otherMint = await createToken(provider, mintAuthSC, tokenPayer);
+      const rateAccount = await createRateAccount(otherMint);
+      try {
+        await tapioSolProgram.methods
+          .addToken(rateProvider)
+          .accounts({
+            jitoSolMintAccount: poolAccounts.jitoSol,
+            tokenMintAccount: lst,
+            stakePoolAccount: rateAccount,
+          })
+          .rpc();
+        assert.ok(false);
+      } catch (_err) {
+        assert.isTrue(_err instanceof AnchorError);
+        const err: AnchorError = _err;
+        const errMsg = "Token failed validation.";
+        assert.strictEqual(err.error.errorMessage, errMsg);
+        assert.strictEqual(err.error.errorCode.number, 6003);
+      }
+    }
+  });
+
   it("swap success", async () => {
     const poolAccounts = await createPool(provider, payer);
     await mintInitial(poolAccounts);
@@ -169,7 +437,7 @@ describe("tapio-sol", () => {
         ),
         solUserAccount: payer.publicKey,
         jitoSolMintAccount: poolAccounts.jitoSol,
-        stakePoolAccount: testState,
+        stakePoolAccount: poolAccounts.stakePool,
       })
       .rpc();
     await logBalance(
@@ -219,7 +487,7 @@ describe("tapio-sol", () => {
         ),
         solUserAccount: payer.publicKey,
         jitoSolMintAccount: poolAccounts.jitoSol,
-        stakePoolAccount: testState,
+        stakePoolAccount: poolAccounts.stakePool,
       })
       .rpc();
     await logBalance(
@@ -260,6 +528,66 @@ describe("tapio-sol", () => {
     assert.ok(poolState.balances[1].eq(new anchor.BN(100502975)));
   });
 
+  it("swap exact out success", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await mintInitial(poolAccounts);
+    const solBefore = await getBalanceWithTokenAccount(
+      provider,
+      poolAccounts.solHolder,
+    );
+    await tapioSolProgram.methods
+      .swapExactOut(0, 1, new anchor.BN(1000000), new anchor.BN(1100000))
+      .accounts({
+        payer: payer.publicKey,
+        jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
+          poolAccounts.jitoSol,
+          payer.publicKey,
+        ),
+        solUserAccount: payer.publicKey,
+        jitoSolMintAccount: poolAccounts.jitoSol,
+        stakePoolAccount: poolAccounts.stakePool,
+      })
+      .rpc();
+    const poolState = await tapioSolProgram.account.poolState.fetch(
+      poolAccounts.state,
+    );
+    const solAfter = await getBalanceWithTokenAccount(
+      provider,
+      poolAccounts.solHolder,
+    );
+    assert.ok(solAfter - solBefore > 0.001);
+    assert.ok(solAfter - solBefore <= 0.0011);
+    assert.ok(poolState.balances[1].lt(new anchor.BN(99000000)));
+    assert.ok(poolState.totalSupply.eq(new anchor.BN(200000000)));
+  });
+
+  it("swap exact out failure", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await mintInitial(poolAccounts);
+    try {
+      await tapioSolProgram.methods
+        .swapExactOut(0, 1, new anchor.BN(1000000), new anchor.BN(1000000))
+        .accounts({
+          payer: payer.publicKey,
+          jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
+            poolAccounts.jitoSol,
+            payer.publicKey,
+          ),
+          solUserAccount: payer.publicKey,
+          jitoSolMintAccount: poolAccounts.jitoSol,
+          stakePoolAccount: poolAccounts.stakePool,
+        })
+        .rpc();
+      assert.ok(false);
+    } catch (_err) {
+      assert.isTrue(_err instanceof AnchorError);
+      const err: AnchorError = _err;
+      const errMsg = "Swap above maximum.";
+      assert.strictEqual(err.error.errorMessage, errMsg);
+      assert.strictEqual(err.error.errorCode.number, 6011);
+    }
+  });
+
   it("redeem proportion success", async () => {
     const poolAccounts = await createPool(provider, payer);
     await mintInitial(poolAccounts);
@@ -276,7 +604,7 @@ describe("tapio-sol", () => {
         ),
         solUserAccount: payer.publicKey,
         jitoSolMintAccount: poolAccounts.jitoSol,
-        stakePoolAccount: testState,
+        stakePoolAccount: poolAccounts.stakePool,
       })
       .rpc();
     await logBalance(
@@ -293,7 +621,7 @@ describe("tapio-sol", () => {
     );
     assert.ok(
       (await getTokenBalance(provider, poolAccounts.mint, payer.publicKey)) ==
-        0.199003,
+        0.199,
     );
     assert.ok(
       (await getTokenBalance(
@@ -330,7 +658,7 @@ describe("tapio-sol", () => {
         ),
         solUserAccount: payer.publicKey,
         jitoSolMintAccount: poolAccounts.jitoSol,
-        stakePoolAccount: testState,
+        stakePoolAccount: poolAccounts.stakePool,
       })
       .rpc();
     await logBalance(
@@ -347,7 +675,7 @@ describe("tapio-sol", () => {
     );
     assert.ok(
       (await getTokenBalance(provider, poolAccounts.mint, payer.publicKey)) ==
-        0.199003,
+        0.199,
     );
     assert.ok(
       (await getTokenBalance(
@@ -380,7 +708,7 @@ describe("tapio-sol", () => {
         ),
         solUserAccount: payer.publicKey,
         jitoSolMintAccount: poolAccounts.jitoSol,
-        stakePoolAccount: testState,
+        stakePoolAccount: poolAccounts.stakePool,
       })
       .rpc();
     await logBalance(
@@ -397,7 +725,7 @@ describe("tapio-sol", () => {
     );
     assert.ok(
       (await getTokenBalance(provider, poolAccounts.mint, payer.publicKey)) ==
-        0.198006,
+        0.198,
     );
     assert.ok(
       (await getTokenBalance(
@@ -421,14 +749,18 @@ describe("tapio-sol", () => {
     assert.ok(poolState.balances[1].eq(new anchor.BN(99002881)));
   });
 
-  it("mint failure", async () => {
+  it("loss mode redeem proportion only", async () => {
     const poolAccounts = await createPool(provider, payer);
+    await mintInitial(poolAccounts);
+    await stakeTestProgram.methods
+      .update(new anchor.BN(10), new anchor.BN(10))
+      .accounts({
+        stakePoolAccount: poolAccounts.stakePool,
+      })
+      .rpc();
     try {
       await tapioSolProgram.methods
-        .mint(
-          [new anchor.BN(100000000), new anchor.BN(100000000)],
-          new anchor.BN(1000000000),
-        )
+        .swap(0, 1, new anchor.BN(1000000), new anchor.BN(0))
         .accounts({
           payer: payer.publicKey,
           jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
@@ -437,25 +769,90 @@ describe("tapio-sol", () => {
           ),
           solUserAccount: payer.publicKey,
           jitoSolMintAccount: poolAccounts.jitoSol,
-          stakePoolAccount: testState,
+          stakePoolAccount: poolAccounts.stakePool,
         })
         .rpc();
       assert.ok(false);
     } catch (_err) {
       assert.isTrue(_err instanceof AnchorError);
       const err: AnchorError = _err;
-      const errMsg = "Mint below minimum.";
+      const errMsg = "Pool imbalanced.";
       assert.strictEqual(err.error.errorMessage, errMsg);
-      assert.strictEqual(err.error.errorCode.number, 6004);
+      assert.strictEqual(err.error.errorCode.number, 6007);
     }
+    await tapioSolProgram.methods
+      .redeemProportion(new anchor.BN(1000000), [
+        new anchor.BN(0),
+        new anchor.BN(0),
+      ])
+      .accounts({
+        payer: payer.publicKey,
+        jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
+          poolAccounts.jitoSol,
+          payer.publicKey,
+        ),
+        solUserAccount: payer.publicKey,
+        jitoSolMintAccount: poolAccounts.jitoSol,
+        stakePoolAccount: poolAccounts.stakePool,
+      })
+      .rpc();
+    const poolState = await tapioSolProgram.account.poolState.fetch(
+      poolAccounts.state,
+    );
+    assert.ok(poolState.lossMode);
+    assert.ok(poolState.totalSupply.lt(new anchor.BN(199000000)));
+    assert.ok(
+      (await getBalanceWithTokenAccount(provider, poolAccounts.solHolder)) ==
+        0.1995015,
+    );
   });
 
-  it("swap failure", async () => {
+  it("redeem multi success", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await mintInitial(poolAccounts);
+    await tapioSolProgram.methods
+      .redeemMulti(
+        [new anchor.BN(1000000), new anchor.BN(0)],
+        new anchor.BN(1100000),
+      )
+      .accounts({
+        payer: payer.publicKey,
+        jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
+          poolAccounts.jitoSol,
+          payer.publicKey,
+        ),
+        solUserAccount: payer.publicKey,
+        jitoSolMintAccount: poolAccounts.jitoSol,
+        stakePoolAccount: poolAccounts.stakePool,
+      })
+      .rpc();
+    const poolState = await tapioSolProgram.account.poolState.fetch(
+      poolAccounts.state,
+    );
+    const poolTokens = await getTokenBalance(
+      provider,
+      poolAccounts.mint,
+      payer.publicKey,
+    );
+    assert.ok(poolTokens < 0.199);
+    assert.ok(poolTokens > 0.1989);
+    assert.ok(
+      (await getBalanceWithTokenAccount(provider, poolAccounts.solHolder)) ==
+        0.199,
+    );
+    assert.ok(poolState.balances[0].eq(new anchor.BN(99000000)));
+    assert.ok(poolState.balances[1].eq(new anchor.BN(100000000)));
+  });
+
+  it("redeem multi failure", async () => {
     const poolAccounts = await createPool(provider, payer);
     await mintInitial(poolAccounts);
     try {
       await tapioSolProgram.methods
-        .swap(0, 1, new anchor.BN(1000000), new anchor.BN(1000000000))
+        .redeemMulti(
+          [new anchor.BN(1000000), new anchor.BN(0)],
+          new anchor.BN(1000000),
+        )
         .accounts({
           payer: payer.publicKey,
           jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
@@ -464,51 +861,687 @@ describe("tapio-sol", () => {
           ),
           solUserAccount: payer.publicKey,
           jitoSolMintAccount: poolAccounts.jitoSol,
-          stakePoolAccount: testState,
+          stakePoolAccount: poolAccounts.stakePool,
         })
         .rpc();
       assert.ok(false);
     } catch (_err) {
       assert.isTrue(_err instanceof AnchorError);
       const err: AnchorError = _err;
-      const errMsg = "Swap below minimum.";
+      const errMsg = "Redeem above maximum.";
       assert.strictEqual(err.error.errorMessage, errMsg);
-      assert.strictEqual(err.error.errorCode.number, 6005);
+      assert.strictEqual(err.error.errorCode.number, 6012);
     }
+  });
 
-    try {
-      await tapioSolProgram.methods
-        .swap(1, 0, new anchor.BN(1500000), new anchor.BN(1000000000))
-        .accounts({
-          payer: payer.publicKey,
-          jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
-            poolAccounts.jitoSol,
-            payer.publicKey,
-          ),
-          solUserAccount: payer.publicKey,
-          jitoSolMintAccount: poolAccounts.jitoSol,
-          stakePoolAccount: testState,
-        })
+  it("quote mint success", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    const quote = await tapioSolProgram.methods
+      .quoteMint([new anchor.BN(100000000), new anchor.BN(100000000)])
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+        stakePoolAccount: poolAccounts.stakePool,
+      })
+      .view();
+    assert.ok(quote.mintAmount.eq(new anchor.BN(200000000)));
+    assert.ok(quote.totalSupply.eq(new anchor.BN(200000000)));
+    assert.ok(quote.iterations > 0);
+    const poolState = await tapioSolProgram.account.poolState.fetch(
+      poolAccounts.state,
+    );
+    assert.ok(poolState.totalSupply.eq(new anchor.BN(0)));
+  });
+
+  it("quote swap matches swap", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await mintInitial(poolAccounts);
+    const quote = await tapioSolProgram.methods
+      .quoteSwap(0, 1, new anchor.BN(1000000))
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+        stakePoolAccount: poolAccounts.stakePool,
+      })
+      .view();
+    await tapioSolProgram.methods
+      .swap(0, 1, new anchor.BN(1000000), new anchor.BN(0))
+      .accounts({
+        payer: payer.publicKey,
+        jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
+          poolAccounts.jitoSol,
+          payer.publicKey,
+        ),
+        solUserAccount: payer.publicKey,
+        jitoSolMintAccount: poolAccounts.jitoSol,
+        stakePoolAccount: poolAccounts.stakePool,
+      })
+      .rpc();
+    const poolState = await tapioSolProgram.account.poolState.fetch(
+      poolAccounts.state,
+    );
+    assert.ok(poolState.totalSupply.eq(quote.totalSupply));
+    assert.ok(poolState.balances[0].eq(quote.balanceI));
+    assert.ok(poolState.balances[1].eq(quote.balanceJ));
+    assert.ok(quote.iterations > 0);
+  });
+
+  it("quote redeem proportion success", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await mintInitial(poolAccounts);
+    const quote = await tapioSolProgram.methods
+      .quoteRedeemProportion(new anchor.BN(2000000))
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+        stakePoolAccount: poolAccounts.stakePool,
+      })
+      .view();
+    assert.ok(quote.amounts[0].eq(new anchor.BN(997000)));
+    assert.ok(quote.amounts[1].eq(new anchor.BN(997000)));
+  });
+
+  it("swap compute units", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await mintInitial(poolAccounts);
+    const simulation = await tapioSolProgram.methods
+      .swap(0, 1, new anchor.BN(1000000), new anchor.BN(0))
+      .accounts({
+        payer: payer.publicKey,
+        jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
+          poolAccounts.jitoSol,
+          payer.publicKey,
+        ),
+        solUserAccount: payer.publicKey,
+        jitoSolMintAccount: poolAccounts.jitoSol,
+        stakePoolAccount: poolAccounts.stakePool,
+      })
+      .simulate();
+    const consumed = simulation.raw
+      .map((log) =>
+        log.match(
+          `Program ${tapioSolProgram.programId} consumed (\\d+) of`,
+        ),
+      )
+      .find((match) => match !== null);
+    assert.ok(consumed);
+    // the 256-bit invariant math has to fit the default budget of one instruction
+    assert.ok(Number(consumed[1]) < 200000);
+  });
+
+  it("get virtual price success", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await mintInitial(poolAccounts);
+    let virtualPrice = await tapioSolProgram.methods
+      .getVirtualPrice()
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+        stakePoolAccount: poolAccounts.stakePool,
+      })
+      .view();
+    assert.ok(virtualPrice.eq(new anchor.BN(1000000000)));
+
+    await tapioSolProgram.methods
+      .swap(0, 1, new anchor.BN(1000000), new anchor.BN(0))
+      .accounts({
+        payer: payer.publicKey,
+        jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
+          poolAccounts.jitoSol,
+          payer.publicKey,
+        ),
+        solUserAccount: payer.publicKey,
+        jitoSolMintAccount: poolAccounts.jitoSol,
+        stakePoolAccount: poolAccounts.stakePool,
+      })
+      .rpc();
+    virtualPrice = await tapioSolProgram.methods
+      .getVirtualPrice()
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+        stakePoolAccount: poolAccounts.stakePool,
+      })
+      .view();
+    assert.ok(virtualPrice.gte(new anchor.BN(1000000000)));
+  });
+
+  it("refresh rate success", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await mintInitial(poolAccounts);
+    const [rateSnapshot] = PublicKey.findProgramAddressSync(
+      [Buffer.from("rate"), poolAccounts.jitoSol.toBuffer()],
+      tapioSolProgram.programId,
+    );
+    let snapshot = await tapioSolProgram.account.rateSnapshot.fetch(
+      rateSnapshot,
+    );
+    assert.ok(snapshot.totalSupply.eq(new anchor.BN(200000000)));
+    assert.ok(snapshot.totalSolValue.eq(new anchor.BN(200000000)));
+
+    await tapioSolProgram.methods
+      .refreshRate()
+      .accounts({
+        quote: {
+          jitoSolMintAccount: poolAccounts.jitoSol,
+          stakePoolAccount: poolAccounts.stakePool,
+        },
+      })
+      .rpc();
+    const { epoch } = await provider.connection.getEpochInfo();
+    const accountInfo = await provider.connection.getAccountInfo(rateSnapshot);
+    assert.strictEqual(accountInfo.data.length, 282);
+    assert.ok(accountInfo.owner.equals(tapioSolProgram.programId));
+    const readU64 = (offset: number) =>
+      new anchor.BN(accountInfo.data.subarray(offset, offset + 8), "le");
+    assert.ok(readU64(258).eq(new anchor.BN(200000000)));
+    assert.ok(readU64(266).eq(new anchor.BN(200000000)));
+    assert.ok(readU64(274).eq(new anchor.BN(epoch)));
+    snapshot = await tapioSolProgram.account.rateSnapshot.fetch(rateSnapshot);
+    assert.ok(snapshot.lastUpdateEpoch.eq(new anchor.BN(epoch)));
+  });
+
+  it("mint failure", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    try {
+      await tapioSolProgram.methods
+        .mint(
+          [new anchor.BN(100000000), new anchor.BN(100000000)],
+          new anchor.BN(1000000000),
+        )
+        .accounts({
+          payer: payer.publicKey,
+          jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
+            poolAccounts.jitoSol,
+            payer.publicKey,
+          ),
+          solUserAccount: payer.publicKey,
+          jitoSolMintAccount: poolAccounts.jitoSol,
+          stakePoolAccount: poolAccounts.stakePool,
+        })
+        .rpc();
+      assert.ok(false);
+    } catch (_err) {
+      assert.isTrue(_err instanceof AnchorError);
+      const err: AnchorError = _err;
+      const errMsg = "Mint below minimum.";
+      assert.strictEqual(err.error.errorMessage, errMsg);
+      assert.strictEqual(err.error.errorCode.number, 6004);
+    }
+  });
+
+  it("swap failure", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await mintInitial(poolAccounts);
+    try {
+      await tapioSolProgram.methods
+        .swap(0, 1, new anchor.BN(1000000), new anchor.BN(1000000000))
+        .accounts({
+          payer: payer.publicKey,
+          jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
+            poolAccounts.jitoSol,
+            payer.publicKey,
+          ),
+          solUserAccount: payer.publicKey,
+          jitoSolMintAccount: poolAccounts.jitoSol,
+          stakePoolAccount: poolAccounts.stakePool,
+        })
+        .rpc();
+      assert.ok(false);
+    } catch (_err) {
+      assert.isTrue(_err instanceof AnchorError);
+      const err: AnchorError = _err;
+      const errMsg = "Swap below minimum.";
+      assert.strictEqual(err.error.errorMessage, errMsg);
+      assert.strictEqual(err.error.errorCode.number, 6005);
+    }
+
+    try {
+      await tapioSolProgram.methods
+        .swap(1, 0, new anchor.BN(1500000), new anchor.BN(1000000000))
+        .accounts({
+          payer: payer.publicKey,
+          jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
+            poolAccounts.jitoSol,
+            payer.publicKey,
+          ),
+          solUserAccount: payer.publicKey,
+          jitoSolMintAccount: poolAccounts.jitoSol,
+          stakePoolAccount: poolAccounts.stakePool,
+        })
+        .rpc();
+      assert.ok(false);
+    } catch (_err) {
+      assert.isTrue(_err instanceof AnchorError);
+      const err: AnchorError = _err;
+      const errMsg = "Swap below minimum.";
+      assert.strictEqual(err.error.errorMessage, errMsg);
+      assert.strictEqual(err.error.errorCode.number, 6005);
+    }
+  });
+
+  it("redeem failure", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await mintInitial(poolAccounts);
+    try {
+      await tapioSolProgram.methods
+        .redeemProportion(new anchor.BN(1000000), [
+          new anchor.BN(1000000000),
+          new anchor.BN(1000000000),
+        ])
+        .accounts({
+          payer: payer.publicKey,
+          jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
+            poolAccounts.jitoSol,
+            payer.publicKey,
+          ),
+          solUserAccount: payer.publicKey,
+          jitoSolMintAccount: poolAccounts.jitoSol,
+          stakePoolAccount: poolAccounts.stakePool,
+        })
+        .rpc();
+      assert.ok(false);
+    } catch (_err) {
+      assert.isTrue(_err instanceof AnchorError);
+      const err: AnchorError = _err;
+      const errMsg = "Redeem below minimum.";
+      assert.strictEqual(err.error.errorMessage, errMsg);
+      assert.strictEqual(err.error.errorCode.number, 6006);
+    }
+
+    try {
+      await tapioSolProgram.methods
+        .redeemSingle(new anchor.BN(1000000), 0, new anchor.BN(1000000000))
+        .accounts({
+          payer: payer.publicKey,
+          jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
+            poolAccounts.jitoSol,
+            payer.publicKey,
+          ),
+          solUserAccount: payer.publicKey,
+          jitoSolMintAccount: poolAccounts.jitoSol,
+          stakePoolAccount: poolAccounts.stakePool,
+        })
+        .rpc();
+      assert.ok(false);
+    } catch (_err) {
+      assert.isTrue(_err instanceof AnchorError);
+      const err: AnchorError = _err;
+      const errMsg = "Redeem below minimum.";
+      assert.strictEqual(err.error.errorMessage, errMsg);
+      assert.strictEqual(err.error.errorCode.number, 6006);
+    }
+
+    try {
+      await tapioSolProgram.methods
+        .redeemSingle(new anchor.BN(1000000), 1, new anchor.BN(1000000000))
+        .accounts({
+          payer: payer.publicKey,
+          jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
+            poolAccounts.jitoSol,
+            payer.publicKey,
+          ),
+          solUserAccount: payer.publicKey,
+          jitoSolMintAccount: poolAccounts.jitoSol,
+          stakePoolAccount: poolAccounts.stakePool,
+        })
+        .rpc();
+      assert.ok(false);
+    } catch (_err) {
+      assert.isTrue(_err instanceof AnchorError);
+      const err: AnchorError = _err;
+      const errMsg = "Redeem below minimum.";
+      assert.strictEqual(err.error.errorMessage, errMsg);
+      assert.strictEqual(err.error.errorCode.number, 6006);
+    }
+  });
+
+  it("setFees success", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await tapioSolProgram.methods
+      .setFees(
+        new anchor.BN(10000000),
+        new anchor.BN(20000000),
+        new anchor.BN(40000000),
+      )
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+      })
+      .rpc();
+    const poolState = await tapioSolProgram.account.poolState.fetch(
+      poolAccounts.state,
+    );
+    assert.ok(poolState.mintFee.eq(new anchor.BN(10000000)));
+    assert.ok(poolState.swapFee.eq(new anchor.BN(20000000)));
+    assert.ok(poolState.redeemFee.eq(new anchor.BN(40000000)));
+  });
+
+  it("setFees above cap", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    try {
+      await tapioSolProgram.methods
+        .setFees(new anchor.BN(0), new anchor.BN(500000001), new anchor.BN(0))
+        .accounts({
+          jitoSolMintAccount: poolAccounts.jitoSol,
+        })
+        .rpc();
+      assert.ok(false);
+    } catch (_err) {
+      assert.isTrue(_err instanceof AnchorError);
+      const err: AnchorError = _err;
+      const errMsg = "Argument failed validation.";
+      assert.strictEqual(err.error.errorMessage, errMsg);
+      assert.strictEqual(err.error.errorCode.number, 6002);
+    }
+  });
+
+  it("setFees unauthorized", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    try {
+      await tapioSolProgram.methods
+        .setFees(new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          payer: tokenPayer.publicKey,
+          jitoSolMintAccount: poolAccounts.jitoSol,
+        })
+        .signers([tokenPayer])
+        .rpc();
+      assert.ok(false);
+    } catch (_err) {
+      assert.isTrue(_err instanceof AnchorError);
+      const err: AnchorError = _err;
+      const errMsg = "Unauthorized signer.";
+      assert.strictEqual(err.error.errorMessage, errMsg);
+      assert.strictEqual(err.error.errorCode.number, 6008);
+    }
+  });
+
+  it("protocol fee success", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await mintInitial(poolAccounts);
+    const feeRecipient = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      tokenPayer,
+      poolAccounts.mint,
+      tokenPayer.publicKey,
+    );
+    await tapioSolProgram.methods
+      .setFeeRecipient(new anchor.BN(5000000000))
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+        feeRecipientAccount: feeRecipient.address,
+      })
+      .rpc();
+    await tapioSolProgram.methods
+      .redeemProportion(new anchor.BN(1000000), [
+        new anchor.BN(0),
+        new anchor.BN(0),
+      ])
+      .accounts({
+        payer: payer.publicKey,
+        jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
+          poolAccounts.jitoSol,
+          payer.publicKey,
+        ),
+        solUserAccount: payer.publicKey,
+        jitoSolMintAccount: poolAccounts.jitoSol,
+        stakePoolAccount: poolAccounts.stakePool,
+        feeRecipientAccount: feeRecipient.address,
+      })
+      .rpc();
+    const poolState = await tapioSolProgram.account.poolState.fetch(
+      poolAccounts.state,
+    );
+    assert.ok(poolState.feeRecipient.equals(feeRecipient.address));
+    assert.ok(
+      (await getTokenBalance(provider, poolAccounts.mint, payer.publicKey)) ==
+        0.199,
+    );
+    assert.ok(
+      (await getTokenBalanceWithTokenAccount(
+        provider,
+        feeRecipient.address,
+      )) == 0.0000015,
+    );
+    assert.ok(poolState.totalSupply.eq(new anchor.BN(199003000)));
+  });
+
+  it("protocol fee missing recipient", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await mintInitial(poolAccounts);
+    const feeRecipient = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      tokenPayer,
+      poolAccounts.mint,
+      tokenPayer.publicKey,
+    );
+    await tapioSolProgram.methods
+      .setFeeRecipient(new anchor.BN(5000000000))
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+        feeRecipientAccount: feeRecipient.address,
+      })
+      .rpc();
+    try {
+      await tapioSolProgram.methods
+        .redeemSingle(new anchor.BN(1000000), 0, new anchor.BN(0))
+        .accounts({
+          payer: payer.publicKey,
+          jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
+            poolAccounts.jitoSol,
+            payer.publicKey,
+          ),
+          solUserAccount: payer.publicKey,
+          jitoSolMintAccount: poolAccounts.jitoSol,
+          stakePoolAccount: poolAccounts.stakePool,
+        })
+        .rpc();
+      assert.ok(false);
+    } catch (_err) {
+      assert.isTrue(_err instanceof AnchorError);
+      const err: AnchorError = _err;
+      const errMsg = "Token failed validation.";
+      assert.strictEqual(err.error.errorMessage, errMsg);
+      assert.strictEqual(err.error.errorCode.number, 6003);
+    }
+  });
+
+  it("distribute yield success", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await mintInitial(poolAccounts);
+    // jitoSOL appreciates from 1.1 to 1.2 SOL, recorded by the next redeem
+    await stakeTestProgram.methods
+      .update(new anchor.BN(12), new anchor.BN(10))
+      .accounts({
+        stakePoolAccount: poolAccounts.stakePool,
+      })
+      .rpc();
+    await tapioSolProgram.methods
+      .redeemProportion(new anchor.BN(1000000), [
+        new anchor.BN(0),
+        new anchor.BN(0),
+      ])
+      .accounts({
+        payer: payer.publicKey,
+        jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
+          poolAccounts.jitoSol,
+          payer.publicKey,
+        ),
+        solUserAccount: payer.publicKey,
+        jitoSolMintAccount: poolAccounts.jitoSol,
+        stakePoolAccount: poolAccounts.stakePool,
+      })
+      .rpc();
+    const pendingYield = (
+      await tapioSolProgram.account.poolState.fetch(poolAccounts.state)
+    ).pendingYield;
+    assert.ok(pendingYield.gt(new anchor.BN(0)));
+    const yieldRecipient = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      tokenPayer,
+      poolAccounts.mint,
+      tokenPayer.publicKey,
+    );
+    await tapioSolProgram.methods
+      .setYieldRecipient(new anchor.BN(0))
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+        yieldRecipientAccount: yieldRecipient.address,
+      })
+      .rpc();
+    await tapioSolProgram.methods
+      .distributeYield()
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+        stakePoolAccount: poolAccounts.stakePool,
+        yieldRecipientAccount: yieldRecipient.address,
+      })
+      .rpc();
+    const poolState = await tapioSolProgram.account.poolState.fetch(
+      poolAccounts.state,
+    );
+    assert.ok(poolState.yieldRecipient.equals(yieldRecipient.address));
+    // the redeem fee kept by the pool raised the worth of a pool token above one SOL, the
+    // yield is minted at that worth
+    const minted = await getTokenBalanceWithTokenAccount(
+      provider,
+      yieldRecipient.address,
+    );
+    assert.ok(minted > 0);
+    assert.ok(minted < pendingYield.toNumber() / LAMPORTS_PER_SOL);
+    assert.ok(poolState.pendingYield.eq(new anchor.BN(0)));
+  });
+
+  it("fee manager setFees success", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await tapioSolProgram.methods
+      .setRole({ feeManager: {} }, tokenPayer.publicKey)
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+      })
+      .rpc();
+    await tapioSolProgram.methods
+      .setFees(new anchor.BN(0), new anchor.BN(30000000), new anchor.BN(0))
+      .accounts({
+        payer: tokenPayer.publicKey,
+        jitoSolMintAccount: poolAccounts.jitoSol,
+      })
+      .signers([tokenPayer])
+      .rpc();
+    const poolState = await tapioSolProgram.account.poolState.fetch(
+      poolAccounts.state,
+    );
+    assert.ok(poolState.feeManager.equals(tokenPayer.publicKey));
+    assert.ok(poolState.swapFee.eq(new anchor.BN(30000000)));
+  });
+
+  it("fee manager modifyA unauthorized", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await tapioSolProgram.methods
+      .setRole({ feeManager: {} }, tokenPayer.publicKey)
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+      })
+      .rpc();
+    try {
+      await tapioSolProgram.methods
+        .modifyA(new anchor.BN(10000), futureATime)
+        .accounts({
+          payer: tokenPayer.publicKey,
+          jitoSolMintAccount: poolAccounts.jitoSol,
+        })
+        .signers([tokenPayer])
         .rpc();
       assert.ok(false);
     } catch (_err) {
       assert.isTrue(_err instanceof AnchorError);
       const err: AnchorError = _err;
-      const errMsg = "Swap below minimum.";
+      const errMsg = "Unauthorized signer.";
       assert.strictEqual(err.error.errorMessage, errMsg);
-      assert.strictEqual(err.error.errorCode.number, 6005);
+      assert.strictEqual(err.error.errorCode.number, 6008);
     }
   });
 
-  it("redeem failure", async () => {
+  it("setRole unauthorized", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    try {
+      await tapioSolProgram.methods
+        .setRole({ curveManager: {} }, tokenPayer.publicKey)
+        .accounts({
+          payer: tokenPayer.publicKey,
+          jitoSolMintAccount: poolAccounts.jitoSol,
+        })
+        .signers([tokenPayer])
+        .rpc();
+      assert.ok(false);
+    } catch (_err) {
+      assert.isTrue(_err instanceof AnchorError);
+      const err: AnchorError = _err;
+      const errMsg = "Unauthorized signer.";
+      assert.strictEqual(err.error.errorMessage, errMsg);
+      assert.strictEqual(err.error.errorCode.number, 6008);
+    }
+  });
+
+  it("guardian pause mint", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await tapioSolProgram.methods
+      .setRole({ guardian: {} }, tokenPayer.publicKey)
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+      })
+      .rpc();
+    await tapioSolProgram.methods
+      .setPaused(1)
+      .accounts({
+        payer: tokenPayer.publicKey,
+        jitoSolMintAccount: poolAccounts.jitoSol,
+      })
+      .signers([tokenPayer])
+      .rpc();
+    try {
+      await mintInitial(poolAccounts);
+      assert.ok(false);
+    } catch (_err) {
+      assert.isTrue(_err instanceof AnchorError);
+      const err: AnchorError = _err;
+      const errMsg = "Operation paused.";
+      assert.strictEqual(err.error.errorMessage, errMsg);
+      assert.strictEqual(err.error.errorCode.number, 6009);
+    }
+    try {
+      await tapioSolProgram.methods
+        .setPaused(0)
+        .accounts({
+          payer: tokenPayer.publicKey,
+          jitoSolMintAccount: poolAccounts.jitoSol,
+        })
+        .signers([tokenPayer])
+        .rpc();
+      assert.ok(false);
+    } catch (_err) {
+      assert.isTrue(_err instanceof AnchorError);
+      const err: AnchorError = _err;
+      const errMsg = "Unauthorized signer.";
+      assert.strictEqual(err.error.errorMessage, errMsg);
+      assert.strictEqual(err.error.errorCode.number, 6008);
+    }
+    await tapioSolProgram.methods
+      .setPaused(0)
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+      })
+      .rpc();
+    await mintInitial(poolAccounts);
+  });
+
+  it("emergency redeem proportion only", async () => {
     const poolAccounts = await createPool(provider, payer);
     await mintInitial(poolAccounts);
+    await tapioSolProgram.methods
+      .setEmergency(true)
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+      })
+      .rpc();
     try {
       await tapioSolProgram.methods
-        .redeemProportion(new anchor.BN(1000000), [
-          new anchor.BN(1000000000),
-          new anchor.BN(1000000000),
-        ])
+        .swap(0, 1, new anchor.BN(1000000), new anchor.BN(0))
         .accounts({
           payer: payer.publicKey,
           jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
@@ -517,70 +1550,157 @@ describe("tapio-sol", () => {
           ),
           solUserAccount: payer.publicKey,
           jitoSolMintAccount: poolAccounts.jitoSol,
-          stakePoolAccount: testState,
+          stakePoolAccount: poolAccounts.stakePool,
         })
         .rpc();
       assert.ok(false);
     } catch (_err) {
       assert.isTrue(_err instanceof AnchorError);
       const err: AnchorError = _err;
-      const errMsg = "Redeem below minimum.";
+      const errMsg = "Operation paused.";
       assert.strictEqual(err.error.errorMessage, errMsg);
-      assert.strictEqual(err.error.errorCode.number, 6006);
+      assert.strictEqual(err.error.errorCode.number, 6009);
     }
+    await tapioSolProgram.methods
+      .redeemProportion(new anchor.BN(1000000), [
+        new anchor.BN(0),
+        new anchor.BN(0),
+      ])
+      .accounts({
+        payer: payer.publicKey,
+        jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
+          poolAccounts.jitoSol,
+          payer.publicKey,
+        ),
+        solUserAccount: payer.publicKey,
+        jitoSolMintAccount: poolAccounts.jitoSol,
+        stakePoolAccount: poolAccounts.stakePool,
+      })
+      .rpc();
+    const poolState = await tapioSolProgram.account.poolState.fetch(
+      poolAccounts.state,
+    );
+    assert.ok(poolState.emergency);
+    assert.ok(poolState.totalSupply.eq(new anchor.BN(199003000)));
+  });
+
+  it("setRateStaleness success", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await tapioSolProgram.methods
+      .setRateStaleness(new anchor.BN(2))
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+      })
+      .rpc();
+    const poolState = await tapioSolProgram.account.poolState.fetch(
+      poolAccounts.state,
+    );
+    assert.ok(poolState.maxRateStaleness.eq(new anchor.BN(2)));
+  });
 
+  it("setRateStaleness unauthorized", async () => {
+    const poolAccounts = await createPool(provider, payer);
     try {
       await tapioSolProgram.methods
-        .redeemSingle(new anchor.BN(1000000), 0, new anchor.BN(1000000000))
+        .setRateStaleness(new anchor.BN(2))
         .accounts({
-          payer: payer.publicKey,
-          jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
-            poolAccounts.jitoSol,
-            payer.publicKey,
-          ),
-          solUserAccount: payer.publicKey,
+          payer: tokenPayer.publicKey,
           jitoSolMintAccount: poolAccounts.jitoSol,
-          stakePoolAccount: testState,
         })
+        .signers([tokenPayer])
         .rpc();
       assert.ok(false);
     } catch (_err) {
       assert.isTrue(_err instanceof AnchorError);
       const err: AnchorError = _err;
-      const errMsg = "Redeem below minimum.";
+      const errMsg = "Unauthorized signer.";
       assert.strictEqual(err.error.errorMessage, errMsg);
-      assert.strictEqual(err.error.errorCode.number, 6006);
+      assert.strictEqual(err.error.errorCode.number, 6008);
     }
+  });
+
+  it("authority transfer success", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await tapioSolProgram.methods
+      .proposeAuthority(tokenPayer.publicKey)
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+      })
+      .rpc();
+    let poolState = await tapioSolProgram.account.poolState.fetch(
+      poolAccounts.state,
+    );
+    assert.ok(poolState.authority.equals(payer.publicKey));
+    assert.ok(poolState.pendingAuthority.equals(tokenPayer.publicKey));
 
+    await tapioSolProgram.methods
+      .acceptAuthority()
+      .accounts({
+        payer: tokenPayer.publicKey,
+        jitoSolMintAccount: poolAccounts.jitoSol,
+      })
+      .signers([tokenPayer])
+      .rpc();
+    poolState = await tapioSolProgram.account.poolState.fetch(
+      poolAccounts.state,
+    );
+    assert.ok(poolState.authority.equals(tokenPayer.publicKey));
+    assert.isNull(poolState.pendingAuthority);
+  });
+
+  it("accept authority unauthorized", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await tapioSolProgram.methods
+      .proposeAuthority(anchor.web3.Keypair.generate().publicKey)
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+      })
+      .rpc();
     try {
       await tapioSolProgram.methods
-        .redeemSingle(new anchor.BN(1000000), 1, new anchor.BN(1000000000))
+        .acceptAuthority()
         .accounts({
-          payer: payer.publicKey,
-          jitoSolUserTokenAccount: getAssociatedTokenAddressSync(
-            poolAccounts.jitoSol,
-            payer.publicKey,
-          ),
-          solUserAccount: payer.publicKey,
+          payer: tokenPayer.publicKey,
           jitoSolMintAccount: poolAccounts.jitoSol,
-          stakePoolAccount: testState,
         })
+        .signers([tokenPayer])
         .rpc();
       assert.ok(false);
     } catch (_err) {
       assert.isTrue(_err instanceof AnchorError);
       const err: AnchorError = _err;
-      const errMsg = "Redeem below minimum.";
+      const errMsg = "Unauthorized signer.";
       assert.strictEqual(err.error.errorMessage, errMsg);
-      assert.strictEqual(err.error.errorCode.number, 6006);
+      assert.strictEqual(err.error.errorCode.number, 6008);
     }
   });
 
-  it("modifyA too low", async () => {
+  it("cancel authority transfer success", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await tapioSolProgram.methods
+      .proposeAuthority(tokenPayer.publicKey)
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+      })
+      .rpc();
+    await tapioSolProgram.methods
+      .cancelAuthorityTransfer()
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+      })
+      .rpc();
+    const poolState = await tapioSolProgram.account.poolState.fetch(
+      poolAccounts.state,
+    );
+    assert.ok(poolState.authority.equals(payer.publicKey));
+    assert.isNull(poolState.pendingAuthority);
+  });
+
+  it("modifyA change too large", async () => {
     const poolAccounts = await createPool(provider, payer);
     try {
       await tapioSolProgram.methods
-        .modifyA(new anchor.BN(100), new anchor.BN(10000))
+        .modifyA(new anchor.BN(99), futureATime)
         .accounts({
           jitoSolMintAccount: poolAccounts.jitoSol,
         })
@@ -599,7 +1719,7 @@ describe("tapio-sol", () => {
     const poolAccounts = await createPool(provider, payer);
     try {
       await tapioSolProgram.methods
-        .modifyA(new anchor.BN(10000), new anchor.BN(10000))
+        .modifyA(new anchor.BN(10000), futureATime)
         .accounts({
           payer: tokenPayer.publicKey,
           jitoSolMintAccount: poolAccounts.jitoSol,
@@ -619,7 +1739,7 @@ describe("tapio-sol", () => {
   it("modifyA success", async () => {
     const poolAccounts = await createPool(provider, payer);
     await tapioSolProgram.methods
-      .modifyA(new anchor.BN(10000), new anchor.BN(10000))
+      .modifyA(new anchor.BN(10000), futureATime)
       .accounts({
         jitoSolMintAccount: poolAccounts.jitoSol,
       })
@@ -628,17 +1748,98 @@ describe("tapio-sol", () => {
       poolAccounts.state,
     );
     assert.ok(poolState.a.eq(new anchor.BN(1000)));
-    assert.ok(poolState.aBlock.eq(new anchor.BN(0)));
+    assert.ok(poolState.aTime.lt(futureATime));
     assert.ok(poolState.futureA.eq(new anchor.BN(10000)));
-    assert.ok(poolState.futureABlock.eq(new anchor.BN(10000)));
+    assert.ok(poolState.futureATime.eq(futureATime));
+  });
+
+  it("modifyA decrease success", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await tapioSolProgram.methods
+      .modifyA(new anchor.BN(100), futureATime)
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+      })
+      .rpc();
+    const poolState = await tapioSolProgram.account.poolState.fetch(
+      poolAccounts.state,
+    );
+    assert.ok(poolState.a.eq(new anchor.BN(1000)));
+    assert.ok(poolState.futureA.eq(new anchor.BN(100)));
+    assert.ok(poolState.futureATime.eq(futureATime));
+  });
+
+  it("stopRampA success", async () => {
+    const poolAccounts = await createPool(provider, payer);
+    await tapioSolProgram.methods
+      .modifyA(new anchor.BN(10000), futureATime)
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+      })
+      .rpc();
+    await tapioSolProgram.methods
+      .stopRampA()
+      .accounts({
+        jitoSolMintAccount: poolAccounts.jitoSol,
+      })
+      .rpc();
+    const poolState = await tapioSolProgram.account.poolState.fetch(
+      poolAccounts.state,
+    );
+    assert.ok(poolState.a.eq(new anchor.BN(1000)));
+    assert.ok(poolState.futureA.eq(new anchor.BN(1000)));
+    assert.ok(poolState.futureATime.eq(poolState.aTime));
   });
 
   async function createStakePool() {
     await getSOL(provider, mintAuthSC);
     await getSOL(provider, tokenPayer);
+  }
+
+  async function createTestMarinadeState(
+    msolMint: PublicKey,
+  ): Promise<PublicKey> {
+    const [state] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("test"), msolMint.toBytes()],
+      marinadeTestProgram.programId,
+    );
+    await marinadeTestProgram.methods
+      .initialize(new anchor.BN(5153960755))
+      .accounts({
+        msolMint,
+      })
+      .rpc();
+    return state;
+  }
+
+  async function createTestLidoState(
+    stSolMint: PublicKey,
+  ): Promise<PublicKey> {
+    const [state] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("test"), stSolMint.toBytes()],
+      lidoTestProgram.programId,
+    );
+    await lidoTestProgram.methods
+      .initialize(new anchor.BN(12), new anchor.BN(10))
+      .accounts({
+        stSolMint,
+      })
+      .rpc();
+    return state;
+  }
+
+  async function createTestStakePool(poolMint: PublicKey): Promise<PublicKey> {
+    const [stakePool] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("test"), poolMint.toBytes()],
+      stakeTestProgram.programId,
+    );
     await stakeTestProgram.methods
       .initialize(new anchor.BN(11), new anchor.BN(10))
+      .accounts({
+        poolMint,
+      })
       .rpc();
+    return stakePool;
   }
 
   async function createPool(
@@ -672,6 +1873,7 @@ describe("tapio-sol", () => {
       [Buffer.from(HOLDER_SEED), Buffer.from(HOLDER_SOL), jitoSol.toBytes()],
       tapioSolProgram.programId,
     );
+    const stakePool = await createTestStakePool(jitoSol);
 
     await tapioSolProgram.methods
       .initializePool(
@@ -682,7 +1884,7 @@ describe("tapio-sol", () => {
       )
       .accounts({
         jitoSolMintAccount: jitoSol,
-        stakePoolAccount: testState,
+        stakePoolAccount: stakePool,
       })
       .rpc();
     await tapioSolProgram.methods
@@ -691,7 +1893,14 @@ describe("tapio-sol", () => {
         jitoSolMintAccount: jitoSol,
       })
       .rpc();
-    return new CreatePoolInfo(jitoSol, jitoSolHolder, mint, state, solHolder);
+    return new CreatePoolInfo(
+      jitoSol,
+      jitoSolHolder,
+      mint,
+      state,
+      solHolder,
+      stakePool,
+    );
   }
 
   async function mintInitial(poolAccounts: CreatePoolInfo) {
@@ -708,7 +1917,7 @@ describe("tapio-sol", () => {
         ),
         solUserAccount: payer.publicKey,
         jitoSolMintAccount: poolAccounts.jitoSol,
-        stakePoolAccount: testState,
+        stakePoolAccount: poolAccounts.stakePool,
       })
       .rpc();
   }
@@ -719,6 +1928,7 @@ describe("tapio-sol", () => {
     mint: PublicKey;
     state: PublicKey;
     solHolder: PublicKey;
+    stakePool: PublicKey;
 
     constructor(
       jitoSol: PublicKey,
@@ -726,12 +1936,14 @@ describe("tapio-sol", () => {
       mint: PublicKey,
       state: PublicKey,
       solHolder: PublicKey,
+      stakePool: PublicKey,
     ) {
       this.jitoSol = jitoSol;
       this.jitoSolHolder = jitoSolHolder;
       this.mint = mint;
       this.state = state;
       this.solHolder = solHolder;
+      this.stakePool = stakePool;
     }
   }
 });